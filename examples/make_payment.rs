@@ -2,6 +2,7 @@ use fapshi_rs::{
     api::{balance::BalanceApi, payment::PaymentApi, transaction::TransactionApi},
     client::FapshiClient,
     models::{DirectPaymentRequest, PaymentRequest},
+    safety::Environment,
 };
 use std::env;
 
@@ -11,7 +12,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv::dotenv().ok();
     let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    let client = FapshiClient::new(&api_user, &api_key, true)?;
+    let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
 
     // Create a payment link
     let payment_request = PaymentRequest {
@@ -1,4 +1,4 @@
-use crate::{client::FapshiClient, error::FapshiError, models::ServiceBalance};
+use crate::{client::FapshiClient, endpoint::Balance, error::FapshiError, models::ServiceBalance};
 
 /// API for retrieving service balance.
 pub struct BalanceApi;
@@ -14,20 +14,22 @@ impl BalanceApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
+    /// ```no_run
     /// use std::env;
-    /// use fapshi_sdk::{FapshiClient, api::balance::BalanceApi};
+    /// use fapshi_rs::{api::balance::BalanceApi, client::FapshiClient, safety::Environment};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let balance = BalanceApi::get_service_balance(&client).unwrap();
     /// println!("Balance: {} {}", balance.balance, balance.currency);
+    /// # Ok(())
+    /// # }
     /// ```
     #[cfg(not(feature = "async"))]
     pub fn get_service_balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
-        let response = client.get("balance")?;
-        let balance: ServiceBalance = serde_json::from_str(&response)?;
-        Ok(balance)
+        client.call(&Balance)
     }
 
     /// Retrieves the current service account balance asynchronously.
@@ -40,14 +42,14 @@ impl BalanceApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
+    /// ```no_run
     /// use std::env;
-    /// use fapshi_sdk::{FapshiClient, api::balance::BalanceApi};
+    /// use fapshi_rs::{api::balance::BalanceApi, client::FapshiClient, safety::Environment};
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let balance = BalanceApi::get_service_balance(&client).await.unwrap();
     /// println!("Balance: {} {}", balance.balance, balance.currency);
     /// # Ok(())
@@ -55,8 +57,6 @@ impl BalanceApi {
     /// ```
     #[cfg(feature = "async")]
     pub async fn get_service_balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
-        let response = client.get("balance").await?;
-        let balance: ServiceBalance = serde_json::from_str(&response)?;
-        Ok(balance)
+        client.call(&Balance).await
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 /// API modules for interacting with Fapshi endpoints.
 pub mod balance;
 pub mod payment;
+pub mod payout;
 pub mod transaction;
 pub mod webhook;
@@ -1,5 +1,6 @@
 use crate::{
     client::FapshiClient,
+    endpoint::{DirectPay, InitiatePay},
     error::FapshiError,
     models::{DirectPaymentRequest, DirectPaymentResponse, PaymentRequest, PaymentResponse},
 };
@@ -19,33 +20,39 @@ impl PaymentApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
+    /// ```no_run
     /// use std::env;
-    /// use fapshi_sdk::{FapshiClient, models::PaymentRequest, api::payment::PaymentApi};
+    /// use fapshi_rs::{
+    ///     api::payment::PaymentApi,
+    ///     client::FapshiClient,
+    ///     models::PaymentRequest,
+    ///     safety::Environment,
+    /// };
     ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let request = PaymentRequest {
     ///     amount: 100.0,
-    ///     currency: "USD".to_string(),
-    ///     description: "Test payment".to_string(),
-    ///     customer_email: Some("test@example.com".to_string()),
+    ///     email: Some("test@example.com".to_string()),
+    ///     redirect_url: None,
     ///     user_id: None,
+    ///     external_id: None,
+    ///     message: "Test payment".to_string(),
+    ///     card_only: None,
     /// };
     /// let response = PaymentApi::create_payment(&client, &request).unwrap();
     /// println!("Payment link: {}", response.payment_link);
+    /// # Ok(())
+    /// # }
     /// ```
     #[cfg(not(feature = "async"))]
     pub fn create_payment(
         client: &FapshiClient,
         request: &PaymentRequest,
     ) -> Result<PaymentResponse, FapshiError> {
-        let body = serde_json::to_string(request)?;
-        println!("{}", body);
-        let response = client.post("initiate-pay", &body)?;
-        let payment_response: PaymentResponse = serde_json::from_str(&response)?;
-        Ok(payment_response)
+        client.call(&InitiatePay(request))
     }
 
     /// Creates a new payment link asynchronously.
@@ -59,21 +66,28 @@ impl PaymentApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
+    /// ```no_run
     /// use std::env;
-    /// use fapshi_sdk::{FapshiClient, models::PaymentRequest, api::payment::PaymentApi};
+    /// use fapshi_rs::{
+    ///     api::payment::PaymentApi,
+    ///     client::FapshiClient,
+    ///     models::PaymentRequest,
+    ///     safety::Environment,
+    /// };
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let request = PaymentRequest {
     ///     amount: 100.0,
-    ///     currency: "USD".to_string(),
-    ///     description: "Test payment".to_string(),
-    ///     customer_email: Some("test@example.com".to_string()),
+    ///     email: Some("test@example.com".to_string()),
+    ///     redirect_url: None,
     ///     user_id: None,
+    ///     external_id: None,
+    ///     message: "Test payment".to_string(),
+    ///     card_only: None,
     /// };
     /// let response = PaymentApi::create_payment(&client, &request).await.unwrap();
     /// println!("Payment link: {}", response.payment_link);
@@ -85,11 +99,7 @@ impl PaymentApi {
         client: &FapshiClient,
         request: &PaymentRequest,
     ) -> Result<PaymentResponse, FapshiError> {
-        let body = serde_json::to_string(request)?;
-        println!("{}", body);
-        let response = client.post("initiate-pay", &body).await?;
-        let payment_response: PaymentResponse = serde_json::from_str(&response)?;
-        Ok(payment_response)
+        client.call(&InitiatePay(request)).await
     }
 
     /// Initiates a direct payment request to a user's mobile device synchronously.
@@ -103,12 +113,19 @@ impl PaymentApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, models::DirectPaymentRequest, api::payment::PaymentApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::payment::PaymentApi,
+    ///     client::FapshiClient,
+    ///     models::DirectPaymentRequest,
+    ///     safety::Environment,
+    /// };
+    /// use std::env;
     ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let request = DirectPaymentRequest {
     ///     amount: 500.0,
     ///     phone: "654988322".to_string(),
@@ -121,16 +138,15 @@ impl PaymentApi {
     /// };
     /// let response = PaymentApi::initiate_direct_payment(&client, &request).unwrap();
     /// println!("Transaction ID: {}", response.transaction_id);
+    /// # Ok(())
+    /// # }
     /// ```
     #[cfg(not(feature = "async"))]
     pub fn initiate_direct_payment(
         client: &FapshiClient,
         request: &DirectPaymentRequest,
     ) -> Result<DirectPaymentResponse, FapshiError> {
-        let body = serde_json::to_string(request)?;
-        let response = client.post("direct-pay", &body)?;
-        let direct_response: DirectPaymentResponse = serde_json::from_str(&response)?;
-        Ok(direct_response)
+        client.call(&DirectPay(request))
     }
 
     /// Initiates a direct payment request to a user's mobile device asynchronously.
@@ -144,14 +160,20 @@ impl PaymentApi {
     /// or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, models::DirectPaymentRequest, api::payment::PaymentApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::payment::PaymentApi,
+    ///     client::FapshiClient,
+    ///     models::DirectPaymentRequest,
+    ///     safety::Environment,
+    /// };
+    /// use std::env;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let request = DirectPaymentRequest {
     ///     amount: 500.0,
     ///     phone: "654988322".to_string(),
@@ -172,9 +194,6 @@ impl PaymentApi {
         client: &FapshiClient,
         request: &DirectPaymentRequest,
     ) -> Result<DirectPaymentResponse, FapshiError> {
-        let body = serde_json::to_string(request)?;
-        let response = client.post("direct-pay", &body).await?;
-        let direct_response: DirectPaymentResponse = serde_json::from_str(&response)?;
-        Ok(direct_response)
+        client.call(&DirectPay(request)).await
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,73 @@
+use crate::{
+    client::FapshiClient,
+    endpoint::Payout,
+    error::FapshiError,
+    models::{PayoutResponse, Payouts},
+};
+
+/// API for sending money from the service balance to mobile money accounts.
+pub struct PayoutApi;
+
+impl PayoutApi {
+    /// Sends a payout to a mobile money or Orange money account synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `payout` - The `Payouts` request with the amount, recipient phone number and medium.
+    ///
+    /// # Returns
+    /// A `Result` containing the `PayoutResponse` with the transaction ID,
+    /// or a `FapshiError` if the request fails.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::payout::PayoutApi,
+    ///     client::FapshiClient,
+    ///     models::Payouts,
+    ///     safety::Environment,
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+    /// let payout = Payouts {
+    ///     amount: 1000.0,
+    ///     phone_number: "670000000".to_string(),
+    ///     medium: "mobile money".to_string(),
+    ///     name: Some("Wilfried".to_string()),
+    ///     email: None,
+    ///     user_id: None,
+    ///     external_id: Some("refund-1001".to_string()),
+    ///     message: Some("Refund for order 1001".to_string()),
+    /// };
+    /// let response = PayoutApi::send_payout(&client, &payout)?;
+    /// println!("Payout transaction ID: {}", response.transaction_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "async"))]
+    pub fn send_payout(
+        client: &FapshiClient,
+        payout: &Payouts,
+    ) -> Result<PayoutResponse, FapshiError> {
+        client.call(&Payout(payout))
+    }
+
+    /// Sends a payout to a mobile money or Orange money account asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `payout` - The `Payouts` request with the amount, recipient phone number and medium.
+    ///
+    /// # Returns
+    /// A `Result` containing the `PayoutResponse` with the transaction ID,
+    /// or a `FapshiError` if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send_payout(
+        client: &FapshiClient,
+        payout: &Payouts,
+    ) -> Result<PayoutResponse, FapshiError> {
+        client.call(&Payout(payout)).await
+    }
+}
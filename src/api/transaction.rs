@@ -1,7 +1,8 @@
 use crate::{
     client::FapshiClient,
+    endpoint::{ExpirePay, PaymentStatus, SearchTransactions, UserTransactions},
     error::FapshiError,
-    models::{PaymentTransactionResponse, TransactionStatus},
+    models::{TransactionSearchQuery, TransactionStatus, SEARCH_LIMIT_MAX},
 };
 
 /// API for querying and managing transactions.
@@ -21,11 +22,7 @@ impl TransactionApi {
         client: &FapshiClient,
         transaction_id: &str,
     ) -> Result<TransactionStatus, FapshiError> {
-        let endpoint = format!("payment-status/{}", transaction_id);
-        let response = client.get(&endpoint)?;
-        println!("{}", response);
-        let status: TransactionStatus = serde_json::from_str(&response)?;
-        Ok(status)
+        client.call(&PaymentStatus(transaction_id))
     }
 
     /// Retrieves the status of a transaction asynchronously.
@@ -38,12 +35,17 @@ impl TransactionApi {
     /// A `Result` containing the `TransactionStatus` or a `FapshiError` if the request fails.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::transaction::TransactionApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     safety::Environment,
+    /// };
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true)?;
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
     /// let status = TransactionApi::get_status(&client, "trans123").await.unwrap();
     /// println!("Transaction status: {:?}", status);
     /// # Ok(())
@@ -54,11 +56,7 @@ impl TransactionApi {
         client: &FapshiClient,
         transaction_id: &str,
     ) -> Result<TransactionStatus, FapshiError> {
-        let endpoint = format!("payment-status/{}", transaction_id);
-        let response = client.get(&endpoint).await?;
-        println!("{}", response);
-        let status: TransactionStatus = serde_json::from_str(&response)?;
-        Ok(status)
+        client.call(&PaymentStatus(transaction_id)).await
     }
 
     /// Expires a payment transaction to prevent further payments synchronously.
@@ -71,22 +69,26 @@ impl TransactionApi {
     /// A `Result` indicating success or a `FapshiError` if the request fails (e.g., if already expired).
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::transaction::TransactionApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     safety::Environment,
+    /// };
     ///
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true).unwrap();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
     /// TransactionApi::expire_transaction(&client, "trans123").unwrap();
+    /// # Ok(())
+    /// # }
     /// ```
     #[cfg(not(feature = "async"))]
     pub fn expire_transaction(
         client: &FapshiClient,
         transaction_id: &str,
     ) -> Result<(), FapshiError> {
-        let endpoint = "expire-pay";
-        let transaction = serde_json::to_string(&PaymentTransactionResponse {
-            transaction_id: transaction_id.to_string(),
-        })?;
-        client.post(endpoint, &transaction)?;
+        client.call(&ExpirePay::new(transaction_id))?;
         Ok(())
     }
 
@@ -100,12 +102,17 @@ impl TransactionApi {
     /// A `Result` indicating success or a `FapshiError` if the request fails (e.g., if already expired).
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::transaction::TransactionApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     safety::Environment,
+    /// };
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true)?;
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
     /// TransactionApi::expire_transaction(&client, "trans123").await.unwrap();
     /// # Ok(())
     /// # }
@@ -115,11 +122,7 @@ impl TransactionApi {
         client: &FapshiClient,
         transaction_id: &str,
     ) -> Result<(), FapshiError> {
-        let endpoint = "expire-pay";
-        let transaction = serde_json::to_string(&PaymentTransactionResponse {
-            transaction_id: transaction_id.to_string(),
-        })?;
-        client.post(endpoint, &transaction).await?;
+        client.call(&ExpirePay::new(transaction_id)).await?;
         Ok(())
     }
 
@@ -133,27 +136,32 @@ impl TransactionApi {
     /// A `Result` containing a `Vec<TransactionStatus>` with matching transactions or a `FapshiError`.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::transaction::TransactionApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     safety::Environment,
+    /// };
     ///
     /// use std::env;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let transactions = TransactionApi::get_transactions_by_user_id(&client, "user123").unwrap();
     /// for tx in transactions {
-    ///     println!("Transaction ID: {}, Status: {}", tx.transaction_id, tx.status);
+    ///     println!("Transaction ID: {}, Status: {:?}", tx.transaction_id, tx.status);
     /// }
+    /// # Ok(())
+    /// # }
     /// ```
     #[cfg(not(feature = "async"))]
     pub fn get_transactions_by_user_id(
         client: &FapshiClient,
         user_id: &str,
     ) -> Result<Vec<TransactionStatus>, FapshiError> {
-        let endpoint = format!("transaction/{}", user_id);
-        let response = client.get(&endpoint)?;
-        let transactions: Vec<TransactionStatus> = serde_json::from_str(&response)?;
-        Ok(transactions)
+        client.call(&UserTransactions(user_id))
     }
 
     /// Retrieves all transactions associated with a user ID asynchronously.
@@ -166,17 +174,22 @@ impl TransactionApi {
     /// A `Result` containing a `Vec<TransactionStatus>` with matching transactions or a `FapshiError`.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::transaction::TransactionApi};
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     safety::Environment,
+    /// };
+    /// use std::env;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
     /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-    /// let client = FapshiClient::new(&api_user, &api_key, true)?;
+    /// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
     /// let transactions = TransactionApi::get_transactions_by_user_id(&client, "user123").await.unwrap();
     /// for tx in transactions {
-    ///     println!("Transaction ID: {}, Status: {}", tx.transaction_id, tx.status);
+    ///     println!("Transaction ID: {}, Status: {:?}", tx.transaction_id, tx.status);
     /// }
     /// # Ok(())
     /// # }
@@ -186,15 +199,134 @@ impl TransactionApi {
         client: &FapshiClient,
         user_id: &str,
     ) -> Result<Vec<TransactionStatus>, FapshiError> {
-        let endpoint = format!("transaction/{}", user_id);
-        let response = client.get(&endpoint).await?;
-        let transactions: Vec<TransactionStatus> = serde_json::from_str(&response)?;
-        Ok(transactions)
+        client.call(&UserTransactions(user_id)).await
+    }
+
+    /// Searches transactions based on specified criteria synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `query` - The `TransactionSearchQuery` with the filters to apply.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<TransactionStatus>` with matching transactions or a `FapshiError`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     api::transaction::TransactionApi,
+    ///     client::FapshiClient,
+    ///     models::{Status, TransactionSearchQuery},
+    ///     safety::Environment,
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+    /// let query = TransactionSearchQuery {
+    ///     status: Some(Status::SUCCESSFUL),
+    ///     start: Some("2025-01-01".to_string()),
+    ///     end: Some("2025-01-31".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// for tx in TransactionApi::search_transactions(&client, &query)? {
+    ///     println!("Transaction ID: {}, Amount: {}", tx.transaction_id, tx.amount);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "async"))]
+    pub fn search_transactions(
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        client.call(&SearchTransactions(query))
+    }
+
+    /// Searches transactions based on specified criteria asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `query` - The `TransactionSearchQuery` with the filters to apply.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<TransactionStatus>` with matching transactions or a `FapshiError`.
+    #[cfg(feature = "async")]
+    pub async fn search_transactions(
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        client.call(&SearchTransactions(query)).await
+    }
+
+    /// Searches every transaction matching `query` with the maximum `limit`, passing each
+    /// page to `on_page`. A page that comes back full is searched again as narrower queries
+    /// (see `TransactionSearchQuery::narrow`) and not passed on.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` when a full page cannot be narrowed.
+    #[cfg(not(feature = "async"))]
+    pub(crate) fn search_pages(
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+        mut on_page: impl FnMut(Vec<TransactionStatus>) -> Result<(), FapshiError>,
+    ) -> Result<(), FapshiError> {
+        let mut pending = vec![TransactionSearchQuery {
+            limit: Some(SEARCH_LIMIT_MAX),
+            ..query.clone()
+        }];
+        while let Some(query) = pending.pop() {
+            let page = Self::search_transactions(client, &query)?;
+            if page.len() < SEARCH_LIMIT_MAX as usize {
+                on_page(page)?;
+                continue;
+            }
+            let narrowed = query.narrow().ok_or_else(|| full_page(&query))?;
+            pending.extend(narrowed.into_iter().rev());
+        }
+        Ok(())
+    }
+
+    /// Searches every transaction matching `query` with the maximum `limit`, passing each
+    /// page to `on_page`, asynchronously. A page that comes back full is searched again as
+    /// narrower queries (see `TransactionSearchQuery::narrow`) and not passed on.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` when a full page cannot be narrowed.
+    #[cfg(feature = "async")]
+    pub(crate) async fn search_pages(
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+        mut on_page: impl FnMut(Vec<TransactionStatus>) -> Result<(), FapshiError>,
+    ) -> Result<(), FapshiError> {
+        let mut pending = vec![TransactionSearchQuery {
+            limit: Some(SEARCH_LIMIT_MAX),
+            ..query.clone()
+        }];
+        while let Some(query) = pending.pop() {
+            let page = Self::search_transactions(client, &query).await?;
+            if page.len() < SEARCH_LIMIT_MAX as usize {
+                on_page(page)?;
+                continue;
+            }
+            let narrowed = query.narrow().ok_or_else(|| full_page(&query))?;
+            pending.extend(narrowed.into_iter().rev());
+        }
+        Ok(())
     }
+}
 
-    // /// Searches transactions based on specified criteria (unchanged, commented out).
-    // /// ...
-    // pub fn search_transactions(client: &FapshiClient, query: &TransactionSearchQuery) -> Result<TransactionList, FapshiError> {
-    //     ...
-    // }
-}
\ No newline at end of file
+/// The error for a full search page that cannot be split into narrower searches.
+fn full_page(query: &TransactionSearchQuery) -> FapshiError {
+    let message = match (&query.start, &query.end) {
+        (Some(start), Some(end)) if start == end => format!(
+            "more than {SEARCH_LIMIT_MAX} transactions on {start} match the search, which \
+             cannot be split further; narrow it by medium, name or amount"
+        ),
+        _ => format!(
+            "the search returned a full page of {SEARCH_LIMIT_MAX} transactions; set `start` \
+             and `end` (yyyy-mm-dd) so it can be split by date"
+        ),
+    };
+    FapshiError::InvalidRequest(message)
+}
@@ -1,58 +1,207 @@
-use crate::{client::FapshiClient, error::FapshiError, models::WebhookConfig};
+use std::time::Duration;
 
-/// API for configuring webhooks.
+#[cfg(not(feature = "async"))]
+use reqwest::blocking::Client;
+#[cfg(feature = "async")]
+use reqwest::Client;
+use url::Url;
+
+use crate::{
+    client::FapshiClient,
+    error::FapshiError,
+    models::{Status, TransactionStatus, WebhookConfig},
+};
+
+/// Timeout for requests sent to the webhook endpoint itself.
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// API for checking webhook endpoints.
+///
+/// Fapshi's public API has no endpoint for webhook settings: the webhook URL of a service is
+/// set in the Fapshi dashboard. This API checks that your endpoint is ready to receive the
+/// events Fapshi sends to it.
 pub struct WebhookApi;
 
 impl WebhookApi {
-    /// Configures a webhook for receiving payment status updates synchronously.
+    /// Checks the webhook endpoint of `config` synchronously.
+    ///
+    /// Fapshi's public API cannot store a webhook URL; set it in the Fapshi dashboard. This
+    /// only runs [`WebhookApi::verify_endpoint`] on `config.url`, without a test event.
     ///
     /// # Arguments
-    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `_client` - Unused; kept for compatibility.
     /// * `config` - The `WebhookConfig` containing the webhook URL and service ID.
     ///
     /// # Returns
-    /// A `Result` indicating success or a `FapshiError` if the request fails.
+    /// A `Result` indicating success or a `FapshiError` if the endpoint fails a check.
     #[cfg(not(feature = "async"))]
+    #[deprecated(
+        note = "Fapshi's API does not configure webhooks; set the URL in the Fapshi dashboard \
+                and check it with `WebhookApi::verify_endpoint`"
+    )]
     pub fn configure_webhook(
-        client: &FapshiClient,
+        _client: &FapshiClient,
         config: &WebhookConfig,
     ) -> Result<(), FapshiError> {
-        let url = &config.url;
-        client.post(url, "{}")?;
-        Ok(())
+        Self::verify_endpoint(&config.url, false)
     }
 
-    /// Configures a webhook for receiving payment status updates asynchronously.
+    /// Checks the webhook endpoint of `config` asynchronously.
+    ///
+    /// Fapshi's public API cannot store a webhook URL; set it in the Fapshi dashboard. This
+    /// only runs [`WebhookApi::verify_endpoint`] on `config.url`, without a test event.
     ///
     /// # Arguments
-    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `_client` - Unused; kept for compatibility.
     /// * `config` - The `WebhookConfig` containing the webhook URL and service ID.
     ///
     /// # Returns
-    /// A `Result` indicating success or a `FapshiError` if the request fails.
+    /// A `Result` indicating success or a `FapshiError` if the endpoint fails a check.
+    #[cfg(feature = "async")]
+    #[deprecated(
+        note = "Fapshi's API does not configure webhooks; set the URL in the Fapshi dashboard \
+                and check it with `WebhookApi::verify_endpoint`"
+    )]
+    pub async fn configure_webhook(
+        _client: &FapshiClient,
+        config: &WebhookConfig,
+    ) -> Result<(), FapshiError> {
+        Self::verify_endpoint(&config.url, false).await
+    }
+
+    /// Checks that a webhook URL is an absolute HTTPS URL with a host.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` describing the problem.
+    pub fn validate_url(url: &str) -> Result<Url, FapshiError> {
+        let parsed = Url::parse(url).map_err(|err| {
+            FapshiError::InvalidRequest(format!("invalid webhook URL {url:?}: {err}"))
+        })?;
+        if parsed.scheme() != "https" {
+            return Err(FapshiError::InvalidRequest(format!(
+                "webhook URL {url:?} must use https"
+            )));
+        }
+        if parsed.host_str().is_none_or(str::is_empty) {
+            return Err(FapshiError::InvalidRequest(format!(
+                "webhook URL {url:?} has no host"
+            )));
+        }
+        Ok(parsed)
+    }
+
+    /// Verifies that a webhook endpoint is valid and reachable, synchronously.
+    ///
+    /// The URL is validated, then probed with a `HEAD` request, which must not be answered
+    /// with a 5xx status, 404 or 410; endpoints that only accept `POST` may answer 405 or
+    /// another 4xx. If `send_test_event` is `true`, a synthetic event built by
+    /// [`WebhookApi::test_event`] is POSTed and must be acknowledged with a 2xx status.
+    /// Your Fapshi credentials are never sent to the endpoint.
+    ///
+    /// # Arguments
+    /// * `url` - The webhook URL to verify.
+    /// * `send_test_event` - Whether to deliver a synthetic event to the endpoint.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or a `FapshiError` if the endpoint fails a check.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_sdk::{FapshiClient, api::webhook::WebhookApi, models::WebhookConfig};
-    ///
-    /// # #[tokio::main]
-    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true)?;
-    /// let config = WebhookConfig {
-    ///     url: "https://yourapp.com/webhook".to_string(),
-    ///     service_id: "service123".to_string(),
-    /// };
-    /// WebhookApi::configure_webhook(&client, &config).await.unwrap();
+    /// ```no_run
+    /// use fapshi_rs::api::webhook::WebhookApi;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// WebhookApi::verify_endpoint("https://yourapp.com/webhook", true)?;
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg(not(feature = "async"))]
+    pub fn verify_endpoint(url: &str, send_test_event: bool) -> Result<(), FapshiError> {
+        let url = Self::validate_url(url)?;
+        let client = Client::builder().timeout(ENDPOINT_TIMEOUT).build()?;
+        let response = client.head(url.clone()).send()?;
+        check_reachable(response.status().as_u16())?;
+        if send_test_event {
+            let response = client.post(url).json(&Self::test_event()).send()?;
+            check_acknowledged(response.status().as_u16(), || response.text().ok())?;
+        }
+        Ok(())
+    }
+
+    /// Verifies that a webhook endpoint is valid and reachable, asynchronously.
+    ///
+    /// The URL is validated, then probed with a `HEAD` request, which must not be answered
+    /// with a 5xx status, 404 or 410; endpoints that only accept `POST` may answer 405 or
+    /// another 4xx. If `send_test_event` is `true`, a synthetic event built by
+    /// [`WebhookApi::test_event`] is POSTed and must be acknowledged with a 2xx status.
+    /// Your Fapshi credentials are never sent to the endpoint.
+    ///
+    /// # Arguments
+    /// * `url` - The webhook URL to verify.
+    /// * `send_test_event` - Whether to deliver a synthetic event to the endpoint.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or a `FapshiError` if the endpoint fails a check.
     #[cfg(feature = "async")]
-    pub async fn configure_webhook(
-        client: &FapshiClient,
-        config: &WebhookConfig,
-    ) -> Result<(), FapshiError> {
-        let url = &config.url;
-        client.post(url, "{}").await?;
+    pub async fn verify_endpoint(url: &str, send_test_event: bool) -> Result<(), FapshiError> {
+        let url = Self::validate_url(url)?;
+        let client = Client::builder().timeout(ENDPOINT_TIMEOUT).build()?;
+        let response = client.head(url.clone()).send().await?;
+        check_reachable(response.status().as_u16())?;
+        if send_test_event {
+            let response = client.post(url).json(&Self::test_event()).send().await?;
+            let status = response.status().as_u16();
+            let body = response.text().await.ok();
+            check_acknowledged(status, || body)?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Builds the synthetic transaction event sent by [`WebhookApi::verify_endpoint`].
+    ///
+    /// It has the shape of a real Fapshi webhook payload, with a transaction ID starting with
+    /// `test-` so handlers can recognise and ignore it.
+    pub fn test_event() -> TransactionStatus {
+        TransactionStatus {
+            transaction_id: "test-webhook-event".to_string(),
+            status: Status::SUCCESSFUL,
+            medium: Some("mobile money".to_string()),
+            service_name: "Webhook test".to_string(),
+            amount: 100.0,
+            revenue: Some(100.0),
+            payer_name: Some("Fapshi SDK".to_string()),
+            email: String::new(),
+            redirect_url: String::new(),
+            external_id: Some("test-webhook-event".to_string()),
+            user_id: None,
+            webhook: None,
+            financial_transaction_id: None,
+            date_initiated: String::new(),
+            date_confirmed: String::new(),
+        }
+    }
+}
+
+fn check_reachable(status: u16) -> Result<(), FapshiError> {
+    if status < 500 && status != 404 && status != 410 {
+        return Ok(());
+    }
+    Err(FapshiError::StatusError {
+        status,
+        message: "webhook endpoint is not reachable".to_string(),
+        request_id: None,
+    })
+}
+
+fn check_acknowledged(
+    status: u16,
+    body: impl FnOnce() -> Option<String>,
+) -> Result<(), FapshiError> {
+    if (200..300).contains(&status) {
+        return Ok(());
+    }
+    Err(FapshiError::StatusError {
+        status,
+        message: body().unwrap_or_default(),
+        request_id: None,
+    })
+}
@@ -0,0 +1,730 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::HeaderMap, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{client, endpoint, error::FapshiError, safety::Environment};
+
+/// The `prev_hash` of the first entry of an audit log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A money-moving operation recorded in the audit trail.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A payment link created (`POST /initiate-pay`).
+    PaymentLink,
+    /// A direct payment initiated (`POST /direct-pay`).
+    DirectPayment,
+    /// A transaction expired (`POST /expire-pay`).
+    Expiry,
+    /// A payout sent (`POST /payout`).
+    Payout,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditAction::PaymentLink => "payment_link",
+            AuditAction::DirectPayment => "direct_payment",
+            AuditAction::Expiry => "expiry",
+            AuditAction::Payout => "payout",
+        })
+    }
+}
+
+/// Whether an audited operation succeeded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// Written just before the request is sent. A later record with the same
+    /// [`AuditRecord::call_id`] gives the outcome; without one, the process stopped during the
+    /// call and the operation may or may not have happened on Fapshi.
+    Initiated,
+    /// Fapshi accepted the request.
+    Success,
+    /// The request failed; see [`AuditRecord::error`]. Unless the error says otherwise, the
+    /// operation may still have happened on Fapshi.
+    Failure,
+}
+
+/// One audited operation: who did what, when, and with which result.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// When the record was written.
+    pub timestamp: DateTime<Utc>,
+    /// Identifies the call; its `initiated` record and its outcome record share it.
+    pub call_id: String,
+    /// Who initiated it: the audit actor set on the client, or its API user.
+    pub actor: String,
+    /// The environment the request went to.
+    pub environment: Environment,
+    /// What was done.
+    pub action: AuditAction,
+    /// The external ID of the request, if any.
+    pub external_id: Option<String>,
+    /// The amount of the request, if any.
+    pub amount: Option<f64>,
+    /// The SHA-256 hash, in hex, of the JSON request body.
+    pub request_hash: String,
+    /// The transaction created, or expired, by the operation.
+    pub transaction_id: Option<String>,
+    /// Whether the operation is starting, succeeded or failed.
+    pub outcome: AuditOutcome,
+    /// The error message of a failed operation.
+    pub error: Option<String>,
+}
+
+/// Destination of the audit records of a [`FapshiClient`](crate::client::FapshiClient).
+///
+/// Attach one with `FapshiClient::with_audit_sink` or `TowerClient::with_audit_sink`. Every
+/// payment link, direct payment, expiry and payout request the client makes is then recorded
+/// twice: an `initiated` record before it is sent, and its outcome once it finishes. This
+/// holds whether it goes through `PaymentApi`, `PayoutApi`, `TransactionApi` or the
+/// lower-level `call`, `post` and `request` methods. Requests refused by dry-run mode or the
+/// amount limits are not recorded. Implement this trait to keep the records in your own
+/// storage.
+pub trait AuditSink: Send + Sync + fmt::Debug {
+    /// Appends a record.
+    fn record(&self, record: &AuditRecord) -> Result<(), FapshiError>;
+}
+
+/// An `AuditSink` kept in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryAuditSink {
+    records: Mutex<Vec<AuditRecord>>,
+}
+
+impl InMemoryAuditSink {
+    /// Creates an empty in-memory sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the records so far, oldest first.
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl AuditSink for InMemoryAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), FapshiError> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record.clone());
+        Ok(())
+    }
+}
+
+/// A line of a [`JsonLinesAuditSink`] file.
+///
+/// `hash` is the SHA-256 of the entry serialized without it, and `prev_hash` the `hash` of the
+/// previous line, so editing, inserting, removing or reordering lines breaks the chain.
+/// Removing the last lines leaves a valid, shorter chain; anchor the head hash outside the
+/// log to detect that, see [`JsonLinesAuditSink::verify_anchored`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// The position of the entry in the file, starting at 1.
+    pub sequence: u64,
+    /// The audited operation.
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    /// The hash of the previous entry, or [`GENESIS_HASH`] for the first one.
+    pub prev_hash: String,
+    /// The hash of this entry.
+    pub hash: String,
+}
+
+/// The entry as hashed: everything but the hash itself.
+#[derive(Serialize)]
+struct UnhashedEntry<'a> {
+    sequence: u64,
+    #[serde(flatten)]
+    record: &'a AuditRecord,
+    prev_hash: &'a str,
+}
+
+impl AuditEntry {
+    fn new(sequence: u64, record: AuditRecord, prev_hash: String) -> Result<Self, FapshiError> {
+        let hash = entry_hash(sequence, &record, &prev_hash)?;
+        Ok(AuditEntry {
+            sequence,
+            record,
+            prev_hash,
+            hash,
+        })
+    }
+}
+
+fn entry_hash(sequence: u64, record: &AuditRecord, prev_hash: &str) -> Result<String, FapshiError> {
+    let unhashed = serde_json::to_string(&UnhashedEntry {
+        sequence,
+        record,
+        prev_hash,
+    })?;
+    Ok(sha256_hex(unhashed.as_bytes()))
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// The result of checking the hash chain of an audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainStatus {
+    /// Every entry is intact and linked to the previous one.
+    Intact {
+        /// The number of entries.
+        entries: u64,
+        /// The hash of the last entry, or [`GENESIS_HASH`] for an empty log.
+        head_hash: String,
+    },
+    /// The chain is broken at `line` (starting at 1).
+    Broken {
+        /// The first line that does not verify.
+        line: u64,
+        /// What is wrong with it.
+        reason: String,
+    },
+}
+
+/// An append-only JSON Lines audit log with hash chaining for tamper evidence.
+///
+/// Each record is written as an [`AuditEntry`] on its own line and synced to disk before the
+/// call returns. Reopening an existing file continues its chain. Use
+/// [`JsonLinesAuditSink::verify`] to check that a file was not altered.
+///
+/// A chain cannot prove on its own that its last entries were not cut off. To detect that,
+/// copy [`JsonLinesAuditSink::head`] somewhere the log's writers cannot change (another
+/// system, a ticket, a signed email) from time to time, and check the log against it with
+/// [`JsonLinesAuditSink::verify_anchored`].
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     api::payout::PayoutApi, audit::JsonLinesAuditSink, client::FapshiClient, models::Payouts,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?
+///         .with_audit_sink(JsonLinesAuditSink::open("audit.jsonl")?)
+///         .with_audit_actor("refunds-worker");
+/// let payout = Payouts {
+///     amount: 1000.0,
+///     phone_number: "670000000".to_string(),
+///     medium: "mobile money".to_string(),
+///     name: None,
+///     email: None,
+///     user_id: None,
+///     external_id: Some("refund-1001".to_string()),
+///     message: None,
+/// };
+/// PayoutApi::send_payout(&client, &payout)?;
+/// println!("{:?}", JsonLinesAuditSink::verify("audit.jsonl")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesAuditSink {
+    path: PathBuf,
+    state: Mutex<ChainHead>,
+}
+
+/// The end of the chain, where the next entry attaches.
+#[derive(Debug)]
+struct ChainHead {
+    file: File,
+    sequence: u64,
+    hash: String,
+}
+
+impl JsonLinesAuditSink {
+    /// Opens the log at `path` for appending, creating it if needed.
+    ///
+    /// A final line without a line break is the remains of a write interrupted by a crash.
+    /// It is moved to `<path>.torn`, so the chain continues from the last complete entry and
+    /// nothing is lost.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::IoError` if the file cannot be opened, and
+    /// `FapshiError::SerializationError` if its last complete line is not an audit entry.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FapshiError> {
+        let path = path.as_ref().to_path_buf();
+        let (sequence, hash) = match last_entry(&path)? {
+            Some(entry) => (entry.sequence, entry.hash),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(JsonLinesAuditSink {
+            path,
+            state: Mutex::new(ChainHead {
+                file,
+                sequence,
+                hash,
+            }),
+        })
+    }
+
+    /// Returns the path of the log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the sequence number and hash of the last entry written, `(0, GENESIS_HASH)`
+    /// for an empty log. Keep them outside the log to check it later with
+    /// [`JsonLinesAuditSink::verify_anchored`].
+    pub fn head(&self) -> (u64, String) {
+        let head = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (head.sequence, head.hash.clone())
+    }
+
+    /// Checks the hash chain of the log at `path`.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::IoError` if the file cannot be read. Tampering is reported as
+    /// [`ChainStatus::Broken`], not as an error.
+    pub fn verify(path: impl AsRef<Path>) -> Result<ChainStatus, FapshiError> {
+        check_chain(path.as_ref(), None)
+    }
+
+    /// Checks the hash chain of the log at `path`, and that it still holds entry `sequence`
+    /// with hash `hash`, as returned by [`JsonLinesAuditSink::head`] earlier. This detects
+    /// entries removed from the end of the log since the anchor was taken.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::IoError` if the file cannot be read. Tampering is reported as
+    /// [`ChainStatus::Broken`], not as an error.
+    pub fn verify_anchored(
+        path: impl AsRef<Path>,
+        sequence: u64,
+        hash: &str,
+    ) -> Result<ChainStatus, FapshiError> {
+        check_chain(path.as_ref(), Some((sequence, hash)))
+    }
+}
+
+/// Checks the hash chain of a log, and optionally that it holds an anchored entry.
+fn check_chain(path: &Path, anchor: Option<(u64, &str)>) -> Result<ChainStatus, FapshiError> {
+    let contents = fs::read(path)?;
+    let contents = String::from_utf8_lossy(&contents);
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut line_number = 0;
+    for line in contents.split_inclusive('\n') {
+        line_number += 1;
+        let broken = |reason: String| ChainStatus::Broken {
+            line: line_number,
+            reason,
+        };
+        if !line.ends_with('\n') {
+            return Ok(broken(
+                "incomplete line left by an interrupted write; reopen the log to set it aside"
+                    .to_string(),
+            ));
+        }
+        let entry: AuditEntry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(err) => return Ok(broken(format!("not an audit entry: {err}"))),
+        };
+        if entry.sequence != line_number {
+            return Ok(broken(format!(
+                "sequence {} where {line_number} was expected",
+                entry.sequence
+            )));
+        }
+        if entry.prev_hash != prev_hash {
+            return Ok(broken(
+                "prev_hash does not match the previous entry".to_string(),
+            ));
+        }
+        if entry_hash(entry.sequence, &entry.record, &entry.prev_hash)? != entry.hash {
+            return Ok(broken("hash does not match the entry".to_string()));
+        }
+        if anchor.is_some_and(|(sequence, hash)| sequence == line_number && hash != entry.hash) {
+            return Ok(broken("hash does not match the anchored hash".to_string()));
+        }
+        prev_hash = entry.hash;
+    }
+    if let Some((sequence, _)) = anchor.filter(|(sequence, _)| *sequence > line_number) {
+        return Ok(ChainStatus::Broken {
+            line: line_number + 1,
+            reason: format!("anchored entry {sequence} is missing; the log was truncated"),
+        });
+    }
+    Ok(ChainStatus::Intact {
+        entries: line_number,
+        head_hash: prev_hash,
+    })
+}
+
+impl AuditSink for JsonLinesAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), FapshiError> {
+        let mut head = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = AuditEntry::new(head.sequence + 1, record.clone(), head.hash.clone())?;
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        head.file.write_all(line.as_bytes())?;
+        head.file.sync_data()?;
+        head.sequence = entry.sequence;
+        head.hash = entry.hash;
+        Ok(())
+    }
+}
+
+/// Reads the last entry of an existing log, first moving an incomplete final line to
+/// `<path>.torn`.
+fn last_entry(path: &Path) -> Result<Option<AuditEntry>, FapshiError> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    // Bytes, not a string: an interrupted write may end in the middle of a character.
+    let complete = contents
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |end| end + 1);
+    if complete < contents.len() {
+        let mut torn_path = path.as_os_str().to_owned();
+        torn_path.push(".torn");
+        let mut torn = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(PathBuf::from(torn_path))?;
+        torn.write_all(&contents[complete..])?;
+        torn.write_all(b"\n")?;
+        torn.sync_data()?;
+        OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_len(complete as u64)?;
+    }
+    match String::from_utf8_lossy(&contents[..complete])
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+    {
+        Some(line) => Ok(Some(serde_json::from_str(line)?)),
+        None => Ok(None),
+    }
+}
+
+/// The keys request bodies carry the external ID under, depending on the endpoint.
+const EXTERNAL_ID_KEYS: &[&str] = &["externalId", "ExternalId", "external_id"];
+
+/// Distinguishes the calls made by this process.
+static CALL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A request that moves money, as seen by [`FapshiClient`](crate::client::FapshiClient) or
+/// [`TowerClient`](crate::service::TowerClient) before sending it.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditedCall {
+    call_id: String,
+    actor: String,
+    environment: Environment,
+    action: AuditAction,
+    external_id: Option<String>,
+    amount: Option<f64>,
+    request_hash: String,
+    transaction_id: Option<String>,
+}
+
+impl AuditedCall {
+    /// Returns the audited call a request makes, or `None` if it is not a payment link, direct
+    /// payment, expiry or payout.
+    pub(crate) fn identify(
+        method: &Method,
+        endpoint: &str,
+        body: Option<&str>,
+        actor: &str,
+        environment: Environment,
+    ) -> Option<Self> {
+        let action = match endpoint::identify(method, endpoint)?.name {
+            "initiate-pay" => AuditAction::PaymentLink,
+            "direct-pay" => AuditAction::DirectPayment,
+            "expire-pay" => AuditAction::Expiry,
+            "payout" => AuditAction::Payout,
+            _ => return None,
+        };
+        let body = body.unwrap_or_default();
+        let fields = serde_json::from_str::<Value>(body).unwrap_or(Value::Null);
+        let field = |key: &str| fields.get(key).and_then(Value::as_str).map(str::to_string);
+        let call_id = format!(
+            "{:x}-{:x}-{}",
+            Utc::now().timestamp_micros(),
+            std::process::id(),
+            CALL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Some(AuditedCall {
+            call_id,
+            actor: actor.to_string(),
+            environment,
+            action,
+            external_id: EXTERNAL_ID_KEYS.iter().find_map(|key| field(key)),
+            amount: fields.get("amount").and_then(Value::as_f64),
+            request_hash: sha256_hex(body.as_bytes()),
+            transaction_id: field("transId"),
+        })
+    }
+
+    /// Writes the `initiated` record of the call, before it is sent.
+    pub(crate) fn initiated(&self, sink: &dyn AuditSink) -> Result<(), FapshiError> {
+        self.record(sink, AuditOutcome::Initiated, None, None)
+    }
+
+    /// Writes the outcome record of the finished call.
+    ///
+    /// # Arguments
+    /// * `response` - The status and body Fapshi answered with, or the error that prevented
+    ///   an answer.
+    pub(crate) fn finished(
+        &self,
+        sink: &dyn AuditSink,
+        response: Result<(StatusCode, &str), &FapshiError>,
+    ) -> Result<(), FapshiError> {
+        match response {
+            Ok((status, text)) if status.is_success() => {
+                let transaction_id = serde_json::from_str::<Value>(text)
+                    .ok()
+                    .and_then(|body| body.get("transId")?.as_str().map(str::to_string));
+                self.record(sink, AuditOutcome::Success, transaction_id, None)
+            }
+            Ok((status, text)) => {
+                let err = client::status_error(status, &HeaderMap::new(), text);
+                self.record(sink, AuditOutcome::Failure, None, Some(err.to_string()))
+            }
+            Err(err) => self.record(sink, AuditOutcome::Failure, None, Some(err.to_string())),
+        }
+    }
+
+    fn record(
+        &self,
+        sink: &dyn AuditSink,
+        outcome: AuditOutcome,
+        transaction_id: Option<String>,
+        error: Option<String>,
+    ) -> Result<(), FapshiError> {
+        sink.record(&AuditRecord {
+            timestamp: Utc::now(),
+            call_id: self.call_id.clone(),
+            actor: self.actor.clone(),
+            environment: self.environment,
+            action: self.action,
+            external_id: self.external_id.clone(),
+            amount: self.amount,
+            request_hash: self.request_hash.clone(),
+            transaction_id: transaction_id.or_else(|| self.transaction_id.clone()),
+            outcome,
+            error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "fapshi-rs-audit-{}-{name}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("jsonl.torn"));
+        path
+    }
+
+    fn record(call: u32, outcome: AuditOutcome) -> AuditRecord {
+        AuditRecord {
+            timestamp: DateTime::parse_from_rfc3339("2025-01-31T09:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            call_id: format!("call-{call}"),
+            actor: "refunds-worker".to_string(),
+            environment: Environment::Sandbox,
+            action: AuditAction::Payout,
+            external_id: Some(format!("refund-{call}")),
+            amount: Some(1000.0),
+            request_hash: sha256_hex(b"{}"),
+            transaction_id: None,
+            outcome,
+            error: None,
+        }
+    }
+
+    /// Writes three entries and returns the head.
+    fn write_log(path: &Path) -> (u64, String) {
+        let sink = JsonLinesAuditSink::open(path).unwrap();
+        sink.record(&record(1, AuditOutcome::Initiated)).unwrap();
+        sink.record(&record(1, AuditOutcome::Success)).unwrap();
+        sink.record(&record(2, AuditOutcome::Initiated)).unwrap();
+        sink.head()
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn rewrite(path: &Path, lines: &[String]) {
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    fn broken_line(status: ChainStatus) -> u64 {
+        match status {
+            ChainStatus::Broken { line, .. } => line,
+            intact => panic!("expected a broken chain, got {intact:?}"),
+        }
+    }
+
+    #[test]
+    fn written_log_verifies() {
+        let path = log_path("intact");
+        let (sequence, hash) = write_log(&path);
+        assert_eq!(sequence, 3);
+        assert_eq!(
+            JsonLinesAuditSink::verify(&path).unwrap(),
+            ChainStatus::Intact {
+                entries: 3,
+                head_hash: hash.clone(),
+            }
+        );
+        assert_eq!(
+            JsonLinesAuditSink::verify_anchored(&path, sequence, &hash).unwrap(),
+            ChainStatus::Intact {
+                entries: 3,
+                head_hash: hash,
+            }
+        );
+
+        let first: AuditEntry = serde_json::from_str(&lines(&path)[0]).unwrap();
+        assert_eq!(first.prev_hash, GENESIS_HASH);
+        assert_eq!(first.record, record(1, AuditOutcome::Initiated));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reopened_log_continues_the_chain() {
+        let path = log_path("reopen");
+        let (_, hash) = write_log(&path);
+        let sink = JsonLinesAuditSink::open(&path).unwrap();
+        assert_eq!(sink.head(), (3, hash.clone()));
+        sink.record(&record(2, AuditOutcome::Success)).unwrap();
+
+        let fourth: AuditEntry = serde_json::from_str(&lines(&path)[3]).unwrap();
+        assert_eq!((fourth.sequence, fourth.prev_hash), (4, hash));
+        assert!(matches!(
+            JsonLinesAuditSink::verify(&path).unwrap(),
+            ChainStatus::Intact { entries: 4, .. }
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn edited_entry_breaks_the_chain() {
+        let path = log_path("edited");
+        write_log(&path);
+        let mut lines = lines(&path);
+        lines[1] = lines[1].replace("1000.0", "100000.0");
+        rewrite(&path, &lines);
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 2);
+
+        // Recomputing the edited entry's hash moves the break to the next entry.
+        let mut entry: AuditEntry = serde_json::from_str(&lines[1]).unwrap();
+        entry.hash = entry_hash(entry.sequence, &entry.record, &entry.prev_hash).unwrap();
+        lines[1] = serde_json::to_string(&entry).unwrap();
+        rewrite(&path, &lines);
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn removed_or_reordered_entries_break_the_chain() {
+        let path = log_path("removed");
+        write_log(&path);
+        let original = lines(&path);
+
+        rewrite(&path, &[original[0].clone(), original[2].clone()]);
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 2);
+
+        rewrite(
+            &path,
+            &[
+                original[1].clone(),
+                original[0].clone(),
+                original[2].clone(),
+            ],
+        );
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 1);
+
+        rewrite(
+            &path,
+            &[original[0].clone(), "{}".to_string(), original[2].clone()],
+        );
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncation_is_only_caught_against_an_anchor() {
+        let path = log_path("truncated");
+        let (sequence, hash) = write_log(&path);
+        rewrite(&path, &lines(&path)[..2]);
+
+        assert!(matches!(
+            JsonLinesAuditSink::verify(&path).unwrap(),
+            ChainStatus::Intact { entries: 2, .. }
+        ));
+        assert_eq!(
+            broken_line(JsonLinesAuditSink::verify_anchored(&path, sequence, &hash).unwrap()),
+            3
+        );
+        assert_eq!(
+            broken_line(JsonLinesAuditSink::verify_anchored(&path, 2, &hash).unwrap()),
+            2
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn torn_tail_is_set_aside_on_reopen() {
+        let path = log_path("torn");
+        let (_, hash) = write_log(&path);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"sequence":4,"call_id":"call-"#)
+            .unwrap();
+        drop(file);
+        assert_eq!(broken_line(JsonLinesAuditSink::verify(&path).unwrap()), 4);
+
+        let sink = JsonLinesAuditSink::open(&path).unwrap();
+        assert_eq!(sink.head(), (3, hash));
+        let torn = path.with_extension("jsonl.torn");
+        assert_eq!(
+            fs::read_to_string(&torn).unwrap(),
+            "{\"sequence\":4,\"call_id\":\"call-\n"
+        );
+        sink.record(&record(2, AuditOutcome::Success)).unwrap();
+        assert!(matches!(
+            JsonLinesAuditSink::verify(&path).unwrap(),
+            ChainStatus::Intact { entries: 4, .. }
+        ));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&torn).unwrap();
+    }
+}
@@ -0,0 +1,935 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{payment::PaymentApi, payout::PayoutApi},
+    client::FapshiClient,
+    error::FapshiError,
+    models::{DirectPaymentRequest, Payouts},
+    store::write_atomically,
+};
+
+/// The kind of request a batch sends.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchKind {
+    /// Direct payments sent with `PaymentApi::initiate_direct_payment`.
+    DirectPayment,
+    /// Payouts sent with `PayoutApi::send_payout`.
+    Payout,
+}
+
+impl BatchKind {
+    fn checkpoint_key(&self, external_id: &str) -> String {
+        match self {
+            BatchKind::DirectPayment => format!("direct-payment:{external_id}"),
+            BatchKind::Payout => format!("payout:{external_id}"),
+        }
+    }
+}
+
+/// The result of one item of a batch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ItemOutcome {
+    /// Fapshi accepted the request.
+    Succeeded {
+        /// The transaction ID returned by Fapshi.
+        transaction_id: String,
+    },
+    /// The request failed local validation and was not sent.
+    Invalid {
+        /// Why the request is invalid.
+        error: String,
+    },
+    /// The request was not processed by Fapshi: it was refused with a 4xx status or could
+    /// not be sent at all. It is sent again when the batch is re-run.
+    Rejected {
+        /// The HTTP status, if Fapshi answered.
+        status: Option<u16>,
+        /// The error message.
+        error: String,
+    },
+    /// The request may or may not have been processed (timeout, 5xx, crash while in flight).
+    /// It is never resubmitted automatically; check its `external_id` on Fapshi, then call
+    /// [`BatchRunner::forget`] if it should be sent again.
+    Unknown {
+        /// The error message.
+        error: String,
+    },
+}
+
+impl ItemOutcome {
+    /// Returns the transaction ID if the item succeeded.
+    pub fn transaction_id(&self) -> Option<&str> {
+        match self {
+            ItemOutcome::Succeeded { transaction_id } => Some(transaction_id),
+            _ => None,
+        }
+    }
+
+    fn from_error(err: FapshiError) -> Self {
+        if !err.is_not_processed() {
+            return ItemOutcome::Unknown {
+                error: err.to_string(),
+            };
+        }
+        let status = match err {
+            FapshiError::StatusError { status, .. } => Some(status),
+            _ => None,
+        };
+        ItemOutcome::Rejected {
+            status,
+            error: err.to_string(),
+        }
+    }
+}
+
+/// The report for one item of a batch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemReport {
+    /// Position of the item in the submitted list.
+    pub index: usize,
+    /// The item's external ID.
+    pub external_id: Option<String>,
+    /// What happened to the item.
+    #[serde(flatten)]
+    pub outcome: ItemOutcome,
+    /// Whether the outcome comes from the checkpoint of an earlier run.
+    pub resumed: bool,
+}
+
+/// Counts of item outcomes in a [`BatchReport`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    /// Items accepted by Fapshi.
+    pub succeeded: usize,
+    /// Items that failed validation.
+    pub invalid: usize,
+    /// Items refused by Fapshi or not sent.
+    pub rejected: usize,
+    /// Items whose fate is unknown.
+    pub unknown: usize,
+}
+
+/// Per-item results of a batch, in submission order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    /// The kind of requests in the batch.
+    pub kind: BatchKind,
+    /// One report per submitted item.
+    pub items: Vec<ItemReport>,
+}
+
+impl BatchReport {
+    /// Counts the items by outcome.
+    pub fn summary(&self) -> BatchSummary {
+        let mut summary = BatchSummary::default();
+        for item in &self.items {
+            match item.outcome {
+                ItemOutcome::Succeeded { .. } => summary.succeeded += 1,
+                ItemOutcome::Invalid { .. } => summary.invalid += 1,
+                ItemOutcome::Rejected { .. } => summary.rejected += 1,
+                ItemOutcome::Unknown { .. } => summary.unknown += 1,
+            }
+        }
+        summary
+    }
+
+    /// Returns the items that did not succeed.
+    pub fn failures(&self) -> impl Iterator<Item = &ItemReport> {
+        self.items
+            .iter()
+            .filter(|item| item.outcome.transaction_id().is_none())
+    }
+
+    /// Writes the report as pretty-printed JSON.
+    pub fn write_json(&self, writer: impl std::io::Write) -> Result<(), FapshiError> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// Options for a [`BatchRunner`].
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Maximum number of requests in flight at once.
+    pub concurrency: usize,
+    /// Maximum number of requests started per second, or `None` for no limit. Must be
+    /// positive; a run with any other value fails with `FapshiError::InvalidRequest`.
+    pub max_per_second: Option<f64>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            concurrency: 4,
+            max_per_second: Some(5.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum CheckpointEntry {
+    /// Written before the request is sent.
+    Submitted,
+    /// Written once the outcome is known.
+    Done { outcome: ItemOutcome },
+}
+
+/// Item states of a batch, optionally persisted as a JSON file.
+#[derive(Debug)]
+struct Checkpoint {
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<String, CheckpointEntry>>,
+}
+
+impl Checkpoint {
+    fn in_memory() -> Self {
+        Checkpoint {
+            path: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn open(path: &Path) -> Result<Self, FapshiError> {
+        let entries = match fs::read_to_string(path) {
+            Ok(contents) if contents.trim().is_empty() => HashMap::new(),
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Checkpoint {
+            path: Some(path.to_path_buf()),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Returns the outcome to report if the item must not be sent, or `None` if it may be.
+    fn recorded(&self, key: &str) -> Option<ItemOutcome> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some(CheckpointEntry::Done {
+                outcome: outcome @ (ItemOutcome::Succeeded { .. } | ItemOutcome::Unknown { .. }),
+            }) => Some(outcome.clone()),
+            Some(CheckpointEntry::Submitted) => Some(ItemOutcome::Unknown {
+                error: "submitted by an earlier run that did not record the outcome".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Marks the item as submitted. Called right before it is sent, after any rate-limit
+    /// wait, so that an item still waiting is not reported as submitted by a later run.
+    fn begin(&self, key: &str) -> Result<(), FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(key.to_string(), CheckpointEntry::Submitted);
+        self.persist(&entries)
+    }
+
+    fn finish(&self, key: &str, outcome: &ItemOutcome) -> Result<(), FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(
+            key.to_string(),
+            CheckpointEntry::Done {
+                outcome: outcome.clone(),
+            },
+        );
+        self.persist(&entries)
+    }
+
+    fn forget(&self, key: &str) -> Result<bool, FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let removed = entries.remove(key).is_some();
+        self.persist(&entries)?;
+        Ok(removed)
+    }
+
+    fn persist(&self, entries: &HashMap<String, CheckpointEntry>) -> Result<(), FapshiError> {
+        if let Some(path) = &self.path {
+            write_atomically(path, entries)?;
+        }
+        Ok(())
+    }
+}
+
+/// Spaces out request starts to respect a maximum rate.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Allows `per_second` request starts per second.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` unless `per_second` is a positive number large
+    /// enough for the interval between requests to fit in a `Duration`.
+    pub(crate) fn new(per_second: f64) -> Result<Self, FapshiError> {
+        let interval = Some(per_second)
+            .filter(|rate| *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+            .ok_or_else(|| {
+                FapshiError::InvalidRequest(format!(
+                    "rate limit must be a positive number of requests per second, got {per_second}"
+                ))
+            })?;
+        Ok(RateLimiter {
+            interval,
+            next: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Reserves the next slot and returns how long to wait before using it.
+    pub(crate) fn reserve(&self) -> Duration {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+        slot - now
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Job {
+    DirectPayment(DirectPaymentRequest),
+    Payout(Payouts),
+}
+
+impl Job {
+    fn external_id(&self) -> Option<&str> {
+        match self {
+            Job::DirectPayment(request) => request.external_id.as_deref(),
+            Job::Payout(payout) => payout.external_id.as_deref(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), FapshiError> {
+        match self {
+            Job::DirectPayment(request) => request.validate(),
+            Job::Payout(payout) => payout.validate(),
+        }
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn submit(&self, client: &FapshiClient) -> Result<String, FapshiError> {
+        match self {
+            Job::DirectPayment(request) => {
+                PaymentApi::initiate_direct_payment(client, request).map(|r| r.transaction_id)
+            }
+            Job::Payout(payout) => PayoutApi::send_payout(client, payout).map(|r| r.transaction_id),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn submit(&self, client: &FapshiClient) -> Result<String, FapshiError> {
+        match self {
+            Job::DirectPayment(request) => PaymentApi::initiate_direct_payment(client, request)
+                .await
+                .map(|r| r.transaction_id),
+            Job::Payout(payout) => PayoutApi::send_payout(client, payout)
+                .await
+                .map(|r| r.transaction_id),
+        }
+    }
+}
+
+/// An item that passed validation and may be sent.
+struct Task {
+    index: usize,
+    key: String,
+    job: Job,
+}
+
+impl Task {
+    fn report(&self, outcome: ItemOutcome, resumed: bool) -> ItemReport {
+        ItemReport {
+            index: self.index,
+            external_id: self.job.external_id().map(str::to_string),
+            outcome,
+            resumed,
+        }
+    }
+}
+
+/// Sends direct payments or payouts in bulk, at most once each.
+///
+/// Every item needs a unique `external_id`, which keys it in the checkpoint. Before an item is
+/// sent, it is marked as submitted in the checkpoint, so a re-run after a crash never sends it
+/// again: items that succeeded are reported from the checkpoint, items that might have
+/// succeeded are reported as [`ItemOutcome::Unknown`], and items that were invalid or
+/// rejected are tried again.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     batch::{BatchOptions, BatchRunner},
+///     client::FapshiClient,
+///     models::DirectPaymentRequest,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let requests: Vec<DirectPaymentRequest> = (1..=3)
+///     .map(|n| DirectPaymentRequest {
+///         amount: 500.0,
+///         phone: format!("67000000{n}"),
+///         medium: Some("mobile money".to_string()),
+///         name: None,
+///         email: None,
+///         user_id: None,
+///         external_id: Some(format!("sub-2025-01-{n}")),
+///         message: Some("January subscription".to_string()),
+///     })
+///     .collect();
+/// let runner = BatchRunner::new(BatchOptions::default()).with_checkpoint("january.json")?;
+/// let report = runner.direct_payments(&client, &requests)?;
+/// println!("{:?}", report.summary());
+/// for item in report.failures() {
+///     println!("{:?}: {:?}", item.external_id, item.outcome);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BatchRunner {
+    options: BatchOptions,
+    checkpoint: Arc<Checkpoint>,
+}
+
+impl BatchRunner {
+    /// Creates a runner whose checkpoint only lives in memory.
+    pub fn new(options: BatchOptions) -> Self {
+        BatchRunner {
+            options,
+            checkpoint: Arc::new(Checkpoint::in_memory()),
+        }
+    }
+
+    /// Persists the checkpoint to the JSON file at `path`, resuming from it if it exists.
+    pub fn with_checkpoint(mut self, path: impl AsRef<Path>) -> Result<Self, FapshiError> {
+        self.checkpoint = Arc::new(Checkpoint::open(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Removes an item from the checkpoint so that the next run sends it again.
+    ///
+    /// Only do this for [`ItemOutcome::Unknown`] items once you have checked on Fapshi that
+    /// they were not processed.
+    pub fn forget(&self, kind: BatchKind, external_id: &str) -> Result<bool, FapshiError> {
+        self.checkpoint.forget(&kind.checkpoint_key(external_id))
+    }
+
+    /// Sends direct payments synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `requests` - The direct payments to initiate, each with a unique `external_id`.
+    ///
+    /// # Returns
+    /// A `Result` containing the per-item `BatchReport`, or a `FapshiError` if the checkpoint
+    /// could not be written, in which case the remaining items are not sent.
+    #[cfg(not(feature = "async"))]
+    pub fn direct_payments(
+        &self,
+        client: &FapshiClient,
+        requests: &[DirectPaymentRequest],
+    ) -> Result<BatchReport, FapshiError> {
+        let jobs = requests.iter().cloned().map(Job::DirectPayment).collect();
+        self.run(client, BatchKind::DirectPayment, jobs)
+    }
+
+    /// Sends direct payments asynchronously.
+    ///
+    /// Requests run on tasks spawned on the current Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `requests` - The direct payments to initiate, each with a unique `external_id`.
+    ///
+    /// # Returns
+    /// A `Result` containing the per-item `BatchReport`, or a `FapshiError` if the checkpoint
+    /// could not be written, in which case the remaining items are not sent.
+    #[cfg(feature = "async")]
+    pub async fn direct_payments(
+        &self,
+        client: &FapshiClient,
+        requests: &[DirectPaymentRequest],
+    ) -> Result<BatchReport, FapshiError> {
+        let jobs = requests.iter().cloned().map(Job::DirectPayment).collect();
+        self.run(client, BatchKind::DirectPayment, jobs).await
+    }
+
+    /// Sends payouts synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `payouts` - The payouts to send, each with a unique `external_id`.
+    ///
+    /// # Returns
+    /// A `Result` containing the per-item `BatchReport`, or a `FapshiError` if the checkpoint
+    /// could not be written, in which case the remaining items are not sent.
+    #[cfg(not(feature = "async"))]
+    pub fn payouts(
+        &self,
+        client: &FapshiClient,
+        payouts: &[Payouts],
+    ) -> Result<BatchReport, FapshiError> {
+        let jobs = payouts.iter().cloned().map(Job::Payout).collect();
+        self.run(client, BatchKind::Payout, jobs)
+    }
+
+    /// Sends payouts asynchronously.
+    ///
+    /// Requests run on tasks spawned on the current Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `payouts` - The payouts to send, each with a unique `external_id`.
+    ///
+    /// # Returns
+    /// A `Result` containing the per-item `BatchReport`, or a `FapshiError` if the checkpoint
+    /// could not be written, in which case the remaining items are not sent.
+    #[cfg(feature = "async")]
+    pub async fn payouts(
+        &self,
+        client: &FapshiClient,
+        payouts: &[Payouts],
+    ) -> Result<BatchReport, FapshiError> {
+        let jobs = payouts.iter().cloned().map(Job::Payout).collect();
+        self.run(client, BatchKind::Payout, jobs).await
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn run(
+        &self,
+        client: &FapshiClient,
+        kind: BatchKind,
+        jobs: Vec<Job>,
+    ) -> Result<BatchReport, FapshiError> {
+        let (mut items, tasks) = plan(kind, jobs);
+        let limiter = self
+            .options
+            .max_per_second
+            .map(RateLimiter::new)
+            .transpose()?;
+        let queue = Mutex::new(tasks.into_iter());
+        let abort = AtomicBool::new(false);
+        let results = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.options.concurrency.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut reports = Vec::new();
+                        while !abort.load(Ordering::SeqCst) {
+                            let task = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                            let Some(task) = task else { break };
+                            let report = (|| {
+                                if let Some(outcome) = self.checkpoint.recorded(&task.key) {
+                                    return Ok(task.report(outcome, true));
+                                }
+                                if let Some(limiter) = &limiter {
+                                    std::thread::sleep(limiter.reserve());
+                                }
+                                self.checkpoint.begin(&task.key)?;
+                                let outcome = match task.job.submit(client) {
+                                    Ok(transaction_id) => ItemOutcome::Succeeded { transaction_id },
+                                    Err(err) => ItemOutcome::from_error(err),
+                                };
+                                self.checkpoint.finish(&task.key, &outcome)?;
+                                Ok(task.report(outcome, false))
+                            })();
+                            match report {
+                                Ok(report) => reports.push(report),
+                                Err(err) => {
+                                    abort.store(true, Ordering::SeqCst);
+                                    return Err(err);
+                                }
+                            }
+                        }
+                        Ok(reports)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect::<Vec<Result<Vec<ItemReport>, FapshiError>>>()
+        });
+        for result in results {
+            items.extend(result?);
+        }
+        items.sort_by_key(|item| item.index);
+        Ok(BatchReport { kind, items })
+    }
+
+    #[cfg(feature = "async")]
+    async fn run(
+        &self,
+        client: &FapshiClient,
+        kind: BatchKind,
+        jobs: Vec<Job>,
+    ) -> Result<BatchReport, FapshiError> {
+        let (mut items, tasks) = plan(kind, jobs);
+        let limiter = Arc::new(
+            self.options
+                .max_per_second
+                .map(RateLimiter::new)
+                .transpose()?,
+        );
+        let queue = Arc::new(Mutex::new(tasks.into_iter()));
+        let abort = Arc::new(AtomicBool::new(false));
+        let mut workers = tokio::task::JoinSet::new();
+        for _ in 0..self.options.concurrency.max(1) {
+            let (client, checkpoint) = (client.clone(), self.checkpoint.clone());
+            let (limiter, queue, abort) = (limiter.clone(), queue.clone(), abort.clone());
+            workers.spawn(async move {
+                let mut reports = Vec::new();
+                while !abort.load(Ordering::SeqCst) {
+                    let task = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                    let Some(task) = task else { break };
+                    if let Some(outcome) = checkpoint.recorded(&task.key) {
+                        reports.push(task.report(outcome, true));
+                        continue;
+                    }
+                    if let Some(limiter) = limiter.as_ref() {
+                        tokio::time::sleep(limiter.reserve()).await;
+                    }
+                    if let Err(err) = checkpoint.begin(&task.key) {
+                        abort.store(true, Ordering::SeqCst);
+                        return Err(err);
+                    }
+                    let outcome = match task.job.submit(&client).await {
+                        Ok(transaction_id) => ItemOutcome::Succeeded { transaction_id },
+                        Err(err) => ItemOutcome::from_error(err),
+                    };
+                    if let Err(err) = checkpoint.finish(&task.key, &outcome) {
+                        abort.store(true, Ordering::SeqCst);
+                        return Err(err);
+                    }
+                    reports.push(task.report(outcome, false));
+                }
+                Ok(reports)
+            });
+        }
+        let mut first_error = None;
+        while let Some(joined) = workers.join_next().await {
+            match joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic())) {
+                Ok(reports) => items.extend(reports),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = first_error {
+            return Err(err);
+        }
+        items.sort_by_key(|item| item.index);
+        Ok(BatchReport { kind, items })
+    }
+}
+
+/// Validates the jobs, returning reports for the items that cannot be sent and tasks for the
+/// others.
+fn plan(kind: BatchKind, jobs: Vec<Job>) -> (Vec<ItemReport>, Vec<Task>) {
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut tasks = Vec::new();
+    for (index, job) in jobs.into_iter().enumerate() {
+        let external_id = job.external_id().map(str::to_string);
+        let error = match (&external_id, job.validate()) {
+            (_, Err(err)) => Some(err.to_string()),
+            (None, Ok(())) => Some("an external_id is required for batch items".to_string()),
+            (Some(id), Ok(())) if !seen.insert(id.clone()) => Some(format!(
+                "external_id {id:?} appears more than once in the batch"
+            )),
+            _ => None,
+        };
+        match (error, external_id) {
+            (Some(error), external_id) => items.push(ItemReport {
+                index,
+                external_id,
+                outcome: ItemOutcome::Invalid { error },
+                resumed: false,
+            }),
+            (None, Some(external_id)) => tasks.push(Task {
+                index,
+                key: kind.checkpoint_key(&external_id),
+                job,
+            }),
+            (None, None) => unreachable!("items without an external_id are invalid"),
+        }
+    }
+    (items, tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    fn request(external_id: &str) -> DirectPaymentRequest {
+        DirectPaymentRequest {
+            amount: 500.0,
+            phone: "670000000".to_string(),
+            medium: None,
+            name: None,
+            email: None,
+            user_id: None,
+            external_id: Some(external_id.to_string()),
+            message: None,
+        }
+    }
+
+    fn direct_pay(external_id: &str, status: u16, body: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: "POST".to_string(),
+                path: "direct-pay".to_string(),
+                headers: BTreeMap::new(),
+                body: Some(serde_json::to_value(request(external_id)).unwrap()),
+            },
+            response: RecordedResponse {
+                status,
+                headers: BTreeMap::new(),
+                body: body.to_string(),
+            },
+        }
+    }
+
+    fn accepted(external_id: &str, transaction_id: &str) -> Interaction {
+        direct_pay(
+            external_id,
+            200,
+            &format!(
+                r#"{{"transId":"{transaction_id}","message":"Accepted","dateInitiated":"2025-01-01"}}"#
+            ),
+        )
+    }
+
+    fn client(interactions: Vec<Interaction>) -> FapshiClient {
+        FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(interactions))
+    }
+
+    fn options() -> BatchOptions {
+        BatchOptions {
+            concurrency: 1,
+            max_per_second: None,
+        }
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "fapshi-rs-batch-{}-{name}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Marks the item as submitted unless the checkpoint has an outcome to report for it.
+    fn begin(checkpoint: &Checkpoint, key: &str) -> Option<ItemOutcome> {
+        let recorded = checkpoint.recorded(key);
+        if recorded.is_none() {
+            checkpoint.begin(key).unwrap();
+        }
+        recorded
+    }
+
+    #[test]
+    fn checkpoint_only_lets_rejected_items_through_again() {
+        let checkpoint = Checkpoint::in_memory();
+        let succeeded = ItemOutcome::Succeeded {
+            transaction_id: "T1".to_string(),
+        };
+        let rejected = ItemOutcome::Rejected {
+            status: Some(400),
+            error: "bad phone".to_string(),
+        };
+
+        assert_eq!(begin(&checkpoint, "a"), None);
+        checkpoint.finish("a", &succeeded).unwrap();
+        assert_eq!(begin(&checkpoint, "a"), Some(succeeded));
+
+        assert_eq!(begin(&checkpoint, "b"), None);
+        assert!(matches!(
+            begin(&checkpoint, "b"),
+            Some(ItemOutcome::Unknown { .. })
+        ));
+
+        assert_eq!(begin(&checkpoint, "c"), None);
+        checkpoint.finish("c", &rejected).unwrap();
+        assert_eq!(begin(&checkpoint, "c"), None);
+
+        assert!(checkpoint.forget("b").unwrap());
+        assert_eq!(begin(&checkpoint, "b"), None);
+    }
+
+    #[test]
+    fn checkpoint_file_survives_reopening() {
+        let path = checkpoint_path("reopen");
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(begin(&checkpoint, "in-flight"), None);
+        drop(checkpoint);
+
+        let reopened = Checkpoint::open(&path).unwrap();
+        assert!(matches!(
+            begin(&reopened, "in-flight"),
+            Some(ItemOutcome::Unknown { .. })
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn send(
+        runner: &BatchRunner,
+        client: &FapshiClient,
+        requests: &[DirectPaymentRequest],
+    ) -> BatchReport {
+        runner.direct_payments(client, requests).unwrap()
+    }
+
+    #[cfg(feature = "async")]
+    fn send(
+        runner: &BatchRunner,
+        client: &FapshiClient,
+        requests: &[DirectPaymentRequest],
+    ) -> BatchReport {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(runner.direct_payments(client, requests))
+            .unwrap()
+    }
+
+    #[test]
+    fn rerun_resumes_from_checkpoint() {
+        let path = checkpoint_path("rerun");
+        let requests = [request("a"), request("b"), request("c")];
+
+        let first = BatchRunner::new(options()).with_checkpoint(&path).unwrap();
+        let report = send(
+            &first,
+            &client(vec![
+                accepted("a", "T1"),
+                direct_pay("b", 500, r#"{"message":"upstream timeout"}"#),
+                direct_pay("c", 400, r#"{"message":"invalid phone"}"#),
+            ]),
+            &requests,
+        );
+        let outcomes: Vec<_> = report.items.iter().map(|item| &item.outcome).collect();
+        assert_eq!(outcomes[0].transaction_id(), Some("T1"));
+        assert!(matches!(outcomes[1], ItemOutcome::Unknown { .. }));
+        assert!(matches!(
+            outcomes[2],
+            ItemOutcome::Rejected {
+                status: Some(400),
+                ..
+            }
+        ));
+
+        // Only the rejected item is sent again; the cassette has nothing else to answer.
+        let second = BatchRunner::new(options()).with_checkpoint(&path).unwrap();
+        let replay = client(vec![accepted("c", "T3")]);
+        let report = send(&second, &replay, &requests);
+        assert!(replay.cassette().unwrap().unplayed().is_empty());
+        let resumed: Vec<_> = report.items.iter().map(|item| item.resumed).collect();
+        assert_eq!(resumed, [true, true, false]);
+        assert_eq!(report.items[0].outcome.transaction_id(), Some("T1"));
+        assert!(matches!(
+            report.items[1].outcome,
+            ItemOutcome::Unknown { .. }
+        ));
+        assert_eq!(report.items[2].outcome.transaction_id(), Some("T3"));
+
+        // Once checked on Fapshi, an unknown item can be forgotten and sent again.
+        assert!(second.forget(BatchKind::DirectPayment, "b").unwrap());
+        let report = send(&second, &client(vec![accepted("b", "T2")]), &requests);
+        assert_eq!(report.items[1].outcome.transaction_id(), Some("T2"));
+        assert!(!report.items[1].resumed);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resumed_items_do_not_wait_for_the_rate_limit() {
+        let path = checkpoint_path("rate");
+        let requests = [request("a"), request("b"), request("c")];
+        let first = BatchRunner::new(options()).with_checkpoint(&path).unwrap();
+        send(
+            &first,
+            &client(vec![accepted("a", "T1"), accepted("b", "T2")]),
+            &requests[..2],
+        );
+
+        // At 2 per second, waiting for the two resumed items would take a second.
+        let limited = BatchOptions {
+            concurrency: 1,
+            max_per_second: Some(2.0),
+        };
+        let second = BatchRunner::new(limited).with_checkpoint(&path).unwrap();
+        let started = Instant::now();
+        let report = send(&second, &client(vec![accepted("c", "T3")]), &requests);
+        assert!(started.elapsed() < Duration::from_millis(400));
+        let resumed: Vec<_> = report.items.iter().map(|item| item.resumed).collect();
+        assert_eq!(resumed, [true, true, false]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn items_are_checkpointed_only_once_their_rate_limit_wait_is_over() {
+        let path = checkpoint_path("wait");
+        let limited = BatchOptions {
+            concurrency: 1,
+            max_per_second: Some(2.0),
+        };
+        let runner = BatchRunner::new(limited).with_checkpoint(&path).unwrap();
+        let client = client(vec![accepted("a", "T1"), accepted("b", "T2")]);
+        std::thread::scope(|scope| {
+            let run = scope.spawn(|| send(&runner, &client, &[request("a"), request("b")]));
+            // "b" waits until 500 ms for its slot.
+            std::thread::sleep(Duration::from_millis(250));
+            let entries: HashMap<String, CheckpointEntry> =
+                serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(
+                run.join().unwrap().items[1].outcome.transaction_id(),
+                Some("T2")
+            );
+        });
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rate_limiter_spaces_out_slots_and_rejects_invalid_rates() {
+        let limiter = RateLimiter::new(10.0).unwrap();
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        let wait = limiter.reserve();
+        assert!(wait > Duration::from_millis(90) && wait <= Duration::from_millis(100));
+
+        for rate in [0.0, -1.0, f64::NAN, f64::MIN_POSITIVE] {
+            assert!(matches!(
+                RateLimiter::new(rate),
+                Err(FapshiError::InvalidRequest(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,556 @@
+//! `fapshi` command-line tool.
+//!
+//! Wraps the SDK's APIs so payments can be checked and acted on from a shell. Credentials are
+//! read from `--api-user`/`--api-key`, the `FAPSHI_API_USER`/`FAPSHI_API_KEY` environment
+//! variables, or a `.env` file.
+//!
+//! Commands run against the sandbox unless `--live` is given.
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use fapshi_rs::{
+    api::{
+        balance::BalanceApi, payment::PaymentApi, payout::PayoutApi, transaction::TransactionApi,
+    },
+    client::FapshiClient,
+    endpoint::catalogue,
+    error::FapshiError,
+    models::{
+        DirectPaymentRequest, PaymentRequest, Payouts, Status, TransactionSearchQuery,
+        TransactionStatus,
+    },
+    safety::{AmountLimits, Environment},
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Runs an SDK call to completion, whichever client flavour is compiled in.
+#[cfg(not(feature = "async"))]
+macro_rules! wait {
+    ($call:expr) => {
+        $call
+    };
+}
+
+/// Runs an SDK call to completion, whichever client flavour is compiled in.
+#[cfg(feature = "async")]
+macro_rules! wait {
+    ($call:expr) => {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on($call)
+    };
+}
+
+#[derive(Parser)]
+#[command(
+    name = "fapshi",
+    version,
+    about = "Command-line client for the Fapshi payment API"
+)]
+struct Cli {
+    /// Use the Fapshi sandbox (the default).
+    #[arg(long, global = true, conflicts_with = "live")]
+    sandbox: bool,
+
+    /// Use the live environment, where real money moves.
+    #[arg(long, global = true)]
+    live: bool,
+
+    /// Validate requests and print them as curl commands instead of sending them.
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Refuse live requests carrying more than this amount.
+    #[arg(long, global = true, value_name = "AMOUNT")]
+    max_amount: Option<f64>,
+
+    /// Refuse live requests once this total would be exceeded. The total is only kept for
+    /// the duration of the command.
+    #[arg(long, global = true, value_name = "AMOUNT")]
+    max_daily_amount: Option<f64>,
+
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+
+    /// Fapshi API user.
+    #[arg(long, env = "FAPSHI_API_USER", hide_env_values = true, global = true)]
+    api_user: Option<String>,
+
+    /// Fapshi API key.
+    #[arg(long, env = "FAPSHI_API_KEY", hide_env_values = true, global = true)]
+    api_key: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage payment links.
+    #[command(subcommand)]
+    Link(LinkCommand),
+    /// Request a payment directly on a customer's phone.
+    DirectPay(DirectPayArgs),
+    /// Show the status of a transaction.
+    Status {
+        /// The transaction ID.
+        trans_id: String,
+    },
+    /// Expire a transaction so it can no longer be paid.
+    Expire {
+        /// The transaction ID.
+        trans_id: String,
+    },
+    /// List the transactions of a user.
+    UserTxns {
+        /// The user ID given when the payments were created.
+        user_id: String,
+    },
+    /// Search transactions.
+    Search(SearchArgs),
+    /// Show the service balance.
+    Balance,
+    /// Send money to a mobile money or Orange money account.
+    Payout(PayoutArgs),
+    /// List the Fapshi API operations supported by the SDK.
+    Endpoints,
+}
+
+#[derive(Subcommand)]
+enum LinkCommand {
+    /// Create a payment link.
+    Create(LinkArgs),
+}
+
+#[derive(Args)]
+struct LinkArgs {
+    /// Amount to collect (at least 100).
+    #[arg(long)]
+    amount: f64,
+    /// Reason for the payment.
+    #[arg(long)]
+    message: String,
+    /// Payer email, so they are not asked for it.
+    #[arg(long)]
+    email: Option<String>,
+    /// URL to redirect the payer to after payment.
+    #[arg(long)]
+    redirect_url: Option<String>,
+    /// User ID in your system.
+    #[arg(long)]
+    user_id: Option<String>,
+    /// Order or reference ID in your system.
+    #[arg(long)]
+    external_id: Option<String>,
+    /// Only offer international card payments.
+    #[arg(long)]
+    card_only: bool,
+}
+
+#[derive(Args)]
+struct DirectPayArgs {
+    /// Amount to collect (at least 100).
+    #[arg(long)]
+    amount: f32,
+    /// Phone number to charge (e.g., 67XXXXXXX).
+    #[arg(long)]
+    phone: String,
+    /// Payment medium; detected from the phone number if omitted.
+    #[arg(long, value_enum)]
+    medium: Option<Medium>,
+    /// Payer name.
+    #[arg(long)]
+    name: Option<String>,
+    /// Payer email.
+    #[arg(long)]
+    email: Option<String>,
+    /// User ID in your system.
+    #[arg(long)]
+    user_id: Option<String>,
+    /// Order or reference ID in your system.
+    #[arg(long)]
+    external_id: Option<String>,
+    /// Reason for the payment.
+    #[arg(long)]
+    message: Option<String>,
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Transaction status.
+    #[arg(long, value_enum)]
+    status: Option<StatusArg>,
+    /// Payment medium.
+    #[arg(long, value_enum)]
+    medium: Option<Medium>,
+    /// Payer name.
+    #[arg(long)]
+    name: Option<String>,
+    /// Start date (yyyy-mm-dd).
+    #[arg(long)]
+    start: Option<String>,
+    /// End date (yyyy-mm-dd).
+    #[arg(long)]
+    end: Option<String>,
+    /// Exact amount.
+    #[arg(long)]
+    amount: Option<f64>,
+    /// Maximum number of transactions.
+    #[arg(long)]
+    limit: Option<u32>,
+    /// Sort order by date.
+    #[arg(long, value_parser = ["asc", "desc"])]
+    sort: Option<String>,
+}
+
+#[derive(Args)]
+struct PayoutArgs {
+    /// Amount to send.
+    #[arg(long)]
+    amount: f64,
+    /// Recipient phone number (e.g., 67XXXXXXX).
+    #[arg(long)]
+    phone: String,
+    /// Payment medium.
+    #[arg(long, value_enum)]
+    medium: Medium,
+    /// Recipient name.
+    #[arg(long)]
+    name: Option<String>,
+    /// Recipient email.
+    #[arg(long)]
+    email: Option<String>,
+    /// User ID in your system.
+    #[arg(long)]
+    user_id: Option<String>,
+    /// Order or reference ID in your system.
+    #[arg(long)]
+    external_id: Option<String>,
+    /// Reason for the payout.
+    #[arg(long)]
+    message: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Medium {
+    MobileMoney,
+    OrangeMoney,
+}
+
+impl Medium {
+    fn as_api_str(self) -> &'static str {
+        match self {
+            Medium::MobileMoney => "mobile money",
+            Medium::OrangeMoney => "orange money",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatusArg {
+    Created,
+    Pending,
+    Successful,
+    Failed,
+    Expired,
+}
+
+impl From<StatusArg> for Status {
+    fn from(status: StatusArg) -> Self {
+        match status {
+            StatusArg::Created => Status::CREATED,
+            StatusArg::Pending => Status::PENDING,
+            StatusArg::Successful => Status::SUCCESSFUL,
+            StatusArg::Failed => Status::FAILED,
+            StatusArg::Expired => Status::EXPIRED,
+        }
+    }
+}
+
+/// The result of a command, renderable in every output format.
+struct Output {
+    json: Value,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Output {
+    fn record<T: Serialize>(
+        value: &T,
+        fields: &[(&'static str, String)],
+    ) -> Result<Self, FapshiError> {
+        Ok(Output {
+            json: serde_json::to_value(value)?,
+            headers: fields.iter().map(|(header, _)| *header).collect(),
+            rows: vec![fields.iter().map(|(_, value)| value.clone()).collect()],
+        })
+    }
+
+    fn transactions(transactions: &[TransactionStatus]) -> Result<Self, FapshiError> {
+        Ok(Output {
+            json: serde_json::to_value(transactions)?,
+            headers: vec![
+                "transId",
+                "status",
+                "medium",
+                "amount",
+                "revenue",
+                "payerName",
+                "externalId",
+                "userId",
+                "dateInitiated",
+                "dateConfirmed",
+            ],
+            rows: transactions.iter().map(transaction_row).collect(),
+        })
+    }
+
+    fn print(&self, format: Format) -> Result<(), FapshiError> {
+        match format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&self.json)?),
+            Format::Csv => {
+                println!("{}", csv_line(self.headers.iter().copied()));
+                for row in &self.rows {
+                    println!("{}", csv_line(row.iter().map(String::as_str)));
+                }
+            }
+            Format::Table => self.print_table(),
+        }
+        Ok(())
+    }
+
+    fn print_table(&self) {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        let headers: Vec<String> = self.headers.iter().map(|h| h.to_string()).collect();
+        let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        println!("{}", line(&headers));
+        println!("{}", line(&separator));
+        for row in &self.rows {
+            println!("{}", line(row));
+        }
+    }
+}
+
+fn transaction_row(tx: &TransactionStatus) -> Vec<String> {
+    vec![
+        tx.transaction_id.clone(),
+        tx.status.to_string(),
+        tx.medium.clone().unwrap_or_default(),
+        tx.amount.to_string(),
+        tx.revenue.map(|r| r.to_string()).unwrap_or_default(),
+        tx.payer_name.clone().unwrap_or_default(),
+        tx.external_id.clone().unwrap_or_default(),
+        tx.user_id.clone().unwrap_or_default(),
+        tx.date_initiated.clone(),
+        tx.date_confirmed.clone(),
+    ]
+}
+
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn client(cli: &Cli) -> Result<FapshiClient, FapshiError> {
+    let (Some(api_user), Some(api_key)) = (&cli.api_user, &cli.api_key) else {
+        return Err(FapshiError::InvalidRequest(
+            "set FAPSHI_API_USER and FAPSHI_API_KEY or pass --api-user and --api-key".to_string(),
+        ));
+    };
+    let environment = if cli.live {
+        Environment::Live
+    } else {
+        Environment::Sandbox
+    };
+    let mut client =
+        FapshiClient::with_environment(api_user, api_key, environment)?.with_dry_run(cli.dry_run);
+    if cli.max_amount.is_some() || cli.max_daily_amount.is_some() {
+        client = client.with_amount_limits(AmountLimits {
+            per_request: cli.max_amount,
+            per_day: cli.max_daily_amount,
+        });
+    }
+    Ok(client)
+}
+
+fn run(cli: &Cli) -> Result<Output, Box<dyn std::error::Error>> {
+    let output = match &cli.command {
+        Command::Endpoints => {
+            let endpoints = catalogue();
+            Output {
+                json: Value::Array(
+                    endpoints
+                        .iter()
+                        .map(|e| json!({ "name": e.name, "method": e.method.as_str(), "path": e.path }))
+                        .collect(),
+                ),
+                headers: vec!["name", "method", "path"],
+                rows: endpoints
+                    .iter()
+                    .map(|e| vec![e.name.to_string(), e.method.to_string(), e.path.to_string()])
+                    .collect(),
+            }
+        }
+        Command::Link(LinkCommand::Create(args)) => {
+            let request = PaymentRequest {
+                amount: args.amount,
+                email: args.email.clone(),
+                redirect_url: args.redirect_url.clone(),
+                user_id: args.user_id.clone(),
+                external_id: args.external_id.clone(),
+                message: args.message.clone(),
+                card_only: args.card_only.then_some(true),
+            };
+            let response = wait!(PaymentApi::create_payment(&client(cli)?, &request))?;
+            Output::record(
+                &response,
+                &[
+                    ("transId", response.transaction_id.clone()),
+                    ("link", response.payment_link.clone()),
+                    ("dateInitiated", response.date_initiated.clone()),
+                    ("message", response.message.clone()),
+                ],
+            )?
+        }
+        Command::DirectPay(args) => {
+            let request = DirectPaymentRequest {
+                amount: args.amount,
+                phone: args.phone.clone(),
+                medium: args.medium.map(|m| m.as_api_str().to_string()),
+                name: args.name.clone(),
+                email: args.email.clone(),
+                user_id: args.user_id.clone(),
+                external_id: args.external_id.clone(),
+                message: args.message.clone(),
+            };
+            let response = wait!(PaymentApi::initiate_direct_payment(&client(cli)?, &request))?;
+            Output::record(
+                &response,
+                &[
+                    ("transId", response.transaction_id.clone()),
+                    ("dateInitiated", response.date_initiated.clone()),
+                    ("message", response.message.clone()),
+                ],
+            )?
+        }
+        Command::Status { trans_id } => {
+            let status = wait!(TransactionApi::get_status(&client(cli)?, trans_id))?;
+            Output::transactions(&[status])?
+        }
+        Command::Expire { trans_id } => {
+            wait!(TransactionApi::expire_transaction(&client(cli)?, trans_id))?;
+            let result = json!({ "transId": trans_id, "expired": true });
+            Output::record(
+                &result,
+                &[
+                    ("transId", trans_id.clone()),
+                    ("expired", "true".to_string()),
+                ],
+            )?
+        }
+        Command::UserTxns { user_id } => {
+            let transactions = wait!(TransactionApi::get_transactions_by_user_id(
+                &client(cli)?,
+                user_id
+            ))?;
+            Output::transactions(&transactions)?
+        }
+        Command::Search(args) => {
+            let query = TransactionSearchQuery {
+                status: args.status.map(Status::from),
+                medium: args.medium.map(|m| m.as_api_str().to_string()),
+                name: args.name.clone(),
+                start: args.start.clone(),
+                end: args.end.clone(),
+                amt: args.amount,
+                limit: args.limit,
+                sort: args.sort.clone(),
+            };
+            let transactions = wait!(TransactionApi::search_transactions(&client(cli)?, &query))?;
+            Output::transactions(&transactions)?
+        }
+        Command::Balance => {
+            let balance = wait!(BalanceApi::get_service_balance(&client(cli)?))?;
+            Output::record(
+                &balance,
+                &[
+                    ("balance", balance.balance.to_string()),
+                    ("currency", balance.currency.clone()),
+                ],
+            )?
+        }
+        Command::Payout(args) => {
+            let payout = Payouts {
+                amount: args.amount,
+                phone_number: args.phone.clone(),
+                medium: args.medium.as_api_str().to_string(),
+                name: args.name.clone(),
+                email: args.email.clone(),
+                user_id: args.user_id.clone(),
+                external_id: args.external_id.clone(),
+                message: args.message.clone(),
+            };
+            let response = wait!(PayoutApi::send_payout(&client(cli)?, &payout))?;
+            Output::record(
+                &response,
+                &[
+                    ("transId", response.transaction_id.clone()),
+                    ("dateInitiated", response.date_initiated.clone()),
+                    ("message", response.message.clone()),
+                ],
+            )?
+        }
+    };
+    Ok(output)
+}
+
+fn main() -> ExitCode {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    match run(&cli).and_then(|output| Ok(output.print(cli.format)?)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => match err.downcast_ref() {
+            Some(FapshiError::DryRun(preview)) => {
+                println!("{}", preview.to_curl());
+                ExitCode::SUCCESS
+            }
+            _ => {
+                eprintln!("error: {err}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
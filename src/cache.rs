@@ -0,0 +1,913 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::transaction::TransactionApi,
+    client::FapshiClient,
+    error::FapshiError,
+    models::{Status, TransactionSearchQuery, TransactionStatus},
+    store::JsonFile,
+};
+
+/// A transaction kept in a [`TransactionStore`], with the time it was last read from Fapshi.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedTransaction {
+    /// The transaction as Fapshi last reported it.
+    pub transaction: TransactionStatus,
+    /// When it was read from Fapshi.
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl CachedTransaction {
+    /// Returns whether the transaction has a final status (successful, failed or expired),
+    /// which never changes again.
+    pub fn is_final(&self) -> bool {
+        !matches!(self.transaction.status, Status::CREATED | Status::PENDING)
+    }
+}
+
+/// Storage for cached transactions, keyed by transaction ID, and the incremental sync cursor.
+///
+/// Implement this trait to keep the cache in your own database.
+pub trait TransactionStore: Send + Sync {
+    /// Returns the cached transaction with the given ID, if any.
+    fn get(&self, transaction_id: &str) -> Result<Option<CachedTransaction>, FapshiError>;
+
+    /// Inserts or replaces transactions.
+    fn put_all(&self, transactions: &[CachedTransaction]) -> Result<(), FapshiError>;
+
+    /// Returns all cached transactions.
+    fn list(&self) -> Result<Vec<CachedTransaction>, FapshiError>;
+
+    /// Returns the last day fully synced by [`TransactionCache::sync`], if any.
+    fn sync_cursor(&self) -> Result<Option<NaiveDate>, FapshiError>;
+
+    /// Records the last day fully synced.
+    fn set_sync_cursor(&self, date: NaiveDate) -> Result<(), FapshiError>;
+}
+
+/// The contents of a store.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Contents {
+    transactions: BTreeMap<String, CachedTransaction>,
+    sync_cursor: Option<NaiveDate>,
+}
+
+impl Contents {
+    /// Inserts or replaces transactions, returning whether any was inserted or replaced.
+    fn insert(&mut self, transactions: &[CachedTransaction]) -> bool {
+        for cached in transactions {
+            self.transactions
+                .insert(cached.transaction.transaction_id.clone(), cached.clone());
+        }
+        !transactions.is_empty()
+    }
+}
+
+/// A `TransactionStore` kept in memory for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct InMemoryTransactionStore {
+    contents: Mutex<Contents>,
+}
+
+impl InMemoryTransactionStore {
+    /// Creates an empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn contents(&self) -> std::sync::MutexGuard<'_, Contents> {
+        self.contents.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl TransactionStore for InMemoryTransactionStore {
+    fn get(&self, transaction_id: &str) -> Result<Option<CachedTransaction>, FapshiError> {
+        Ok(self.contents().transactions.get(transaction_id).cloned())
+    }
+
+    fn put_all(&self, transactions: &[CachedTransaction]) -> Result<(), FapshiError> {
+        self.contents().insert(transactions);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<CachedTransaction>, FapshiError> {
+        Ok(self.contents().transactions.values().cloned().collect())
+    }
+
+    fn sync_cursor(&self) -> Result<Option<NaiveDate>, FapshiError> {
+        Ok(self.contents().sync_cursor)
+    }
+
+    fn set_sync_cursor(&self, date: NaiveDate) -> Result<(), FapshiError> {
+        self.contents().sync_cursor = Some(date);
+        Ok(())
+    }
+}
+
+/// A `TransactionStore` persisted as a JSON file, surviving process restarts.
+///
+/// The cache is kept in memory once read, and the file is rewritten atomically (write to a
+/// temporary file, then rename) only when a write changes it. Do not share the file between
+/// processes.
+#[derive(Debug)]
+pub struct FileTransactionStore {
+    file: JsonFile<Contents>,
+}
+
+impl FileTransactionStore {
+    /// Creates a store backed by the file at `path`. The file is created on first write.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileTransactionStore {
+            file: JsonFile::new(path),
+        }
+    }
+}
+
+impl TransactionStore for FileTransactionStore {
+    fn get(&self, transaction_id: &str) -> Result<Option<CachedTransaction>, FapshiError> {
+        self.file
+            .read(|contents| contents.transactions.get(transaction_id).cloned())
+    }
+
+    fn put_all(&self, transactions: &[CachedTransaction]) -> Result<(), FapshiError> {
+        self.file.update(|contents| contents.insert(transactions))?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<CachedTransaction>, FapshiError> {
+        self.file
+            .read(|contents| contents.transactions.values().cloned().collect())
+    }
+
+    fn sync_cursor(&self) -> Result<Option<NaiveDate>, FapshiError> {
+        self.file.read(|contents| contents.sync_cursor)
+    }
+
+    fn set_sync_cursor(&self, date: NaiveDate) -> Result<(), FapshiError> {
+        self.file.update(|contents| {
+            let changed = contents.sync_cursor != Some(date);
+            contents.sync_cursor = Some(date);
+            changed
+        })?;
+        Ok(())
+    }
+}
+
+/// Counts from a [`TransactionCache::sync`] or [`TransactionCache::refresh_stale`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The number of days searched.
+    pub days: u32,
+    /// The number of transactions read from Fapshi.
+    pub fetched: usize,
+    /// The number of transactions new to the cache.
+    pub inserted: usize,
+    /// The number of cached transactions whose record changed.
+    pub updated: usize,
+}
+
+/// A local cache of Fapshi transactions, so dashboards and repeated lookups read locally.
+///
+/// Transactions with a final status are immutable: once cached they are served from the
+/// cache and never overwritten. Transactions still `CREATED` or `PENDING` are served from the cache
+/// until they are older than the refresh interval, then fetched again on the next lookup or
+/// by [`TransactionCache::refresh_stale`]. [`TransactionCache::sync`] pulls new transactions
+/// day by day with the search endpoint, resuming from the last synced day.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use chrono::NaiveDate;
+/// use fapshi_rs::{
+///     cache::{FileTransactionStore, TransactionCache},
+///     client::FapshiClient,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let cache = TransactionCache::new(FileTransactionStore::new("transactions.json"))
+///     .with_refresh_interval(Duration::from_secs(60))
+///     .with_sync_start(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+/// // Run from your cron, e.g. every few minutes:
+/// let report = cache.sync(&client)?;
+/// cache.refresh_stale(&client)?;
+/// println!("{} new transactions", report.inserted);
+/// // Then read locally:
+/// let january = cache.initiated_between(
+///     NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+///     NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+/// )?;
+/// println!("{} transactions in January", january.len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct TransactionCache<S> {
+    store: S,
+    refresh_interval: Duration,
+    sync_start: Option<NaiveDate>,
+    user_fetches: Mutex<HashMap<String, DateTime<Utc>>>,
+    error_callbacks: Vec<ErrorCallback>,
+}
+
+type ErrorCallback = Box<dyn Fn(&FapshiError) + Send + Sync>;
+
+impl<S: fmt::Debug> fmt::Debug for TransactionCache<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionCache")
+            .field("store", &self.store)
+            .field("refresh_interval", &self.refresh_interval)
+            .field("sync_start", &self.sync_start)
+            .field("user_fetches", &self.user_fetches)
+            .field("error_callbacks", &self.error_callbacks.len())
+            .finish()
+    }
+}
+
+impl<S: TransactionStore> TransactionCache<S> {
+    /// Creates a cache on top of `store`, refreshing open transactions after 5 minutes.
+    pub fn new(store: S) -> Self {
+        TransactionCache {
+            store,
+            refresh_interval: Duration::from_secs(300),
+            sync_start: None,
+            user_fetches: Mutex::new(HashMap::new()),
+            error_callbacks: Vec::new(),
+        }
+    }
+
+    /// Sets how long a `CREATED` or `PENDING` transaction, or a user's transaction list, is
+    /// served from the cache before being fetched again.
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Sets the first day [`TransactionCache::sync`] searches when nothing was synced yet.
+    /// Defaults to the current day.
+    pub fn with_sync_start(mut self, date: NaiveDate) -> Self {
+        self.sync_start = Some(date);
+        self
+    }
+
+    /// Registers a callback invoked when a sync or refresh fails in [`TransactionCache::run`].
+    pub fn on_error(mut self, callback: impl Fn(&FapshiError) + Send + Sync + 'static) -> Self {
+        self.error_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns a cached transaction without calling Fapshi.
+    pub fn cached(&self, transaction_id: &str) -> Result<Option<TransactionStatus>, FapshiError> {
+        Ok(self
+            .store
+            .get(transaction_id)?
+            .map(|cached| cached.transaction))
+    }
+
+    /// Returns the cached transactions of a user without calling Fapshi, oldest first.
+    pub fn cached_for_user(&self, user_id: &str) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.select(|tx| tx.user_id.as_deref() == Some(user_id))
+    }
+
+    /// Returns the cached transactions initiated between `start` and `end` (inclusive, UTC)
+    /// without calling Fapshi, oldest first.
+    pub fn initiated_between(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.select(|tx| {
+            tx.initiated_at()
+                .is_some_and(|at| (start..=end).contains(&at.date_naive()))
+        })
+    }
+
+    /// Returns the status of a transaction, from the cache when it is final or fresh.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `transaction_id` - The unique ID of the transaction.
+    #[cfg(not(feature = "async"))]
+    pub fn get_status(
+        &self,
+        client: &FapshiClient,
+        transaction_id: &str,
+    ) -> Result<TransactionStatus, FapshiError> {
+        if let Some(cached) = self.usable(transaction_id)? {
+            return Ok(cached);
+        }
+        let fetched = TransactionApi::get_status(client, transaction_id)?;
+        self.merge(vec![fetched.clone()], &mut SyncReport::default())?;
+        Ok(fetched)
+    }
+
+    /// Returns the status of a transaction, from the cache when it is final or fresh,
+    /// asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `transaction_id` - The unique ID of the transaction.
+    #[cfg(feature = "async")]
+    pub async fn get_status(
+        &self,
+        client: &FapshiClient,
+        transaction_id: &str,
+    ) -> Result<TransactionStatus, FapshiError> {
+        if let Some(cached) = self.usable(transaction_id)? {
+            return Ok(cached);
+        }
+        let fetched = TransactionApi::get_status(client, transaction_id).await?;
+        self.merge(vec![fetched.clone()], &mut SyncReport::default())?;
+        Ok(fetched)
+    }
+
+    /// Returns the transactions of a user, fetching them from Fapshi at most once per refresh
+    /// interval.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `user_id` - The user ID whose transactions are requested.
+    #[cfg(not(feature = "async"))]
+    pub fn get_transactions_by_user_id(
+        &self,
+        client: &FapshiClient,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        if !self.user_is_fresh(user_id) {
+            let fetched = TransactionApi::get_transactions_by_user_id(client, user_id)?;
+            self.merge(fetched, &mut SyncReport::default())?;
+            self.mark_user_fetched(user_id);
+        }
+        self.cached_for_user(user_id)
+    }
+
+    /// Returns the transactions of a user, fetching them from Fapshi at most once per refresh
+    /// interval, asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `user_id` - The user ID whose transactions are requested.
+    #[cfg(feature = "async")]
+    pub async fn get_transactions_by_user_id(
+        &self,
+        client: &FapshiClient,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        if !self.user_is_fresh(user_id) {
+            let fetched = TransactionApi::get_transactions_by_user_id(client, user_id).await?;
+            self.merge(fetched, &mut SyncReport::default())?;
+            self.mark_user_fetched(user_id);
+        }
+        self.cached_for_user(user_id)
+    }
+
+    /// Fetches the cached `CREATED` and `PENDING` transactions older than the refresh
+    /// interval.
+    ///
+    /// # Errors
+    /// Stops at the first failed request; transactions refreshed before it stay updated.
+    #[cfg(not(feature = "async"))]
+    pub fn refresh_stale(&self, client: &FapshiClient) -> Result<SyncReport, FapshiError> {
+        let mut report = SyncReport::default();
+        for transaction_id in self.stale()? {
+            let fetched = TransactionApi::get_status(client, &transaction_id)?;
+            report.fetched += 1;
+            self.merge(vec![fetched], &mut report)?;
+        }
+        Ok(report)
+    }
+
+    /// Fetches the cached `CREATED` and `PENDING` transactions older than the refresh
+    /// interval, asynchronously.
+    ///
+    /// # Errors
+    /// Stops at the first failed request; transactions refreshed before it stay updated.
+    #[cfg(feature = "async")]
+    pub async fn refresh_stale(&self, client: &FapshiClient) -> Result<SyncReport, FapshiError> {
+        let mut report = SyncReport::default();
+        for transaction_id in self.stale()? {
+            let fetched = TransactionApi::get_status(client, &transaction_id).await?;
+            report.fetched += 1;
+            self.merge(vec![fetched], &mut report)?;
+        }
+        Ok(report)
+    }
+
+    /// Pulls the transactions initiated since the last sync, one day per search request.
+    ///
+    /// Searches from the last synced day (searched again, as it may have been incomplete), or
+    /// the sync start, through today, and moves the sync cursor after each day. A day that
+    /// fills a search page (100 transactions) is searched again one status at a time.
+    ///
+    /// # Errors
+    /// Stops at the first failed request; the next sync resumes from the last completed day.
+    /// Returns `FapshiError::InvalidRequest`, without moving the cursor past the day, if more
+    /// than 100 transactions of one status were initiated on the same day.
+    #[cfg(not(feature = "async"))]
+    pub fn sync(&self, client: &FapshiClient) -> Result<SyncReport, FapshiError> {
+        let mut report = SyncReport::default();
+        for day in self.days_to_sync()? {
+            TransactionApi::search_pages(client, &day_query(day), |fetched| {
+                report.fetched += fetched.len();
+                self.merge(fetched, &mut report)
+            })?;
+            report.days += 1;
+            self.store.set_sync_cursor(day)?;
+        }
+        Ok(report)
+    }
+
+    /// Pulls the transactions initiated since the last sync, one day per search request,
+    /// asynchronously.
+    ///
+    /// Searches from the last synced day (searched again, as it may have been incomplete), or
+    /// the sync start, through today, and moves the sync cursor after each day. A day that
+    /// fills a search page (100 transactions) is searched again one status at a time.
+    ///
+    /// # Errors
+    /// Stops at the first failed request; the next sync resumes from the last completed day.
+    /// Returns `FapshiError::InvalidRequest`, without moving the cursor past the day, if more
+    /// than 100 transactions of one status were initiated on the same day.
+    #[cfg(feature = "async")]
+    pub async fn sync(&self, client: &FapshiClient) -> Result<SyncReport, FapshiError> {
+        let mut report = SyncReport::default();
+        for day in self.days_to_sync()? {
+            TransactionApi::search_pages(client, &day_query(day), |fetched| {
+                report.fetched += fetched.len();
+                self.merge(fetched, &mut report)
+            })
+            .await?;
+            report.days += 1;
+            self.store.set_sync_cursor(day)?;
+        }
+        Ok(report)
+    }
+
+    /// Syncs and refreshes stale transactions every `interval`, forever, blocking the current
+    /// thread.
+    ///
+    /// Failures are passed to the [`TransactionCache::on_error`] callbacks and retried on the
+    /// next tick.
+    #[cfg(not(feature = "async"))]
+    pub fn run(&self, client: &FapshiClient, interval: Duration) {
+        loop {
+            if let Err(err) = self.sync(client) {
+                self.report_error(&err);
+            }
+            if let Err(err) = self.refresh_stale(client) {
+                self.report_error(&err);
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Syncs and refreshes stale transactions every `interval`, forever.
+    ///
+    /// Failures are passed to the [`TransactionCache::on_error`] callbacks and retried on the
+    /// next tick.
+    #[cfg(feature = "async")]
+    pub async fn run(&self, client: &FapshiClient, interval: Duration) {
+        loop {
+            if let Err(err) = self.sync(client).await {
+                self.report_error(&err);
+            }
+            if let Err(err) = self.refresh_stale(client).await {
+                self.report_error(&err);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    fn report_error(&self, err: &FapshiError) {
+        for callback in &self.error_callbacks {
+            callback(err);
+        }
+    }
+
+    /// Writes fetched transactions to the store, leaving cached final ones untouched.
+    fn merge(
+        &self,
+        fetched: Vec<TransactionStatus>,
+        report: &mut SyncReport,
+    ) -> Result<(), FapshiError> {
+        if fetched.is_empty() {
+            return Ok(());
+        }
+        let mut cached: HashMap<String, CachedTransaction> = self
+            .store
+            .list()?
+            .into_iter()
+            .map(|cached| (cached.transaction.transaction_id.clone(), cached))
+            .collect();
+        let now = Utc::now();
+        let mut changes = Vec::new();
+        for transaction in fetched {
+            match cached.remove(&transaction.transaction_id) {
+                Some(cached) if cached.is_final() => continue,
+                Some(cached) => {
+                    if serde_json::to_value(&cached.transaction)?
+                        != serde_json::to_value(&transaction)?
+                    {
+                        report.updated += 1;
+                    }
+                }
+                None => report.inserted += 1,
+            }
+            changes.push(CachedTransaction {
+                transaction,
+                fetched_at: now,
+            });
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.store.put_all(&changes)
+    }
+
+    /// Returns the cached transaction if it is final or was fetched recently.
+    fn usable(&self, transaction_id: &str) -> Result<Option<TransactionStatus>, FapshiError> {
+        Ok(self
+            .store
+            .get(transaction_id)?
+            .filter(|cached| cached.is_final() || self.is_fresh(cached.fetched_at))
+            .map(|cached| cached.transaction))
+    }
+
+    /// Returns the IDs of the open transactions due for a refresh.
+    fn stale(&self) -> Result<Vec<String>, FapshiError> {
+        Ok(self
+            .store
+            .list()?
+            .into_iter()
+            .filter(|cached| !cached.is_final() && !self.is_fresh(cached.fetched_at))
+            .map(|cached| cached.transaction.transaction_id)
+            .collect())
+    }
+
+    fn is_fresh(&self, fetched_at: DateTime<Utc>) -> bool {
+        let age = Utc::now() - fetched_at;
+        age < TimeDelta::from_std(self.refresh_interval).unwrap_or(TimeDelta::MAX)
+    }
+
+    fn user_is_fresh(&self, user_id: &str) -> bool {
+        let fetches = self.user_fetches.lock().unwrap_or_else(|e| e.into_inner());
+        fetches.get(user_id).is_some_and(|at| self.is_fresh(*at))
+    }
+
+    fn mark_user_fetched(&self, user_id: &str) {
+        let mut fetches = self.user_fetches.lock().unwrap_or_else(|e| e.into_inner());
+        fetches.insert(user_id.to_string(), Utc::now());
+    }
+
+    fn days_to_sync(&self) -> Result<Vec<NaiveDate>, FapshiError> {
+        let today = Utc::now().date_naive();
+        let mut day = self
+            .store
+            .sync_cursor()?
+            .or(self.sync_start)
+            .unwrap_or(today);
+        let mut days = Vec::new();
+        while day <= today {
+            days.push(day);
+            match day.checked_add_days(Days::new(1)) {
+                Some(next) => day = next,
+                None => break,
+            }
+        }
+        Ok(days)
+    }
+
+    fn select(
+        &self,
+        filter: impl Fn(&TransactionStatus) -> bool,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        let mut selected: Vec<TransactionStatus> = self
+            .store
+            .list()?
+            .into_iter()
+            .map(|cached| cached.transaction)
+            .filter(|tx| filter(tx))
+            .collect();
+        selected.sort_by_key(|tx| tx.initiated_at());
+        Ok(selected)
+    }
+}
+
+/// The search for the transactions of one day.
+fn day_query(day: NaiveDate) -> TransactionSearchQuery {
+    let day = day.format("%Y-%m-%d").to_string();
+    TransactionSearchQuery {
+        start: Some(day.clone()),
+        end: Some(day),
+        sort: Some("asc".to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    fn transaction(transaction_id: &str, status: &str) -> Value {
+        json!({
+            "transId": transaction_id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": 500.0,
+            "email": "",
+            "redirectUrl": "",
+            "userId": "customer42",
+            "dateInitiated": "2025-01-31",
+            "dateConfirmed": "",
+        })
+    }
+
+    fn get(path: &str, response: Value) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: "GET".to_string(),
+                path: path.to_string(),
+                headers: BTreeMap::new(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: response.to_string(),
+            },
+        }
+    }
+
+    fn client(interactions: Vec<Interaction>) -> FapshiClient {
+        FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(interactions))
+    }
+
+    /// A cache whose open transactions are always refetched.
+    fn cache() -> TransactionCache<InMemoryTransactionStore> {
+        TransactionCache::new(InMemoryTransactionStore::new()).with_refresh_interval(Duration::ZERO)
+    }
+
+    fn status_of(
+        cache: &TransactionCache<InMemoryTransactionStore>,
+        transaction_id: &str,
+    ) -> Status {
+        cache.cached(transaction_id).unwrap().unwrap().status
+    }
+
+    #[cfg(not(feature = "async"))]
+    mod calls {
+        use super::*;
+
+        pub fn get_status(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+            id: &str,
+        ) -> Result<TransactionStatus, FapshiError> {
+            cache.get_status(client, id)
+        }
+
+        pub fn by_user(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> Vec<TransactionStatus> {
+            cache
+                .get_transactions_by_user_id(client, "customer42")
+                .unwrap()
+        }
+
+        pub fn refresh_stale(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> SyncReport {
+            cache.refresh_stale(client).unwrap()
+        }
+
+        pub fn sync(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> SyncReport {
+            cache.sync(client).unwrap()
+        }
+    }
+
+    #[cfg(feature = "async")]
+    mod calls {
+        use super::*;
+
+        fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+            tokio::runtime::Runtime::new().unwrap().block_on(future)
+        }
+
+        pub fn get_status(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+            id: &str,
+        ) -> Result<TransactionStatus, FapshiError> {
+            block_on(cache.get_status(client, id))
+        }
+
+        pub fn by_user(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> Vec<TransactionStatus> {
+            block_on(cache.get_transactions_by_user_id(client, "customer42")).unwrap()
+        }
+
+        pub fn refresh_stale(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> SyncReport {
+            block_on(cache.refresh_stale(client)).unwrap()
+        }
+
+        pub fn sync(
+            cache: &TransactionCache<InMemoryTransactionStore>,
+            client: &FapshiClient,
+        ) -> SyncReport {
+            block_on(cache.sync(client)).unwrap()
+        }
+    }
+
+    #[test]
+    fn final_status_is_never_overwritten() {
+        let cache = cache();
+        let client = client(vec![
+            get(
+                "transaction/customer42",
+                json!([
+                    transaction("T1", "SUCCESSFUL"),
+                    transaction("T2", "PENDING")
+                ]),
+            ),
+            get(
+                "transaction/customer42",
+                json!([transaction("T1", "FAILED"), transaction("T2", "EXPIRED")]),
+            ),
+        ]);
+
+        assert_eq!(calls::by_user(&cache, &client).len(), 2);
+        calls::by_user(&cache, &client);
+        assert_eq!(status_of(&cache, "T1"), Status::SUCCESSFUL);
+        assert_eq!(status_of(&cache, "T2"), Status::EXPIRED);
+        assert!(cache.store().get("T2").unwrap().unwrap().is_final());
+    }
+
+    #[test]
+    fn final_status_is_served_without_calling_fapshi() {
+        let cache = cache();
+        let client = client(vec![
+            get("payment-status/T1", transaction("T1", "SUCCESSFUL")),
+            get("payment-status/T2", transaction("T2", "PENDING")),
+            get("payment-status/T2", transaction("T2", "SUCCESSFUL")),
+        ]);
+
+        calls::get_status(&cache, &client, "T1").unwrap();
+        calls::get_status(&cache, &client, "T2").unwrap();
+        // The cassette has no other answer for T1, so these must come from the cache.
+        for _ in 0..3 {
+            let cached = calls::get_status(&cache, &client, "T1").unwrap();
+            assert_eq!(cached.status, Status::SUCCESSFUL);
+        }
+        assert_eq!(
+            calls::get_status(&cache, &client, "T2").unwrap().status,
+            Status::SUCCESSFUL
+        );
+        assert_eq!(
+            calls::get_status(&cache, &client, "T2").unwrap().status,
+            Status::SUCCESSFUL
+        );
+        assert!(client.cassette().unwrap().unplayed().is_empty());
+    }
+
+    #[test]
+    fn fresh_open_status_is_served_from_the_cache() {
+        let cache = TransactionCache::new(InMemoryTransactionStore::new());
+        let client = client(vec![get("payment-status/T1", transaction("T1", "PENDING"))]);
+
+        calls::get_status(&cache, &client, "T1").unwrap();
+        assert_eq!(
+            calls::get_status(&cache, &client, "T1").unwrap().status,
+            Status::PENDING
+        );
+        assert_eq!(calls::refresh_stale(&cache, &client), SyncReport::default());
+    }
+
+    #[test]
+    fn refresh_stale_only_fetches_open_transactions() {
+        let cache = cache();
+        let seed = client(vec![get(
+            "transaction/customer42",
+            json!([
+                transaction("T1", "SUCCESSFUL"),
+                transaction("T2", "PENDING"),
+                transaction("T3", "CREATED"),
+            ]),
+        )]);
+        calls::by_user(&cache, &seed);
+
+        let client = client(vec![
+            get("payment-status/T2", transaction("T2", "SUCCESSFUL")),
+            get("payment-status/T3", transaction("T3", "CREATED")),
+        ]);
+        let report = calls::refresh_stale(&cache, &client);
+        assert_eq!(
+            report,
+            SyncReport {
+                days: 0,
+                fetched: 2,
+                inserted: 0,
+                updated: 1,
+            }
+        );
+        assert_eq!(status_of(&cache, "T2"), Status::SUCCESSFUL);
+        assert!(client.cassette().unwrap().unplayed().is_empty());
+    }
+
+    #[test]
+    fn sync_keeps_final_statuses_and_moves_the_cursor() {
+        let cache = cache();
+        let seed = client(vec![get(
+            "transaction/customer42",
+            json!([transaction("T1", "FAILED"), transaction("T2", "PENDING")]),
+        )]);
+        calls::by_user(&cache, &seed);
+
+        let today = Utc::now().date_naive().format("%Y-%m-%d");
+        let client = client(vec![get(
+            &format!("search?start={today}&end={today}&limit=100&sort=asc"),
+            json!([
+                transaction("T1", "SUCCESSFUL"),
+                transaction("T2", "SUCCESSFUL"),
+                transaction("T3", "PENDING"),
+            ]),
+        )]);
+        let report = calls::sync(&cache, &client);
+        assert_eq!(
+            report,
+            SyncReport {
+                days: 1,
+                fetched: 3,
+                inserted: 1,
+                updated: 1,
+            }
+        );
+        assert_eq!(status_of(&cache, "T1"), Status::FAILED);
+        assert_eq!(status_of(&cache, "T2"), Status::SUCCESSFUL);
+        assert_eq!(status_of(&cache, "T3"), Status::PENDING);
+        assert_eq!(
+            cache.store().sync_cursor().unwrap(),
+            Some(Utc::now().date_naive())
+        );
+    }
+
+    #[test]
+    fn file_store_writes_only_changes_and_survives_reopening() {
+        let path =
+            std::env::temp_dir().join(format!("fapshi-rs-cache-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = FileTransactionStore::new(&path);
+        store.put_all(&[]).unwrap();
+        assert!(!path.exists());
+
+        let cached = CachedTransaction {
+            transaction: serde_json::from_value(transaction("T1", "SUCCESSFUL")).unwrap(),
+            fetched_at: Utc::now(),
+        };
+        let day = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        store.put_all(&[cached]).unwrap();
+        store.set_sync_cursor(day).unwrap();
+
+        let reopened = FileTransactionStore::new(&path);
+        assert_eq!(reopened.list().unwrap().len(), 1);
+        assert_eq!(reopened.sync_cursor().unwrap(), Some(day));
+
+        // Reads are served from memory; an unchanged cursor is not written back.
+        std::fs::remove_file(&path).unwrap();
+        assert!(reopened.get("T1").unwrap().unwrap().is_final());
+        reopened.set_sync_cursor(day).unwrap();
+        assert!(!path.exists());
+    }
+}
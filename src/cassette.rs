@@ -0,0 +1,550 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{client::RawResponse, error::FapshiError, store::write_atomically};
+
+/// Placeholder written in place of scrubbed header values and body fields.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Request headers scrubbed by default: the Fapshi credentials.
+const DEFAULT_SCRUBBED_HEADERS: &[&str] = &["apiuser", "apikey", "authorization"];
+
+/// JSON body fields scrubbed by default: the payer's personal data.
+const DEFAULT_SCRUBBED_FIELDS: &[&str] = &["email", "phone", "name", "payerName"];
+
+/// Whether a cassette records live traffic or serves recorded responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests are sent to Fapshi and each exchange is appended to the cassette file.
+    Record,
+    /// Requests are answered from the cassette file; nothing is sent to Fapshi.
+    Replay,
+}
+
+/// A request as written to a cassette.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// The HTTP method, e.g. `GET`.
+    pub method: String,
+    /// The endpoint path, relative to the environment's base URL, with personal data
+    /// scrubbed from its query string.
+    pub path: String,
+    /// The request headers, with credentials scrubbed.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The JSON request body, with personal data scrubbed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// A response as written to a cassette.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The raw response body, with personal data scrubbed if it is JSON.
+    pub body: String,
+}
+
+/// One request and the response Fapshi gave to it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interaction {
+    /// The request sent.
+    pub request: RecordedRequest,
+    /// The response received.
+    pub response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+struct State {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+/// Records HTTP exchanges with Fapshi to a file, or replays them without network access.
+///
+/// Attach a cassette with [`FapshiClient::with_cassette`](crate::client::FapshiClient::with_cassette).
+/// In record mode the values of the `apiuser`/`apikey` headers and of the `email`, `phone`,
+/// `name` and `payerName` fields of JSON bodies and query strings (e.g. `search?name=…`) are
+/// replaced with `"[REDACTED]"`. In replay mode each request is answered by the first not yet
+/// played interaction with the same method, (scrubbed) path and (scrubbed) body, so polling
+/// sequences replay in order; a request without a match fails with
+/// `FapshiError::InvalidRequest`.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     api::balance::BalanceApi,
+///     cassette::Cassette,
+///     client::FapshiClient,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Once, against the sandbox:
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?
+///         .with_cassette(Cassette::record("tests/cassettes/balance.json"));
+/// BalanceApi::get_service_balance(&client)?;
+///
+/// // In CI, offline:
+/// let client = FapshiClient::with_environment("user", "key", Environment::Sandbox)?
+///     .with_cassette(Cassette::replay("tests/cassettes/balance.json")?);
+/// let balance = BalanceApi::get_service_balance(&client)?;
+/// println!("Balance: {} {}", balance.balance, balance.currency);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cassette {
+    path: Option<PathBuf>,
+    mode: CassetteMode,
+    scrubbed_headers: Vec<String>,
+    scrubbed_fields: Vec<String>,
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cassette {
+    /// Creates a cassette recording to `path`. An existing file is overwritten on the first
+    /// recorded exchange.
+    ///
+    /// If an exchange cannot be written, the request still returns Fapshi's response and the
+    /// error goes to the client's [`on_record_error`](crate::FapshiClient::on_record_error)
+    /// hooks.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self::with_interactions(Some(path), CassetteMode::Record, Vec::new())
+    }
+
+    /// Loads the cassette at `path` for replay.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, FapshiError> {
+        let file: CassetteFile = serde_json::from_str(&fs::read_to_string(path.as_ref())?)?;
+        Ok(Self::with_interactions(
+            Some(path),
+            CassetteMode::Replay,
+            file.interactions,
+        ))
+    }
+
+    /// Creates a replay cassette from interactions built in memory, e.g. a local mock of
+    /// Fapshi. Request bodies are scrubbed like live requests before being matched.
+    pub fn from_interactions(interactions: Vec<Interaction>) -> Self {
+        Self::with_interactions(None::<&Path>, CassetteMode::Replay, interactions)
+    }
+
+    fn with_interactions(
+        path: Option<impl AsRef<Path>>,
+        mode: CassetteMode,
+        interactions: Vec<Interaction>,
+    ) -> Self {
+        Cassette {
+            path: path.map(|path| path.as_ref().to_path_buf()),
+            mode,
+            scrubbed_headers: DEFAULT_SCRUBBED_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            scrubbed_fields: DEFAULT_SCRUBBED_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            state: Mutex::new(State {
+                played: vec![false; interactions.len()],
+                interactions,
+            }),
+        }
+    }
+
+    /// Scrubs additional fields, matched by name at any depth of JSON bodies and in query
+    /// strings.
+    pub fn scrub_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scrubbed_fields
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Scrubs additional request headers, matched case-insensitively.
+    pub fn scrub_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scrubbed_headers
+            .extend(headers.into_iter().map(|h| h.into().to_ascii_lowercase()));
+        self
+    }
+
+    /// Returns the cassette's mode.
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Returns the path of the cassette file, or `None` for an in-memory cassette.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns a copy of the recorded interactions.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state().interactions.clone()
+    }
+
+    /// Returns the interactions that have not been replayed yet, e.g. to check at the end of
+    /// a test that every recorded call was made.
+    pub fn unplayed(&self) -> Vec<Interaction> {
+        let state = self.state();
+        state
+            .interactions
+            .iter()
+            .zip(&state.played)
+            .filter(|(_, played)| !**played)
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    /// Answers a request from the cassette.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if no unplayed interaction matches the request.
+    pub(crate) fn replay_request(
+        &self,
+        method: &Method,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<RawResponse, FapshiError> {
+        let scrubbed_path = self.scrub_path(path);
+        let body = body.map(|body| self.scrub_body(body));
+        let mut state = self.state();
+        let State {
+            interactions,
+            played,
+        } = &mut *state;
+        let index = interactions
+            .iter()
+            .zip(played.iter())
+            .position(|(interaction, played)| {
+                !*played
+                    && interaction.request.method == method.as_str()
+                    && self.scrub_path(&interaction.request.path) == scrubbed_path
+                    && interaction.request.body.clone().map(|mut recorded| {
+                        self.scrub_value(&mut recorded);
+                        recorded
+                    }) == body
+            })
+            .ok_or_else(|| {
+                FapshiError::InvalidRequest(format!(
+                    "no unplayed interaction in {} matches {} {}",
+                    self.describe(),
+                    method,
+                    path
+                ))
+            })?;
+        played[index] = true;
+        let response = &interactions[index].response;
+        let status = StatusCode::from_u16(response.status).map_err(|_| {
+            FapshiError::InvalidRequest(format!(
+                "invalid status {} in {}",
+                response.status,
+                self.describe()
+            ))
+        })?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        Ok(RawResponse {
+            status,
+            headers,
+            text: response.body.clone(),
+        })
+    }
+
+    /// Appends a live exchange to the cassette and rewrites the file.
+    pub(crate) fn record_exchange(
+        &self,
+        method: &Method,
+        path: &str,
+        request_headers: &HeaderMap,
+        body: Option<&str>,
+        response: &RawResponse,
+    ) -> Result<(), FapshiError> {
+        let interaction = Interaction {
+            request: RecordedRequest {
+                method: method.to_string(),
+                path: self.scrub_path(path),
+                headers: self.header_map(request_headers, true),
+                body: body.map(|body| self.scrub_body(body)),
+            },
+            response: RecordedResponse {
+                status: response.status.as_u16(),
+                headers: self.header_map(&response.headers, false),
+                body: match serde_json::from_str::<Value>(&response.text) {
+                    Ok(mut value) => {
+                        self.scrub_value(&mut value);
+                        serde_json::to_string(&value)?
+                    }
+                    Err(_) => response.text.clone(),
+                },
+            },
+        };
+        let mut state = self.state();
+        state.interactions.push(interaction);
+        state.played.push(true);
+        let file = CassetteFile {
+            interactions: state.interactions.clone(),
+        };
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomically(path, &file)
+    }
+
+    fn describe(&self) -> String {
+        match &self.path {
+            Some(path) => format!("cassette {}", path.display()),
+            None => "in-memory cassette".to_string(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn header_map(&self, headers: &HeaderMap, scrub: bool) -> BTreeMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if scrub && self.scrubbed_headers.iter().any(|h| h == name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    value.to_str().unwrap_or_default().to_string()
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Scrubs the values of the scrubbed fields from the query string of a path.
+    fn scrub_path(&self, path: &str) -> String {
+        let Some((endpoint, query)) = path.split_once('?') else {
+            return path.to_string();
+        };
+        let mut scrubbed = url::form_urlencoded::Serializer::new(String::new());
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            if !value.is_empty() && self.scrubbed_fields.iter().any(|field| *field == key) {
+                scrubbed.append_pair(&key, REDACTED);
+            } else {
+                scrubbed.append_pair(&key, &value);
+            }
+        }
+        format!("{endpoint}?{}", scrubbed.finish())
+    }
+
+    /// Parses a request body as JSON and scrubs it; bodies that are not JSON are kept as a
+    /// string.
+    fn scrub_body(&self, body: &str) -> Value {
+        match serde_json::from_str(body) {
+            Ok(mut value) => {
+                self.scrub_value(&mut value);
+                value
+            }
+            Err(_) => Value::String(body.to_string()),
+        }
+    }
+
+    fn scrub_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.scrubbed_fields.iter().any(|field| field == key) {
+                        if !value.is_null() {
+                            *value = Value::String(REDACTED.to_string());
+                        }
+                    } else {
+                        self.scrub_value(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.scrub_value(item)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        api::balance::BalanceApi, client::FapshiClient, models::ServiceBalance, safety::Environment,
+    };
+
+    fn cassette_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "fapshi-rs-cassette-{}-{name}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn response(status: u16, text: &str) -> RawResponse {
+        RawResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: HeaderMap::new(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn recording_scrubs_credentials_and_personal_data() {
+        let path = cassette_path("scrub");
+        let cassette = Cassette::record(&path).scrub_fields(["userId"]);
+        let mut headers = HeaderMap::new();
+        headers.insert("apiuser", HeaderValue::from_static("user"));
+        headers.insert("apikey", HeaderValue::from_static("secret"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        let body = json!({"amount": 500, "phone": "670000000", "userId": "u1", "email": null});
+        cassette
+            .record_exchange(
+                &Method::POST,
+                "direct-pay",
+                &headers,
+                Some(&body.to_string()),
+                &response(200, r#"{"transId":"T1","payerName":"Jane"}"#),
+            )
+            .unwrap();
+
+        let file = fs::read_to_string(&path).unwrap();
+        assert!(!file.contains("secret") && !file.contains("670000000"));
+        assert!(!file.contains("Jane") && !file.contains("u1"));
+        let recorded = &Cassette::replay(&path).unwrap().interactions()[0];
+        assert_eq!(recorded.request.headers["apikey"], REDACTED);
+        assert_eq!(recorded.request.headers["content-type"], "application/json");
+        assert_eq!(
+            recorded.request.body,
+            Some(json!({"amount": 500, "phone": REDACTED, "userId": REDACTED, "email": null}))
+        );
+        assert_eq!(
+            recorded.response.body,
+            json!({"transId": "T1", "payerName": REDACTED}).to_string()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replay_answers_matching_requests_in_order() {
+        let path = cassette_path("order");
+        let interaction = |status: &str| Interaction {
+            request: RecordedRequest {
+                method: "GET".to_string(),
+                path: "payment-status/T1".to_string(),
+                headers: BTreeMap::new(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: json!({"status": status}).to_string(),
+            },
+        };
+        let file = CassetteFile {
+            interactions: vec![interaction("PENDING"), interaction("SUCCESSFUL")],
+        };
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        let cassette = Cassette::replay(&path).unwrap();
+        assert_eq!(cassette.mode(), CassetteMode::Replay);
+        let first = cassette
+            .replay_request(&Method::GET, "payment-status/T1", None)
+            .unwrap();
+        assert!(first.text.contains("PENDING"));
+        assert_eq!(cassette.unplayed().len(), 1);
+        let second = cassette
+            .replay_request(&Method::GET, "payment-status/T1", None)
+            .unwrap();
+        assert!(second.text.contains("SUCCESSFUL"));
+        assert!(cassette.unplayed().is_empty());
+        let exhausted = cassette.replay_request(&Method::GET, "payment-status/T1", None);
+        assert!(matches!(exhausted, Err(FapshiError::InvalidRequest(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
+        BalanceApi::get_service_balance(client)
+    }
+
+    #[cfg(feature = "async")]
+    fn balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(BalanceApi::get_service_balance(client))
+    }
+
+    #[test]
+    fn client_is_served_from_the_cassette() {
+        let path = cassette_path("client");
+        let file = CassetteFile {
+            interactions: vec![Interaction {
+                request: RecordedRequest {
+                    method: "GET".to_string(),
+                    path: "balance".to_string(),
+                    headers: BTreeMap::new(),
+                    body: None,
+                },
+                response: RecordedResponse {
+                    status: 200,
+                    headers: BTreeMap::new(),
+                    body: r#"{"service":"Shop","balance":1500,"currency":"XAF"}"#.to_string(),
+                },
+            }],
+        };
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        let client = FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::replay(&path).unwrap());
+        let result = balance(&client).unwrap();
+        assert_eq!(result.balance, 1500.0);
+        assert!(client.cassette().unwrap().unplayed().is_empty());
+        assert!(balance(&client).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,322 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::FapshiError;
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally and failures are counted.
+    Closed,
+    /// Requests are rejected immediately with `FapshiError::CircuitOpen`.
+    Open,
+    /// The cool-down has elapsed and a single trial request at a time is let through to probe
+    /// Fapshi; other requests are rejected until it completes.
+    HalfOpen,
+}
+
+/// Configuration for a [`CircuitBreaker`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Number of consecutive failures that trips the breaker open.
+    pub failure_threshold: u32,
+    /// Number of consecutive successful trial requests needed to close the breaker again.
+    pub success_threshold: u32,
+    /// How long the breaker stays open before allowing trial requests.
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            success_threshold: 1,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    opened_at: Option<Instant>,
+    trial_started: Option<Instant>,
+}
+
+/// A circuit breaker guarding requests to the Fapshi API.
+///
+/// Transport errors and 5xx responses count as failures. Once `failure_threshold`
+/// consecutive failures are recorded, the breaker opens and requests fail fast until
+/// `cool_down` has elapsed. The breaker then goes half-open and lets one trial request
+/// through at a time: `success_threshold` successful trials close it again, a failed trial
+/// re-opens it for another cool-down period. A trial whose outcome is never recorded (e.g. a
+/// cancelled future) stops blocking other trials after `cool_down`.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use fapshi_rs::{
+///     circuit_breaker::CircuitBreakerConfig,
+///     client::FapshiClient,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?
+///         .with_circuit_breaker(CircuitBreakerConfig {
+///             failure_threshold: 3,
+///             success_threshold: 1,
+///             cool_down: Duration::from_secs(10),
+///         });
+/// println!("Circuit state: {:?}", client.circuit_state());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new, closed circuit breaker.
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                consecutive_successes: 0,
+                opened_at: None,
+                trial_started: None,
+            }),
+        }
+    }
+
+    /// Returns the configuration of this breaker.
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Returns the current state, moving from open to half-open if the cool-down has elapsed.
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.lock();
+        self.refresh(&mut inner);
+        inner.state
+    }
+
+    /// Checks whether a request may be sent. In the half-open state, an admitted request is
+    /// the trial, and its outcome must be passed to [`CircuitBreaker::record_success`] or
+    /// [`CircuitBreaker::record_failure`].
+    ///
+    /// # Errors
+    /// Returns `FapshiError::CircuitOpen` with the remaining cool-down if the breaker is open,
+    /// or with the time after which an unfinished trial is given up if one is in flight.
+    pub fn allow_request(&self) -> Result<(), FapshiError> {
+        let mut inner = self.lock();
+        self.refresh(&mut inner);
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => Err(FapshiError::CircuitOpen(self.remaining(inner.opened_at))),
+            CircuitState::HalfOpen => match inner.trial_started {
+                Some(started) if started.elapsed() < self.config.cool_down => {
+                    Err(FapshiError::CircuitOpen(self.remaining(Some(started))))
+                }
+                _ => {
+                    inner.trial_started = Some(Instant::now());
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    /// Ends a trial admitted by [`CircuitBreaker::allow_request`] without an outcome, e.g.
+    /// when the request was answered locally, so the next request can be the trial.
+    pub fn abandon_trial(&self) {
+        self.lock().trial_started = None;
+    }
+
+    /// Records a request that reached Fapshi and got a non-5xx answer.
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = 0;
+        inner.trial_started = None;
+        if inner.state == CircuitState::HalfOpen {
+            inner.consecutive_successes += 1;
+            if inner.consecutive_successes >= self.config.success_threshold {
+                inner.state = CircuitState::Closed;
+                inner.consecutive_successes = 0;
+                inner.opened_at = None;
+            }
+        }
+    }
+
+    /// Records a transport error or 5xx response.
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_successes = 0;
+        inner.consecutive_failures += 1;
+        inner.trial_started = None;
+        let trips = match inner.state {
+            CircuitState::HalfOpen => true,
+            CircuitState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+            CircuitState::Open => false,
+        };
+        if trips {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Forces the breaker back to the closed state.
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.consecutive_successes = 0;
+        inner.opened_at = None;
+        inner.trial_started = None;
+    }
+
+    /// Returns how much of the cool-down started at `since` is left.
+    fn remaining(&self, since: Option<Instant>) -> Duration {
+        since.map_or(Duration::ZERO, |since| {
+            self.config.cool_down.saturating_sub(since.elapsed())
+        })
+    }
+
+    fn refresh(&self, inner: &mut Inner) {
+        if let (CircuitState::Open, Some(opened_at)) = (inner.state, inner.opened_at) {
+            if opened_at.elapsed() >= self.config.cool_down {
+                inner.state = CircuitState::HalfOpen;
+                inner.consecutive_successes = 0;
+                inner.trial_started = None;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(success_threshold: u32, cool_down: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            success_threshold,
+            cool_down,
+        })
+    }
+
+    fn trip(breaker: &CircuitBreaker) {
+        breaker.record_failure();
+        breaker.record_failure();
+    }
+
+    fn is_open_error(result: Result<(), FapshiError>) -> bool {
+        matches!(result, Err(FapshiError::CircuitOpen(_)))
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker(1, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow_request().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        match breaker.allow_request() {
+            Err(FapshiError::CircuitOpen(remaining)) => {
+                assert!(remaining > Duration::ZERO && remaining <= Duration::from_secs(60));
+            }
+            other => panic!("expected CircuitOpen, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn half_open_admits_one_trial_and_closes_on_success() {
+        let breaker = breaker(1, Duration::from_millis(50));
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.allow_request().is_ok());
+        assert!(is_open_error(breaker.allow_request()));
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow_request().is_ok());
+        assert!(breaker.allow_request().is_ok());
+    }
+
+    #[test]
+    fn failed_trial_reopens() {
+        let breaker = breaker(1, Duration::from_millis(50));
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(is_open_error(breaker.allow_request()));
+    }
+
+    #[test]
+    fn success_threshold_needs_consecutive_trials() {
+        let breaker = breaker(2, Duration::from_millis(50));
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(breaker.allow_request().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow_request().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn abandoned_trial_frees_the_slot() {
+        let breaker = breaker(1, Duration::from_millis(50));
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(breaker.allow_request().is_ok());
+        breaker.abandon_trial();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow_request().is_ok());
+        assert!(is_open_error(breaker.allow_request()));
+    }
+
+    #[test]
+    fn unfinished_trial_is_given_up_after_cool_down() {
+        let breaker = breaker(1, Duration::from_millis(50));
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(breaker.allow_request().is_ok());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request().is_ok());
+    }
+
+    #[test]
+    fn reset_closes() {
+        let breaker = breaker(1, Duration::from_secs(60));
+        trip(&breaker);
+        breaker.reset();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow_request().is_ok());
+    }
+}
@@ -1,32 +1,85 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::audit::{AuditSink, AuditedCall};
+use crate::cassette::{Cassette, CassetteMode};
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+use crate::endpoint::Endpoint;
 use crate::error::FapshiError;
+use crate::interceptor::{Interceptors, RequestParts, ResponseParts};
+use crate::metrics::Metrics;
+use crate::preview::RequestPreview;
+use crate::safety::{self, AmountGuard, AmountLimits, Environment};
 #[cfg(not(feature = "async"))]
 use reqwest::blocking::Client;
 #[cfg(feature = "async")]
 use reqwest::Client;
 
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::HeaderValue;
+pub use reqwest::{header::HeaderMap, Method, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Response header carrying the request identifier, when Fapshi provides one.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// A decoded response from the Fapshi API.
+///
+/// Returned by [`FapshiClient::request`] for callers that need more than the response body.
+#[derive(Debug, Clone)]
+pub struct ApiResponse<T> {
+    /// The HTTP status code of the response.
+    pub status: StatusCode,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The request identifier from the `x-request-id` header, if present.
+    pub request_id: Option<String>,
+    /// The deserialized response body.
+    pub body: T,
+}
+
+/// A response as read off the wire, before decoding.
+#[derive(Debug, Clone)]
+pub(crate) struct RawResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) text: String,
+}
 
 /// The main client for interacting with the Fapshi API.
 ///
 /// This struct manages authentication and HTTP requests to the Fapshi API. It supports both
 /// sandbox and production environments, and all requests are authenticated using the provided
-/// `apiuser` and `apikey`.
+/// `apiuser` and `apikey`. The live environment must be chosen explicitly with
+/// [`FapshiClient::with_environment`].
 ///
 /// # Example
-/// ```
-/// use fapshi_rs::client::FapshiClient;
+/// ```no_run
+/// use fapshi_rs::{client::FapshiClient, safety::Environment};
 ///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// use std::env;
 /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
 /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-/// let client = FapshiClient::new(&api_user, &api_key, true)?;
+/// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Debug, Clone)]
 pub struct FapshiClient {
     client: Client,
+    environment: Environment,
     base_url: String,
+    headers: HeaderMap,
     _api_user: String,
     _api_key: String,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    cassette: Option<Arc<Cassette>>,
+    amount_guard: Option<Arc<AmountGuard>>,
+    dry_run: bool,
+    metrics: Option<Arc<Metrics>>,
+    interceptors: Interceptors,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    audit_actor: Option<String>,
 }
 
 impl FapshiClient {
@@ -42,28 +95,402 @@ impl FapshiClient {
     ///
     /// # Errors
     /// Returns an error if the headers are invalid or the HTTP client cannot be built.
+    #[deprecated(
+        note = "use `FapshiClient::with_environment`, which names the environment explicitly"
+    )]
     pub fn new(api_user: &str, api_key: &str, sandbox: bool) -> Result<Self, FapshiError> {
-        let base_url = if sandbox {
-            "https://sandbox.fapshi.com".to_string()
+        let environment = if sandbox {
+            Environment::Sandbox
         } else {
-            "https://live.fapshi.com".to_string()
+            Environment::Live
         };
+        Self::with_environment(api_user, api_key, environment)
+    }
 
+    /// Creates a new `FapshiClient` instance for the given environment.
+    ///
+    /// # Arguments
+    /// * `api_user` - The API user ID obtained from the Fapshi dashboard.
+    /// * `api_key` - The API key obtained from the Fapshi dashboard.
+    /// * `environment` - The environment to use; `Environment::Live` moves real money.
+    ///
+    /// # Returns
+    /// A `Result` containing the `FapshiClient` or a `FapshiError` if initialization fails.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     client::FapshiClient,
+    ///     safety::{AmountLimits, Environment},
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Live)?
+    ///     .with_amount_limits(AmountLimits {
+    ///         per_request: Some(50_000.0),
+    ///         per_day: Some(500_000.0),
+    ///     });
+    /// client.verify_environment()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_environment(
+        api_user: &str,
+        api_key: &str,
+        environment: Environment,
+    ) -> Result<Self, FapshiError> {
         let mut headers = HeaderMap::new();
         headers.insert("apiuser", HeaderValue::from_str(api_user)?);
         headers.insert("apikey", HeaderValue::from_str(api_key)?);
         headers.insert("Content-Type", HeaderValue::from_static("application/json"));
 
-        let client = Client::builder().default_headers(headers).build()?;
+        let client = Client::builder().build()?;
 
         Ok(FapshiClient {
             client,
-            base_url,
+            environment,
+            base_url: environment.base_url().to_string(),
+            headers,
             _api_user: api_user.to_string(),
             _api_key: api_key.to_string(),
+            circuit_breaker: None,
+            cassette: None,
+            amount_guard: None,
+            dry_run: false,
+            metrics: None,
+            interceptors: Interceptors::default(),
+            audit_sink: None,
+            audit_actor: None,
         })
     }
 
+    /// Returns the environment the client talks to.
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    /// Caps the amounts the client may request in the live environment.
+    ///
+    /// Requests over a limit fail with `FapshiError::InvalidRequest` without being sent.
+    /// Clones of the returned client share the same daily total.
+    ///
+    /// # Arguments
+    /// * `limits` - The per-request and per-day ceilings.
+    pub fn with_amount_limits(mut self, limits: AmountLimits) -> Self {
+        self.amount_guard = Some(Arc::new(AmountGuard::new(limits)));
+        self
+    }
+
+    /// Returns the total amount requested today against the amount limits, or `None` if no
+    /// limits are configured.
+    pub fn spent_today(&self) -> Option<f64> {
+        self.amount_guard.as_ref().map(|guard| guard.spent_today())
+    }
+
+    /// Turns dry-run mode on or off.
+    ///
+    /// In dry-run mode requests are validated (direct payment and payout fields, amount
+    /// limits) but not sent; each call fails with `FapshiError::DryRun` carrying a
+    /// [`RequestPreview`] of the request that would have been made.
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Returns whether the client is in dry-run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Checks that the credentials belong to the client's environment.
+    ///
+    /// Reads the balance; if Fapshi refuses the credentials, the other environment is tried
+    /// with the same credentials. The probes always go to Fapshi, bypassing the circuit
+    /// breaker, cassette, dry-run mode, metrics, hooks and audit sink.
+    ///
+    /// # Returns
+    /// `Ok(())` if the credentials work, or `FapshiError::EnvironmentMismatch` if they only
+    /// work in the other environment, or the original error.
+    #[cfg(not(feature = "async"))]
+    pub fn verify_environment(&self) -> Result<(), FapshiError> {
+        let err = match self
+            .bare(self.environment)
+            .get_json::<serde_json::Value>("balance")
+        {
+            Ok(_) => return Ok(()),
+            Err(err) if is_auth_error(&err) => err,
+            Err(err) => return Err(err),
+        };
+        match self
+            .bare(self.environment.other())
+            .get_json::<serde_json::Value>("balance")
+        {
+            Ok(_) => Err(FapshiError::EnvironmentMismatch {
+                configured: self.environment,
+                detected: self.environment.other(),
+            }),
+            Err(_) => Err(err),
+        }
+    }
+
+    /// Checks that the credentials belong to the client's environment, asynchronously.
+    ///
+    /// Reads the balance; if Fapshi refuses the credentials, the other environment is tried
+    /// with the same credentials. The probes always go to Fapshi, bypassing the circuit
+    /// breaker, cassette, dry-run mode, metrics, hooks and audit sink.
+    ///
+    /// # Returns
+    /// `Ok(())` if the credentials work, or `FapshiError::EnvironmentMismatch` if they only
+    /// work in the other environment, or the original error.
+    #[cfg(feature = "async")]
+    pub async fn verify_environment(&self) -> Result<(), FapshiError> {
+        let err = match self
+            .bare(self.environment)
+            .get_json::<serde_json::Value>("balance")
+            .await
+        {
+            Ok(_) => return Ok(()),
+            Err(err) if is_auth_error(&err) => err,
+            Err(err) => return Err(err),
+        };
+        match self
+            .bare(self.environment.other())
+            .get_json::<serde_json::Value>("balance")
+            .await
+        {
+            Ok(_) => Err(FapshiError::EnvironmentMismatch {
+                configured: self.environment,
+                detected: self.environment.other(),
+            }),
+            Err(_) => Err(err),
+        }
+    }
+
+    /// Returns a client for `environment` with the same credentials and none of the
+    /// configured extras (circuit breaker, cassette, dry run, limits, metrics, hooks, audit),
+    /// so environment probes neither skew them nor get stopped by them.
+    fn bare(&self, environment: Environment) -> Self {
+        FapshiClient {
+            client: self.client.clone(),
+            environment,
+            base_url: environment.base_url().to_string(),
+            headers: self.headers.clone(),
+            _api_user: self._api_user.clone(),
+            _api_key: self._api_key.clone(),
+            circuit_breaker: None,
+            cassette: None,
+            amount_guard: None,
+            dry_run: false,
+            metrics: None,
+            interceptors: Interceptors::default(),
+            audit_sink: None,
+            audit_actor: None,
+        }
+    }
+
+    /// Enables a circuit breaker around all requests made by this client.
+    ///
+    /// Clones of the returned client share the same breaker.
+    ///
+    /// # Arguments
+    /// * `config` - The failure threshold and cool-down settings of the breaker.
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(Arc::new(CircuitBreaker::new(config)));
+        self
+    }
+
+    /// Returns the current circuit breaker state, or `None` if no breaker is configured.
+    ///
+    /// Useful for exposing the health of the Fapshi integration.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit_breaker.as_ref().map(|breaker| breaker.state())
+    }
+
+    /// Returns the circuit breaker, if one is configured.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_deref()
+    }
+
+    /// Records requests to, or replays them from, a cassette file.
+    ///
+    /// Clones of the returned client share the same cassette. See [`Cassette`] for the file
+    /// format and what is scrubbed.
+    ///
+    /// # Arguments
+    /// * `cassette` - The cassette, in record or replay mode.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
+    }
+
+    /// Returns the cassette, if one is attached.
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_deref()
+    }
+
+    /// Collects request, latency and circuit breaker metrics for this client.
+    ///
+    /// Clones of the returned client share the same metrics. Read them with
+    /// [`FapshiClient::metrics`].
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = Some(Arc::new(Metrics::new()));
+        self
+    }
+
+    /// Returns the metrics, if they are enabled.
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.as_deref()
+    }
+
+    /// Records every payment link, direct payment, expiry and payout made with this client.
+    ///
+    /// Every request to a money-moving endpoint (`initiate-pay`, `direct-pay`, `expire-pay`,
+    /// `payout`) writes an `initiated` [`AuditRecord`](crate::audit::AuditRecord) before it
+    /// is sent and a second record with its outcome once it finishes, whichever method sent
+    /// it. If the first record cannot be written, the request is not sent and the call fails
+    /// with the sink's error. If the second cannot, the call still returns Fapshi's response
+    /// and the error goes to the [`FapshiClient::on_record_error`] hooks. Clones of the
+    /// returned client share the sink.
+    ///
+    /// # Arguments
+    /// * `sink` - Where the records go, e.g. a
+    ///   [`JsonLinesAuditSink`](crate::audit::JsonLinesAuditSink).
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sets who the audit records name as the actor, e.g. a service or operator name.
+    /// Defaults to the API user.
+    pub fn with_audit_actor(mut self, actor: &str) -> Self {
+        self.audit_actor = Some(actor.to_string());
+        self
+    }
+
+    /// Returns the audit sink, if one is attached.
+    pub fn audit_sink(&self) -> Option<&dyn AuditSink> {
+        self.audit_sink.as_deref()
+    }
+
+    /// Returns the actor named in audit records.
+    pub fn audit_actor(&self) -> &str {
+        self.audit_actor.as_deref().unwrap_or(&self._api_user)
+    }
+
+    /// Registers a hook run on every request before it is sent.
+    ///
+    /// Hooks run in registration order and may change the method, endpoint, headers and body.
+    /// They also run in dry-run mode, before the request is validated. Clones of the returned
+    /// client share the hooks.
+    ///
+    /// # Arguments
+    /// * `hook` - The function called with the outgoing request.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{client::FapshiClient, safety::Environment};
+    /// use reqwest::header::HeaderValue;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?
+    ///         .before_request(|request| {
+    ///             request
+    ///                 .headers
+    ///                 .insert("x-correlation-id", HeaderValue::from_static("order-1001"));
+    ///         })
+    ///         .after_response(|response| {
+    ///             println!(
+    ///                 "{} {} -> {:?} in {:?}",
+    ///                 response.request.method,
+    ///                 response.request.endpoint,
+    ///                 response.status,
+    ///                 response.elapsed
+    ///             );
+    ///         });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn before_request(
+        mut self,
+        hook: impl Fn(&mut RequestParts) + Send + Sync + 'static,
+    ) -> Self {
+        self.interceptors.add_before(hook);
+        self
+    }
+
+    /// Registers a hook run on every request that was sent, with its response or the error
+    /// that prevented one.
+    ///
+    /// Hooks run in registration order. Requests stopped before being sent (dry run, amount
+    /// limits, open circuit breaker) do not reach them. Clones of the returned client share
+    /// the hooks.
+    ///
+    /// # Arguments
+    /// * `hook` - The function called with the request and its outcome.
+    pub fn after_response(
+        mut self,
+        hook: impl Fn(&ResponseParts<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.interceptors.add_after(hook);
+        self
+    }
+
+    /// Registers a hook called when an audit record or cassette entry cannot be written
+    /// after a request was sent.
+    ///
+    /// The call itself still returns Fapshi's response, so that a payment that went through
+    /// is not retried; the hook is the only place such failures surface. Without a hook they
+    /// are dropped. Clones of the returned client share the hooks.
+    ///
+    /// # Arguments
+    /// * `hook` - The function called with the error.
+    pub fn on_record_error(mut self, hook: impl Fn(&FapshiError) + Send + Sync + 'static) -> Self {
+        self.interceptors.add_record_error(hook);
+        self
+    }
+
+    /// Fails fast if the circuit breaker is open, giving back the amount `admit` reserved for
+    /// the request.
+    fn check_circuit(&self, reserved: Option<f64>) -> Result<(), FapshiError> {
+        let Some(breaker) = &self.circuit_breaker else {
+            return Ok(());
+        };
+        breaker.allow_request().inspect_err(|_| {
+            self.observe_circuit();
+            self.release(reserved);
+        })
+    }
+
+    /// Gives back an amount reserved by `admit` for a request that is not sent.
+    fn release(&self, reserved: Option<f64>) {
+        if let (Some(amount), Some(guard)) = (reserved, &self.amount_guard) {
+            guard.release(amount);
+        }
+    }
+
+    /// Feeds a response status into the circuit breaker; `None` stands for a transport error.
+    fn record_status(&self, status: Option<StatusCode>) {
+        let Some(breaker) = &self.circuit_breaker else {
+            return;
+        };
+        if status.is_none_or(|status| status.is_server_error()) {
+            breaker.record_failure();
+        } else {
+            breaker.record_success();
+        }
+    }
+
+    /// Feeds a replayed response into the circuit breaker like a live one; a request the
+    /// cassette cannot answer ends a half-open trial without an outcome.
+    fn record_replay(&self, result: &Result<RawResponse, FapshiError>) {
+        match (result, &self.circuit_breaker) {
+            (Ok(raw), _) => self.record_status(Some(raw.status)),
+            (Err(_), Some(breaker)) => breaker.abandon_trial(),
+            (Err(_), None) => {}
+        }
+    }
+
     /// Sends a GET request to the specified API endpoint.
     ///
     /// # Arguments
@@ -73,9 +500,7 @@ impl FapshiClient {
     /// A `Result` containing the response body as a `String` or a `FapshiError`.
     #[cfg(not(feature = "async"))]
     pub fn get(&self, endpoint: &str) -> Result<String, FapshiError> {
-        let url = format!("{}/{}", self.base_url, endpoint);
-        let response = self.client.get(&url).send()?.error_for_status()?;
-        Ok(response.text()?)
+        text_or_error(self.send(Method::GET, endpoint, None)?)
     }
 
     /// Sends a GET request to the specified API endpoint asynchronously.
@@ -87,12 +512,13 @@ impl FapshiClient {
     /// A `Result` containing the response body as a `String` or a `FapshiError`.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_rs::client::FapshiClient;
+    /// ```no_run
+    /// use fapshi_rs::{client::FapshiClient, safety::Environment};
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true)?;
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
     /// let response = client.get("transaction/status/123").await?;
     /// println!("Response: {}", response);
     /// # Ok(())
@@ -100,9 +526,7 @@ impl FapshiClient {
     /// ```
     #[cfg(feature = "async")]
     pub async fn get(&self, endpoint: &str) -> Result<String, FapshiError> {
-        let url = format!("{}/{}", self.base_url, endpoint);
-        let response = self.client.get(&url).send().await?.error_for_status()?;
-        Ok(response.text().await?)
+        text_or_error(self.send(Method::GET, endpoint, None).await?)
     }
 
     /// Sends a POST request to the specified API endpoint with a JSON body.
@@ -115,14 +539,7 @@ impl FapshiClient {
     /// A `Result` containing the response body as a `String` or a `FapshiError`.
     #[cfg(not(feature = "async"))]
     pub fn post(&self, endpoint: &str, body: &str) -> Result<String, FapshiError> {
-        let url = format!("{}/{}", self.base_url, endpoint);
-        let response = self
-            .client
-            .post(&url)
-            .body(body.to_string())
-            .send()?
-            .error_for_status()?;
-        Ok(response.text()?)
+        text_or_error(self.send(Method::POST, endpoint, Some(body.to_string()))?)
     }
 
     /// Sends a POST request to the specified API endpoint with a JSON body asynchronously.
@@ -135,12 +552,13 @@ impl FapshiClient {
     /// A `Result` containing the response body as a `String` or a `FapshiError`.
     ///
     /// # Example
-    /// ```
-    /// use fapshi_rs::client::FapshiClient;
+    /// ```no_run
+    /// use fapshi_rs::{client::FapshiClient, safety::Environment};
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = FapshiClient::new("your_api_user", "your_api_key", true)?;
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
     /// let response = client.post("payment/create", r#"{"amount": 100}"#).await?;
     /// println!("Response: {}", response);
     /// # Ok(())
@@ -148,14 +566,617 @@ impl FapshiClient {
     /// ```
     #[cfg(feature = "async")]
     pub async fn post(&self, endpoint: &str, body: &str) -> Result<String, FapshiError> {
-        let url = format!("{}/{}", self.base_url, endpoint);
-        let response = self
-            .client
-            .post(&url)
-            .body(body.to_string())
-            .send()
+        text_or_error(
+            self.send(Method::POST, endpoint, Some(body.to_string()))
+                .await?,
+        )
+    }
+
+    /// Executes a Fapshi API operation from the endpoint catalogue.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The operation, carrying its path parameters and request body.
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized response or a `FapshiError`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{client::FapshiClient, endpoint::PaymentStatus, safety::Environment};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+    /// let status = client.call(&PaymentStatus("trans123"))?;
+    /// println!("Transaction status: {:?}", status.status);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "async"))]
+    pub fn call<E: Endpoint>(&self, endpoint: &E) -> Result<E::Response, FapshiError> {
+        Ok(self
+            .request(E::METHOD, &endpoint.path(), endpoint.body())?
+            .body)
+    }
+
+    /// Executes a Fapshi API operation from the endpoint catalogue asynchronously.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The operation, carrying its path parameters and request body.
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized response or a `FapshiError`.
+    #[cfg(feature = "async")]
+    pub async fn call<E: Endpoint>(&self, endpoint: &E) -> Result<E::Response, FapshiError> {
+        Ok(self
+            .request(E::METHOD, &endpoint.path(), endpoint.body())
             .await?
-            .error_for_status()?;
-        Ok(response.text().await?)
+            .body)
+    }
+
+    /// Sends a GET request and deserializes the JSON response body.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The API endpoint path (e.g., "balance").
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized body or a `FapshiError`. Non-success responses
+    /// are decoded into `FapshiError::StatusError` with Fapshi's error message.
+    #[cfg(not(feature = "async"))]
+    pub fn get_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, FapshiError> {
+        Ok(self.request::<(), T>(Method::GET, endpoint, None)?.body)
+    }
+
+    /// Sends a GET request and deserializes the JSON response body asynchronously.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The API endpoint path (e.g., "balance").
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized body or a `FapshiError`. Non-success responses
+    /// are decoded into `FapshiError::StatusError` with Fapshi's error message.
+    #[cfg(feature = "async")]
+    pub async fn get_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, FapshiError> {
+        Ok(self
+            .request::<(), T>(Method::GET, endpoint, None)
+            .await?
+            .body)
+    }
+
+    /// Serializes `body` as JSON, sends it in a POST request and deserializes the response body.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The API endpoint path (e.g., "initiate-pay").
+    /// * `body` - The request payload.
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized body or a `FapshiError`. Non-success responses
+    /// are decoded into `FapshiError::StatusError` with Fapshi's error message.
+    #[cfg(not(feature = "async"))]
+    pub fn post_json<B, T>(&self, endpoint: &str, body: &B) -> Result<T, FapshiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Ok(self.request(Method::POST, endpoint, Some(body))?.body)
+    }
+
+    /// Serializes `body` as JSON, sends it in a POST request and deserializes the response body
+    /// asynchronously.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The API endpoint path (e.g., "initiate-pay").
+    /// * `body` - The request payload.
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized body or a `FapshiError`. Non-success responses
+    /// are decoded into `FapshiError::StatusError` with Fapshi's error message.
+    #[cfg(feature = "async")]
+    pub async fn post_json<B, T>(&self, endpoint: &str, body: &B) -> Result<T, FapshiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Ok(self.request(Method::POST, endpoint, Some(body)).await?.body)
+    }
+
+    /// Sends a request with an optional JSON body and returns the full decoded response.
+    ///
+    /// This is the building block for endpoints the SDK does not cover yet.
+    ///
+    /// # Arguments
+    /// * `method` - The HTTP method.
+    /// * `endpoint` - The API endpoint path, relative to the environment's base URL.
+    /// * `body` - The request payload, serialized as JSON if present.
+    ///
+    /// # Returns
+    /// A `Result` containing an `ApiResponse` with the status, headers, request ID and
+    /// deserialized body, or a `FapshiError`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use fapshi_rs::{
+    ///     client::{ApiResponse, FapshiClient, Method},
+    ///     safety::Environment,
+    /// };
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client =
+    ///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+    /// let response: ApiResponse<serde_json::Value> =
+    ///     client.request::<(), _>(Method::GET, "balance", None)?;
+    /// println!("{} (request id {:?}): {}", response.status, response.request_id, response.body);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "async"))]
+    pub fn request<B, T>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<ApiResponse<T>, FapshiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let body = body.map(serde_json::to_string).transpose()?;
+        let raw = self.send(method, endpoint, body)?;
+        decode_response(raw.status, raw.headers, &raw.text)
     }
+
+    /// Sends a request with an optional JSON body and returns the full decoded response
+    /// asynchronously.
+    ///
+    /// This is the building block for endpoints the SDK does not cover yet.
+    ///
+    /// # Arguments
+    /// * `method` - The HTTP method.
+    /// * `endpoint` - The API endpoint path, relative to the environment's base URL.
+    /// * `body` - The request payload, serialized as JSON if present.
+    ///
+    /// # Returns
+    /// A `Result` containing an `ApiResponse` with the status, headers, request ID and
+    /// deserialized body, or a `FapshiError`.
+    #[cfg(feature = "async")]
+    pub async fn request<B, T>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<ApiResponse<T>, FapshiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let body = body.map(serde_json::to_string).transpose()?;
+        let raw = self.send(method, endpoint, body).await?;
+        decode_response(raw.status, raw.headers, &raw.text)
+    }
+
+    /// Sends a request through the safety checks, the circuit breaker and the cassette, if
+    /// configured.
+    ///
+    /// Every request made by the client goes through here.
+    #[cfg(not(feature = "async"))]
+    fn send(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<String>,
+    ) -> Result<RawResponse, FapshiError> {
+        let request = self.prepare(method, endpoint, body);
+        let reserved = self.admit(&request)?;
+        let audited = self
+            .begin_audit(&request)
+            .inspect_err(|_| self.release(reserved))?;
+        let result = match self.check_circuit(reserved) {
+            Ok(()) => {
+                let started = Instant::now();
+                let result = self.transmit(&request);
+                let elapsed = started.elapsed();
+                self.observe(&request.method, &request.endpoint, &result, elapsed);
+                self.notify(&request, &result, elapsed);
+                self.settle_reservation(reserved, &result);
+                result
+            }
+            Err(err) => Err(err),
+        };
+        self.finish_audit(audited, &result);
+        result
+    }
+
+    /// Sends a request to Fapshi, or answers it from the cassette.
+    #[cfg(not(feature = "async"))]
+    fn transmit(&self, request: &RequestParts) -> Result<RawResponse, FapshiError> {
+        if let Some(cassette) = self.replaying() {
+            let result = cassette.replay_request(
+                &request.method,
+                &request.endpoint,
+                request.body.as_deref(),
+            );
+            self.record_replay(&result);
+            return result;
+        }
+        let url = self.url(&request.endpoint);
+        let mut builder = self
+            .client
+            .request(request.method.clone(), &url)
+            .headers(request.headers.clone());
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        let result = builder.send();
+        self.record_status(result.as_ref().ok().map(|response| response.status()));
+        let response = result?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let raw = RawResponse {
+            status,
+            headers,
+            text: response.text()?,
+        };
+        self.record_exchange(request, &raw);
+        Ok(raw)
+    }
+
+    /// Sends a request through the safety checks, the circuit breaker and the cassette, if
+    /// configured.
+    ///
+    /// Every request made by the client goes through here.
+    #[cfg(feature = "async")]
+    async fn send(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<String>,
+    ) -> Result<RawResponse, FapshiError> {
+        let request = self.prepare(method, endpoint, body);
+        let reserved = self.admit(&request)?;
+        let audited = self
+            .begin_audit(&request)
+            .inspect_err(|_| self.release(reserved))?;
+        let result = match self.check_circuit(reserved) {
+            Ok(()) => {
+                let started = Instant::now();
+                let result = self.transmit(&request).await;
+                let elapsed = started.elapsed();
+                self.observe(&request.method, &request.endpoint, &result, elapsed);
+                self.notify(&request, &result, elapsed);
+                self.settle_reservation(reserved, &result);
+                result
+            }
+            Err(err) => Err(err),
+        };
+        self.finish_audit(audited, &result);
+        result
+    }
+
+    /// Sends a request to Fapshi, or answers it from the cassette.
+    #[cfg(feature = "async")]
+    async fn transmit(&self, request: &RequestParts) -> Result<RawResponse, FapshiError> {
+        if let Some(cassette) = self.replaying() {
+            let result = cassette.replay_request(
+                &request.method,
+                &request.endpoint,
+                request.body.as_deref(),
+            );
+            self.record_replay(&result);
+            return result;
+        }
+        let url = self.url(&request.endpoint);
+        let mut builder = self
+            .client
+            .request(request.method.clone(), &url)
+            .headers(request.headers.clone());
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        let result = builder.send().await;
+        self.record_status(result.as_ref().ok().map(|response| response.status()));
+        let response = result?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let raw = RawResponse {
+            status,
+            headers,
+            text: response.text().await?,
+        };
+        self.record_exchange(request, &raw);
+        Ok(raw)
+    }
+
+    /// Returns the exact request an endpoint call would send, without sending it.
+    ///
+    /// `before_request` hooks are not run, so headers they add are not shown.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The operation, carrying its path parameters and request body.
+    ///
+    /// # Returns
+    /// A `RequestPreview` with the method, URL, headers (API key redacted) and body, or a
+    /// `FapshiError` if the body cannot be serialized.
+    pub fn preview<E: Endpoint>(&self, endpoint: &E) -> Result<RequestPreview, FapshiError> {
+        self.preview_request(E::METHOD, &endpoint.path(), endpoint.body())
+    }
+
+    /// Returns the exact request [`FapshiClient::request`] would send, without sending it.
+    ///
+    /// # Arguments
+    /// * `method` - The HTTP method.
+    /// * `endpoint` - The API endpoint path, relative to the environment's base URL.
+    /// * `body` - The request payload, serialized as JSON if present.
+    pub fn preview_request<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<RequestPreview, FapshiError> {
+        let body = body.map(serde_json::to_string).transpose()?;
+        Ok(self.request_preview(&method, endpoint, &self.headers, body.as_deref()))
+    }
+
+    fn request_preview(
+        &self,
+        method: &Method,
+        endpoint: &str,
+        headers: &HeaderMap,
+        body: Option<&str>,
+    ) -> RequestPreview {
+        RequestPreview::new(
+            self.environment,
+            method.as_str(),
+            &self.url(endpoint),
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.to_str().unwrap_or_default())),
+            body,
+        )
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.base_url, endpoint)
+    }
+
+    /// Builds the request for `endpoint` and runs the `before_request` hooks on it.
+    fn prepare(&self, method: Method, endpoint: &str, body: Option<String>) -> RequestParts {
+        let mut request = RequestParts {
+            method,
+            endpoint: endpoint.to_string(),
+            headers: self.headers.clone(),
+            body,
+        };
+        self.interceptors.before_request(&mut request);
+        request
+    }
+
+    /// Runs the `after_response` hooks on a request that was sent.
+    fn notify(
+        &self,
+        request: &RequestParts,
+        result: &Result<RawResponse, FapshiError>,
+        elapsed: Duration,
+    ) {
+        let response = match result {
+            Ok(raw) => ResponseParts {
+                request,
+                status: Some(raw.status),
+                headers: Some(&raw.headers),
+                body: Some(&raw.text),
+                error: None,
+                elapsed,
+            },
+            Err(err) => ResponseParts {
+                request,
+                status: None,
+                headers: None,
+                body: None,
+                error: Some(err),
+                elapsed,
+            },
+        };
+        self.interceptors.after_response(&response);
+    }
+
+    /// Applies dry-run mode and the amount limits to a request before it is sent.
+    ///
+    /// # Returns
+    /// The amount reserved against the daily limit, if any.
+    fn admit(&self, request: &RequestParts) -> Result<Option<f64>, FapshiError> {
+        let (endpoint, body) = (request.endpoint.as_str(), request.body.as_deref());
+        let guard = self
+            .amount_guard
+            .as_ref()
+            .filter(|_| self.environment == Environment::Live);
+        let amount = safety::body_amount(body);
+        if self.dry_run {
+            safety::validate_body(endpoint, body)?;
+            if let (Some(guard), Some(amount)) = (guard, amount) {
+                guard.check(amount)?;
+            }
+            return Err(FapshiError::DryRun(Box::new(self.request_preview(
+                &request.method,
+                endpoint,
+                &request.headers,
+                body,
+            ))));
+        }
+        match (guard, amount) {
+            (Some(guard), Some(amount)) => {
+                guard.reserve(amount)?;
+                Ok(Some(amount))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Records a finished request and the circuit state in the metrics.
+    fn observe(
+        &self,
+        method: &Method,
+        endpoint: &str,
+        result: &Result<RawResponse, FapshiError>,
+        elapsed: Duration,
+    ) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let status = match result {
+            Ok(raw) => Some(raw.status),
+            Err(FapshiError::HttpError(err)) => err.status(),
+            Err(_) => None,
+        };
+        metrics.record_request(method, endpoint, status.map(|s| s.as_u16()), elapsed);
+        self.observe_circuit();
+    }
+
+    /// Records the circuit breaker state in the metrics.
+    fn observe_circuit(&self) {
+        if let (Some(metrics), Some(state)) = (&self.metrics, self.circuit_state()) {
+            metrics.record_circuit_state(state);
+        }
+    }
+
+    /// Gives back a reserved amount if the request certainly had no effect on Fapshi.
+    fn settle_reservation(&self, reserved: Option<f64>, result: &Result<RawResponse, FapshiError>) {
+        let (Some(amount), Some(guard)) = (reserved, &self.amount_guard) else {
+            return;
+        };
+        let not_processed = match result {
+            Ok(raw) => raw.status.is_client_error(),
+            Err(err) => err.is_not_processed(),
+        };
+        if not_processed {
+            guard.release(amount);
+        }
+    }
+
+    /// Writes the `initiated` audit record of a request that moves money, if a sink is
+    /// attached. The request must not be sent if this fails.
+    fn begin_audit(&self, request: &RequestParts) -> Result<Option<AuditedCall>, FapshiError> {
+        let Some(sink) = &self.audit_sink else {
+            return Ok(None);
+        };
+        let Some(call) = AuditedCall::identify(
+            &request.method,
+            &request.endpoint,
+            request.body.as_deref(),
+            self.audit_actor(),
+            self.environment,
+        ) else {
+            return Ok(None);
+        };
+        call.initiated(sink.as_ref())?;
+        Ok(Some(call))
+    }
+
+    /// Writes the outcome audit record of a call. A failure is reported to the
+    /// `on_record_error` hooks rather than returned, so Fapshi's response is not lost.
+    fn finish_audit(&self, call: Option<AuditedCall>, result: &Result<RawResponse, FapshiError>) {
+        let (Some(call), Some(sink)) = (call, &self.audit_sink) else {
+            return;
+        };
+        let response = result.as_ref().map(|raw| (raw.status, raw.text.as_str()));
+        if let Err(err) = call.finished(sink.as_ref(), response) {
+            self.interceptors.record_error(&err);
+        }
+    }
+
+    /// Returns the cassette if it is in replay mode.
+    fn replaying(&self) -> Option<&Cassette> {
+        self.cassette()
+            .filter(|cassette| cassette.mode() == CassetteMode::Replay)
+    }
+
+    /// Writes a live exchange to the cassette if it is in record mode. A failure is reported
+    /// to the `on_record_error` hooks rather than returned, so Fapshi's response is not lost.
+    fn record_exchange(&self, request: &RequestParts, raw: &RawResponse) {
+        let Some(cassette) = self
+            .cassette()
+            .filter(|cassette| cassette.mode() == CassetteMode::Record)
+        else {
+            return;
+        };
+        let recorded = cassette.record_exchange(
+            &request.method,
+            &request.endpoint,
+            &request.headers,
+            request.body.as_deref(),
+            raw,
+        );
+        if let Err(err) = recorded {
+            self.interceptors.record_error(&err);
+        }
+    }
+}
+
+/// Returns the body of a success response, or a `FapshiError::StatusError`.
+fn text_or_error(raw: RawResponse) -> Result<String, FapshiError> {
+    if !raw.status.is_success() {
+        return Err(status_error(raw.status, &raw.headers, &raw.text));
+    }
+    Ok(raw.text)
+}
+
+/// Builds the `FapshiError::StatusError` for a non-success response.
+pub(crate) fn status_error(status: StatusCode, headers: &HeaderMap, text: &str) -> FapshiError {
+    FapshiError::StatusError {
+        status: status.as_u16(),
+        message: error_message(status, text),
+        request_id: request_id(headers),
+    }
+}
+
+/// Turns a raw HTTP response into an `ApiResponse`, or a `FapshiError::StatusError` for
+/// non-success status codes.
+pub(crate) fn decode_response<T: DeserializeOwned>(
+    status: StatusCode,
+    headers: HeaderMap,
+    text: &str,
+) -> Result<ApiResponse<T>, FapshiError> {
+    if !status.is_success() {
+        return Err(status_error(status, &headers, text));
+    }
+    let request_id = request_id(&headers);
+    // An empty body decodes like `null`, so `()` and `Option<_>` responses work.
+    let text = if text.trim().is_empty() { "null" } else { text };
+    Ok(ApiResponse {
+        status,
+        headers,
+        request_id,
+        body: serde_json::from_str(text)?,
+    })
+}
+
+/// Whether Fapshi refused the request's credentials.
+fn is_auth_error(err: &FapshiError) -> bool {
+    matches!(
+        err,
+        FapshiError::StatusError {
+            status: 401 | 403,
+            ..
+        }
+    )
+}
+
+/// Reads the request identifier from the `x-request-id` header.
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Extracts the `message` field Fapshi puts in error bodies, falling back to the raw body.
+fn error_message(status: StatusCode, text: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|value| value.get("message")?.as_str().map(str::to_string))
+        .or_else(|| Some(text.trim().to_string()).filter(|text| !text.is_empty()))
+        .unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("unknown error")
+                .to_string()
+        })
 }
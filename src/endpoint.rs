@@ -0,0 +1,238 @@
+//! Declarative definitions of the Fapshi API operations.
+//!
+//! Each Fapshi operation is described once by a type implementing [`Endpoint`]: its HTTP method,
+//! path template, request body type and response type. `FapshiClient::call` executes any
+//! endpoint, and the `api` modules are thin wrappers around it. Adding an operation to the SDK
+//! starts with adding its definition here and listing it in [`catalogue`].
+
+use reqwest::Method;
+use serde::{de::DeserializeOwned, de::IgnoredAny, Serialize};
+
+use crate::models::{
+    DirectPaymentRequest, DirectPaymentResponse, PaymentRequest, PaymentResponse,
+    PaymentTransactionResponse, PayoutResponse, Payouts, ServiceBalance, TransactionSearchQuery,
+    TransactionStatus,
+};
+
+/// A single Fapshi API operation.
+pub trait Endpoint {
+    /// The JSON request body type. Use `()` for endpoints without a body.
+    type Request: Serialize;
+    /// The JSON response body type.
+    type Response: DeserializeOwned;
+
+    /// A short, stable name for the operation (e.g., "payment-status").
+    const NAME: &'static str;
+    /// The HTTP method.
+    const METHOD: Method;
+    /// The path template relative to the base URL, with parameters in braces.
+    const PATH: &'static str;
+
+    /// Returns the concrete path with the parameters filled in.
+    fn path(&self) -> String {
+        Self::PATH.to_string()
+    }
+
+    /// Returns the request body, if the operation sends one.
+    fn body(&self) -> Option<&Self::Request> {
+        None
+    }
+}
+
+/// Static description of an endpoint, as listed in the [`catalogue`].
+#[derive(Debug, Clone)]
+pub struct EndpointInfo {
+    /// The operation name.
+    pub name: &'static str,
+    /// The HTTP method.
+    pub method: Method,
+    /// The path template.
+    pub path: &'static str,
+}
+
+impl EndpointInfo {
+    /// Describes the endpoint `E`.
+    pub fn of<E: Endpoint>() -> Self {
+        EndpointInfo {
+            name: E::NAME,
+            method: E::METHOD,
+            path: E::PATH,
+        }
+    }
+}
+
+/// Returns the description of every operation defined in this module.
+pub fn catalogue() -> Vec<EndpointInfo> {
+    vec![
+        EndpointInfo::of::<InitiatePay>(),
+        EndpointInfo::of::<DirectPay>(),
+        EndpointInfo::of::<PaymentStatus>(),
+        EndpointInfo::of::<ExpirePay>(),
+        EndpointInfo::of::<UserTransactions>(),
+        EndpointInfo::of::<SearchTransactions>(),
+        EndpointInfo::of::<Balance>(),
+        EndpointInfo::of::<Payout>(),
+    ]
+}
+
+/// Finds the catalogue entry a concrete request path belongs to, e.g. `payment-status/abc`
+/// belongs to `payment-status`. The query string is ignored.
+pub fn identify(method: &Method, path: &str) -> Option<EndpointInfo> {
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').collect();
+    catalogue().into_iter().find(|info| {
+        let template: Vec<&str> = info.path.split('/').collect();
+        info.method == method
+            && template.len() == segments.len()
+            && template
+                .iter()
+                .zip(&segments)
+                .all(|(t, s)| t == s || (t.starts_with('{') && !s.is_empty()))
+    })
+}
+
+/// Creates a payment link (`POST /initiate-pay`).
+#[derive(Debug, Clone, Copy)]
+pub struct InitiatePay<'a>(pub &'a PaymentRequest);
+
+impl Endpoint for InitiatePay<'_> {
+    type Request = PaymentRequest;
+    type Response = PaymentResponse;
+
+    const NAME: &'static str = "initiate-pay";
+    const METHOD: Method = Method::POST;
+    const PATH: &'static str = "initiate-pay";
+
+    fn body(&self) -> Option<&PaymentRequest> {
+        Some(self.0)
+    }
+}
+
+/// Initiates a direct payment to a mobile device (`POST /direct-pay`).
+#[derive(Debug, Clone, Copy)]
+pub struct DirectPay<'a>(pub &'a DirectPaymentRequest);
+
+impl Endpoint for DirectPay<'_> {
+    type Request = DirectPaymentRequest;
+    type Response = DirectPaymentResponse;
+
+    const NAME: &'static str = "direct-pay";
+    const METHOD: Method = Method::POST;
+    const PATH: &'static str = "direct-pay";
+
+    fn body(&self) -> Option<&DirectPaymentRequest> {
+        Some(self.0)
+    }
+}
+
+/// Retrieves the status of a transaction (`GET /payment-status/{transId}`).
+#[derive(Debug, Clone, Copy)]
+pub struct PaymentStatus<'a>(pub &'a str);
+
+impl Endpoint for PaymentStatus<'_> {
+    type Request = ();
+    type Response = TransactionStatus;
+
+    const NAME: &'static str = "payment-status";
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "payment-status/{transId}";
+
+    fn path(&self) -> String {
+        format!("payment-status/{}", self.0)
+    }
+}
+
+/// Expires a transaction (`POST /expire-pay`).
+#[derive(Debug, Clone)]
+pub struct ExpirePay(pub PaymentTransactionResponse);
+
+impl ExpirePay {
+    /// Creates the endpoint for the transaction `transaction_id`.
+    pub fn new(transaction_id: &str) -> Self {
+        ExpirePay(PaymentTransactionResponse {
+            transaction_id: transaction_id.to_string(),
+        })
+    }
+}
+
+impl Endpoint for ExpirePay {
+    type Request = PaymentTransactionResponse;
+    type Response = IgnoredAny;
+
+    const NAME: &'static str = "expire-pay";
+    const METHOD: Method = Method::POST;
+    const PATH: &'static str = "expire-pay";
+
+    fn body(&self) -> Option<&PaymentTransactionResponse> {
+        Some(&self.0)
+    }
+}
+
+/// Retrieves all transactions of a user (`GET /transaction/{userId}`).
+#[derive(Debug, Clone, Copy)]
+pub struct UserTransactions<'a>(pub &'a str);
+
+impl Endpoint for UserTransactions<'_> {
+    type Request = ();
+    type Response = Vec<TransactionStatus>;
+
+    const NAME: &'static str = "user-transactions";
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "transaction/{userId}";
+
+    fn path(&self) -> String {
+        format!("transaction/{}", self.0)
+    }
+}
+
+/// Searches transactions (`GET /search?status=..&medium=..`).
+#[derive(Debug, Clone, Copy)]
+pub struct SearchTransactions<'a>(pub &'a TransactionSearchQuery);
+
+impl Endpoint for SearchTransactions<'_> {
+    type Request = ();
+    type Response = Vec<TransactionStatus>;
+
+    const NAME: &'static str = "search";
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "search";
+
+    fn path(&self) -> String {
+        let query = self.0.to_query_string();
+        if query.is_empty() {
+            Self::PATH.to_string()
+        } else {
+            format!("search?{query}")
+        }
+    }
+}
+
+/// Retrieves the service balance (`GET /balance`).
+#[derive(Debug, Clone, Copy)]
+pub struct Balance;
+
+impl Endpoint for Balance {
+    type Request = ();
+    type Response = ServiceBalance;
+
+    const NAME: &'static str = "balance";
+    const METHOD: Method = Method::GET;
+    const PATH: &'static str = "balance";
+}
+
+/// Sends money to a mobile money or Orange money account (`POST /payout`).
+#[derive(Debug, Clone, Copy)]
+pub struct Payout<'a>(pub &'a Payouts);
+
+impl Endpoint for Payout<'_> {
+    type Request = Payouts;
+    type Response = PayoutResponse;
+
+    const NAME: &'static str = "payout";
+    const METHOD: Method = Method::POST;
+    const PATH: &'static str = "payout";
+
+    fn body(&self) -> Option<&Payouts> {
+        Some(self.0)
+    }
+}
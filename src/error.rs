@@ -1,6 +1,10 @@
+use std::time::Duration;
+
 use reqwest::Error as ReqwestError;
 use thiserror::Error;
 
+use crate::{preview::RequestPreview, safety::Environment};
+
 /// Custom error type for the Fapshi SDK.
 ///
 /// This enum encapsulates various errors that can occur when interacting with the Fapshi API,
@@ -19,9 +23,71 @@ pub enum FapshiError {
     #[error("API error: {0}")]
     ApiError(String),
 
+    /// The Fapshi API answered with a non-success status code.
+    #[error("Fapshi API returned status {status}: {message}")]
+    StatusError {
+        /// The HTTP status code.
+        status: u16,
+        /// The error message from the response body.
+        message: String,
+        /// The request identifier, if the response carried one.
+        request_id: Option<String>,
+    },
+
     /// An error occurred during JSON serialization or deserialization.
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+
+    /// The request was rejected locally before being sent to Fapshi.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// A payment already exists on Fapshi for the given external ID.
+    #[error(
+        "A payment already exists for external ID {external_id}: transaction {transaction_id}"
+    )]
+    DuplicatePayment {
+        /// The external ID the payment was requested for.
+        external_id: String,
+        /// The ID of the existing Fapshi transaction.
+        transaction_id: String,
+    },
+
+    /// Another request for the same external ID is being sent, or was interrupted too
+    /// recently to tell whether Fapshi received it.
+    #[error("A payment for external ID {external_id} is already in progress")]
+    PaymentInProgress {
+        /// The external ID the payment was requested for.
+        external_id: String,
+    },
+
+    /// An I/O error occurred while reading or writing local state.
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// The circuit breaker is open; the request was not sent. Holds the remaining cool-down.
+    #[error("Circuit breaker is open, retry in {0:?}")]
+    CircuitOpen(Duration),
+
+    /// The credentials were refused by the configured environment but accepted by the other
+    /// one, e.g. sandbox credentials used against live.
+    #[error("Credentials belong to the {detected} environment, but the client uses {configured}")]
+    EnvironmentMismatch {
+        /// The environment the client was created for.
+        configured: Environment,
+        /// The environment that accepted the credentials.
+        detected: Environment,
+    },
+
+    /// The client is in dry-run mode; the request was validated but not sent. Holds the
+    /// request that would have been sent.
+    #[error("Dry run, not sent: {0}")]
+    DryRun(Box<RequestPreview>),
+
+    /// A `tower` service or layer wrapping the HTTP transport failed, e.g. a timeout layer
+    /// gave up. Whether the request reached Fapshi is unknown.
+    #[error("Service error: {0}")]
+    ServiceError(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl From<ReqwestError> for FapshiError {
@@ -29,3 +95,22 @@ impl From<ReqwestError> for FapshiError {
         FapshiError::HttpError(err)
     }
 }
+
+impl FapshiError {
+    /// Returns whether the request certainly had no effect on Fapshi: it was refused with a
+    /// 4xx status or was never sent. Timeouts, 5xx responses and unreadable responses may hide
+    /// a request that went through, so they return `false`.
+    pub fn is_not_processed(&self) -> bool {
+        match self {
+            FapshiError::StatusError { status, .. } => *status < 500,
+            FapshiError::HttpError(err) => err.is_connect() || err.is_builder(),
+            FapshiError::HeaderError(_)
+            | FapshiError::InvalidRequest(_)
+            | FapshiError::PaymentInProgress { .. }
+            | FapshiError::CircuitOpen(_)
+            | FapshiError::EnvironmentMismatch { .. }
+            | FapshiError::DryRun(_) => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::transaction::TransactionApi,
+    client::FapshiClient,
+    error::FapshiError,
+    models::{PaymentResponse, Status, TransactionStatus, PAYMENT_LINK_VALIDITY},
+};
+
+/// When a payment link expires.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkExpiry {
+    /// The transaction ID of the link.
+    pub transaction_id: String,
+    /// The last known status, if the link was read from Fapshi.
+    pub status: Option<Status>,
+    /// When the link was created.
+    pub initiated_at: DateTime<Utc>,
+    /// When the link stops accepting payments.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl LinkExpiry {
+    /// Builds the expiry of a transaction's link, if its `date_initiated` can be parsed.
+    pub fn from_transaction(transaction: &TransactionStatus) -> Option<Self> {
+        let initiated_at = transaction.initiated_at()?;
+        Some(LinkExpiry {
+            transaction_id: transaction.transaction_id.clone(),
+            status: Some(transaction.status.clone()),
+            initiated_at,
+            expires_at: initiated_at + PAYMENT_LINK_VALIDITY,
+        })
+    }
+
+    /// Returns the time left before the link expires, negative once it has expired.
+    pub fn remaining(&self, now: DateTime<Utc>) -> TimeDelta {
+        self.expires_at - now
+    }
+
+    /// Returns whether the link has expired at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Lists the open (CREATED or PENDING) links among `transactions` that expire within `window`
+/// of `now`, soonest first. Links that have already expired are not included.
+pub fn expiring_links(
+    transactions: &[TransactionStatus],
+    window: Duration,
+    now: DateTime<Utc>,
+) -> Vec<LinkExpiry> {
+    let window = TimeDelta::from_std(window).unwrap_or(TimeDelta::MAX);
+    let mut links: Vec<LinkExpiry> = transactions
+        .iter()
+        .filter(|tx| is_open(&tx.status))
+        .filter_map(LinkExpiry::from_transaction)
+        .filter(|link| !link.is_expired(now) && link.remaining(now) <= window)
+        .collect();
+    links.sort_by_key(|link| link.expires_at);
+    links
+}
+
+/// What an [`ExpiryScheduler`] did with a link that reached its TTL.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ExpiryOutcome {
+    /// The link was still open and has been expired.
+    Expired {
+        /// The transaction ID of the link.
+        transaction_id: String,
+    },
+    /// The link had already reached a final status, so it was left alone.
+    AlreadyFinal {
+        /// The transaction ID of the link.
+        transaction_id: String,
+        /// The status found on Fapshi.
+        status: Status,
+    },
+    /// The status could not be read or the link could not be expired; it is retried on the
+    /// next run.
+    Failed {
+        /// The transaction ID of the link.
+        transaction_id: String,
+        /// The error message.
+        error: String,
+    },
+}
+
+type OutcomeCallback = Box<dyn Fn(&ExpiryOutcome) + Send + Sync>;
+
+/// Expires payment links after a custom time-to-live shorter than Fapshi's 24 hours.
+///
+/// Track links as they are created; each run checks the links older than the TTL and calls
+/// [`TransactionApi::expire_transaction`] on those still CREATED or PENDING. Links that are
+/// expired or already final are no longer tracked.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use fapshi_rs::{
+///     api::payment::PaymentApi,
+///     client::FapshiClient,
+///     expiry::ExpiryScheduler,
+///     models::PaymentRequest,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let mut scheduler = ExpiryScheduler::new(Duration::from_secs(15 * 60))
+///     .on_outcome(|outcome| println!("{:?}", outcome));
+/// let request = PaymentRequest {
+///     amount: 2500.0,
+///     email: None,
+///     redirect_url: None,
+///     user_id: None,
+///     external_id: Some("flash-sale-7".to_string()),
+///     message: "Flash sale".to_string(),
+///     card_only: None,
+/// };
+/// let response = PaymentApi::create_payment(&client, &request)?;
+/// scheduler.track(&response);
+/// scheduler.run(&client, Duration::from_secs(60));
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExpiryScheduler {
+    ttl: TimeDelta,
+    links: HashMap<String, DateTime<Utc>>,
+    callbacks: Vec<OutcomeCallback>,
+}
+
+impl ExpiryScheduler {
+    /// Creates a scheduler expiring links `ttl` after they were created.
+    ///
+    /// A TTL longer than 24 hours has no effect, as Fapshi expires links by then.
+    pub fn new(ttl: Duration) -> Self {
+        ExpiryScheduler {
+            ttl: TimeDelta::from_std(ttl)
+                .unwrap_or(PAYMENT_LINK_VALIDITY)
+                .min(PAYMENT_LINK_VALIDITY),
+            links: HashMap::new(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Registers a callback invoked with the outcome of every link handled by a run.
+    pub fn on_outcome(mut self, callback: impl Fn(&ExpiryOutcome) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Tracks a newly created payment link.
+    ///
+    /// If `date_initiated` cannot be parsed, the current time is used instead.
+    pub fn track(&mut self, response: &PaymentResponse) {
+        let initiated_at = response.initiated_at().unwrap_or_else(Utc::now);
+        self.track_at(&response.transaction_id, initiated_at);
+    }
+
+    /// Tracks a payment link created at `initiated_at`.
+    pub fn track_at(&mut self, transaction_id: &str, initiated_at: DateTime<Utc>) {
+        self.links.insert(transaction_id.to_string(), initiated_at);
+    }
+
+    /// Stops tracking a link, e.g. once your webhook reports it as paid.
+    pub fn untrack(&mut self, transaction_id: &str) -> bool {
+        self.links.remove(transaction_id).is_some()
+    }
+
+    /// Returns the number of tracked links.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Returns whether no links are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Returns when a tracked link will be expired by the scheduler.
+    pub fn deadline(&self, transaction_id: &str) -> Option<DateTime<Utc>> {
+        self.links
+            .get(transaction_id)
+            .map(|initiated| *initiated + self.ttl)
+    }
+
+    /// Returns the tracked links whose TTL has elapsed at `now`.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut due: Vec<(&String, &DateTime<Utc>)> = self
+            .links
+            .iter()
+            .filter(|(_, initiated)| **initiated + self.ttl <= now)
+            .collect();
+        due.sort_by_key(|(_, initiated)| **initiated);
+        due.into_iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    /// Lists the tracked links the scheduler will expire within `window` of `now`, soonest
+    /// first. `expires_at` is the scheduler's deadline, not Fapshi's.
+    pub fn expiring_within(&self, window: Duration, now: DateTime<Utc>) -> Vec<LinkExpiry> {
+        let window = TimeDelta::from_std(window).unwrap_or(TimeDelta::MAX);
+        let mut links: Vec<LinkExpiry> = self
+            .links
+            .iter()
+            .map(|(id, initiated)| LinkExpiry {
+                transaction_id: id.clone(),
+                status: None,
+                initiated_at: *initiated,
+                expires_at: *initiated + self.ttl,
+            })
+            .filter(|link| !link.is_expired(now) && link.remaining(now) <= window)
+            .collect();
+        links.sort_by_key(|link| link.expires_at);
+        links
+    }
+
+    /// Expires the due links that are still open, synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    ///
+    /// # Returns
+    /// The outcome for each due link. Failures are reported as [`ExpiryOutcome::Failed`] and
+    /// the link is retried on the next run.
+    #[cfg(not(feature = "async"))]
+    pub fn run_once(&mut self, client: &FapshiClient) -> Vec<ExpiryOutcome> {
+        let mut outcomes = Vec::new();
+        for transaction_id in self.due(Utc::now()) {
+            let outcome = match TransactionApi::get_status(client, &transaction_id) {
+                Ok(tx) if is_open(&tx.status) => {
+                    match TransactionApi::expire_transaction(client, &transaction_id) {
+                        Ok(()) => ExpiryOutcome::Expired { transaction_id },
+                        Err(err) => failed(transaction_id, err),
+                    }
+                }
+                Ok(tx) => ExpiryOutcome::AlreadyFinal {
+                    transaction_id,
+                    status: tx.status,
+                },
+                Err(err) => failed(transaction_id, err),
+            };
+            outcomes.push(self.settle(outcome));
+        }
+        outcomes
+    }
+
+    /// Expires the due links that are still open, asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    ///
+    /// # Returns
+    /// The outcome for each due link. Failures are reported as [`ExpiryOutcome::Failed`] and
+    /// the link is retried on the next run.
+    #[cfg(feature = "async")]
+    pub async fn run_once(&mut self, client: &FapshiClient) -> Vec<ExpiryOutcome> {
+        let mut outcomes = Vec::new();
+        for transaction_id in self.due(Utc::now()) {
+            let outcome = match TransactionApi::get_status(client, &transaction_id).await {
+                Ok(tx) if is_open(&tx.status) => {
+                    match TransactionApi::expire_transaction(client, &transaction_id).await {
+                        Ok(()) => ExpiryOutcome::Expired { transaction_id },
+                        Err(err) => failed(transaction_id, err),
+                    }
+                }
+                Ok(tx) => ExpiryOutcome::AlreadyFinal {
+                    transaction_id,
+                    status: tx.status,
+                },
+                Err(err) => failed(transaction_id, err),
+            };
+            outcomes.push(self.settle(outcome));
+        }
+        outcomes
+    }
+
+    /// Runs the scheduler every `interval`, forever, blocking the current thread.
+    #[cfg(not(feature = "async"))]
+    pub fn run(&mut self, client: &FapshiClient, interval: Duration) {
+        loop {
+            self.run_once(client);
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Runs the scheduler every `interval`, forever.
+    #[cfg(feature = "async")]
+    pub async fn run(&mut self, client: &FapshiClient, interval: Duration) {
+        loop {
+            self.run_once(client).await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Stops tracking settled links and notifies the callbacks.
+    fn settle(&mut self, outcome: ExpiryOutcome) -> ExpiryOutcome {
+        match &outcome {
+            ExpiryOutcome::Expired { transaction_id }
+            | ExpiryOutcome::AlreadyFinal { transaction_id, .. } => {
+                self.links.remove(transaction_id);
+            }
+            ExpiryOutcome::Failed { .. } => {}
+        }
+        for callback in &self.callbacks {
+            callback(&outcome);
+        }
+        outcome
+    }
+}
+
+fn is_open(status: &Status) -> bool {
+    matches!(status, Status::CREATED | Status::PENDING)
+}
+
+fn failed(transaction_id: String, err: FapshiError) -> ExpiryOutcome {
+    ExpiryOutcome::Failed {
+        transaction_id,
+        error: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 31, hour, minute, 0).unwrap()
+    }
+
+    fn transaction(id: &str, status: &str, initiated: &str) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": 500.0,
+            "email": "",
+            "redirectUrl": "",
+            "dateInitiated": initiated,
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn links_expire_a_day_after_creation() {
+        let tx = transaction("T1", "CREATED", "2025-01-31T09:15:00.000Z");
+        let link = LinkExpiry::from_transaction(&tx).unwrap();
+        assert_eq!(
+            link.expires_at,
+            Utc.with_ymd_and_hms(2025, 2, 1, 9, 15, 0).unwrap()
+        );
+        assert_eq!(tx.expires_at(), Some(link.expires_at));
+        assert_eq!(link.remaining(at(21, 15)), TimeDelta::hours(12));
+        assert!(!link.is_expired(at(21, 15)));
+        assert!(link.is_expired(link.expires_at));
+    }
+
+    #[test]
+    fn only_open_links_expiring_in_the_window_are_listed() {
+        let transactions = vec![
+            transaction("late", "PENDING", "2025-01-30T12:00:00.000Z"),
+            transaction("soon", "CREATED", "2025-01-30T10:00:00.000Z"),
+            transaction("paid", "SUCCESSFUL", "2025-01-30T10:00:00.000Z"),
+            transaction("gone", "CREATED", "2025-01-30T08:00:00.000Z"),
+            transaction("fresh", "CREATED", "2025-01-31T08:00:00.000Z"),
+            transaction("undated", "CREATED", ""),
+        ];
+        let links = expiring_links(&transactions, Duration::from_secs(4 * 3600), at(9, 0));
+        let ids: Vec<_> = links.iter().map(|l| l.transaction_id.as_str()).collect();
+        assert_eq!(ids, vec!["soon", "late"]);
+    }
+
+    #[test]
+    fn scheduler_tracks_links_until_their_ttl() {
+        let mut scheduler = ExpiryScheduler::new(Duration::from_secs(15 * 60));
+        scheduler.track_at("T1", at(9, 0));
+        scheduler.track_at("T2", at(9, 10));
+        assert_eq!(scheduler.len(), 2);
+        assert_eq!(scheduler.deadline("T1"), Some(at(9, 15)));
+        assert!(scheduler.due(at(9, 14)).is_empty());
+        assert_eq!(scheduler.due(at(9, 30)), vec!["T1", "T2"]);
+
+        let soon = scheduler.expiring_within(Duration::from_secs(10 * 60), at(9, 10));
+        assert_eq!(soon.len(), 1);
+        assert_eq!(soon[0].expires_at, at(9, 15));
+
+        assert!(scheduler.untrack("T1"));
+        assert!(!scheduler.untrack("T1"));
+        assert_eq!(scheduler.due(at(9, 30)), vec!["T2"]);
+    }
+
+    #[test]
+    fn ttl_is_capped_at_the_link_validity() {
+        let mut scheduler = ExpiryScheduler::new(Duration::from_secs(48 * 3600));
+        scheduler.track_at("T1", at(9, 0));
+        assert_eq!(
+            scheduler.deadline("T1"),
+            Some(at(9, 0) + PAYMENT_LINK_VALIDITY)
+        );
+    }
+}
@@ -0,0 +1,798 @@
+use std::{fmt::Write as _, io::Write};
+
+use chrono::format::{Item, StrftimeItems};
+use serde_json::{Map, Value};
+
+use crate::{
+    api::transaction::TransactionApi,
+    client::FapshiClient,
+    error::FapshiError,
+    models::{parse_date, TransactionSearchQuery, TransactionStatus},
+};
+
+/// The file format produced by a [`TransactionExporter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row and `\n` line endings.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+    /// CSV that Excel opens correctly: UTF-8 byte order mark and `\r\n` line endings. Text
+    /// fields starting with `=`, `+`, `-`, `@`, a tab or a carriage return are prefixed with
+    /// `'` so that Excel shows them as text instead of running them as formulas.
+    ExcelCsv,
+}
+
+/// A field of `TransactionStatus` that can be exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The transaction ID (`transId`).
+    TransactionId,
+    /// The transaction status.
+    Status,
+    /// The payment medium.
+    Medium,
+    /// The name of the Fapshi service.
+    ServiceName,
+    /// The amount paid.
+    Amount,
+    /// The amount credited to the service.
+    Revenue,
+    /// The Fapshi fee (amount minus revenue).
+    Fee,
+    /// The payer's name.
+    PayerName,
+    /// The payer's email.
+    Email,
+    /// The redirect URL.
+    RedirectUrl,
+    /// The external ID.
+    ExternalId,
+    /// The user ID.
+    UserId,
+    /// The operator's transaction ID.
+    FinancialTransactionId,
+    /// When the transaction was initiated.
+    DateInitiated,
+    /// When the transaction was confirmed.
+    DateConfirmed,
+}
+
+impl Column {
+    /// Every column, in `TransactionStatus` field order.
+    pub const ALL: [Column; 15] = [
+        Column::TransactionId,
+        Column::Status,
+        Column::Medium,
+        Column::ServiceName,
+        Column::Amount,
+        Column::Revenue,
+        Column::Fee,
+        Column::PayerName,
+        Column::Email,
+        Column::RedirectUrl,
+        Column::ExternalId,
+        Column::UserId,
+        Column::FinancialTransactionId,
+        Column::DateInitiated,
+        Column::DateConfirmed,
+    ];
+
+    /// The header used for the column, matching the Fapshi API field name.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::TransactionId => "transId",
+            Column::Status => "status",
+            Column::Medium => "medium",
+            Column::ServiceName => "serviceName",
+            Column::Amount => "amount",
+            Column::Revenue => "revenue",
+            Column::Fee => "fee",
+            Column::PayerName => "payerName",
+            Column::Email => "email",
+            Column::RedirectUrl => "redirectUrl",
+            Column::ExternalId => "externalId",
+            Column::UserId => "userId",
+            Column::FinancialTransactionId => "financialTransId",
+            Column::DateInitiated => "dateInitiated",
+            Column::DateConfirmed => "dateConfirmed",
+        }
+    }
+}
+
+/// Options controlling what a [`TransactionExporter`] writes and how values are formatted.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// The output format.
+    pub format: ExportFormat,
+    /// The columns to write, in order.
+    pub columns: Vec<Column>,
+    /// A `chrono` format string for dates (e.g., `"%Y-%m-%d %H:%M"`); `None` keeps Fapshi's value.
+    /// An invalid format fails the export with `FapshiError::InvalidRequest`.
+    pub date_format: Option<String>,
+    /// The number of decimals for amounts; `None` writes them unrounded.
+    pub amount_decimals: Option<usize>,
+    /// The decimal separator for amounts in CSV output (e.g., `','` for French locales).
+    pub decimal_separator: char,
+    /// The field delimiter for CSV output.
+    pub delimiter: char,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            format: ExportFormat::Csv,
+            columns: Column::ALL.to_vec(),
+            date_format: None,
+            amount_decimals: None,
+            decimal_separator: '.',
+            delimiter: ',',
+        }
+    }
+}
+
+/// Streams `TransactionStatus` records to a writer as CSV, JSON Lines or Excel-compatible CSV.
+///
+/// Records are written as they are passed in. [`export_search`](Self::export_search) pages
+/// through the search results, so it holds at most one page of transactions in memory;
+/// [`export_user`](Self::export_user) holds the full history Fapshi returns for the user.
+///
+/// # Example
+/// ```no_run
+/// use std::fs::File;
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     export::{Column, ExportFormat, ExportOptions, TransactionExporter},
+///     models::TransactionSearchQuery,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let options = ExportOptions {
+///     format: ExportFormat::ExcelCsv,
+///     columns: vec![Column::TransactionId, Column::Status, Column::Amount, Column::DateConfirmed],
+///     date_format: Some("%d/%m/%Y %H:%M".to_string()),
+///     amount_decimals: Some(0),
+///     ..Default::default()
+/// };
+/// let mut exporter = TransactionExporter::new(File::create("january.csv")?, options);
+/// let query = TransactionSearchQuery {
+///     start: Some("2025-01-01".to_string()),
+///     end: Some("2025-01-31".to_string()),
+///     ..Default::default()
+/// };
+/// exporter.export_search(&client, &query)?;
+/// exporter.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TransactionExporter<W: Write> {
+    writer: W,
+    options: ExportOptions,
+    started: bool,
+    written: usize,
+}
+
+impl<W: Write> TransactionExporter<W> {
+    /// Creates an exporter writing to `writer`.
+    pub fn new(writer: W, options: ExportOptions) -> Self {
+        TransactionExporter {
+            writer,
+            options,
+            started: false,
+            written: 0,
+        }
+    }
+
+    /// Returns the number of records written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Writes a single record, preceded by the header on the first call.
+    pub fn write(&mut self, transaction: &TransactionStatus) -> Result<(), FapshiError> {
+        self.start()?;
+        match self.options.format {
+            ExportFormat::JsonLines => {
+                let mut object = Map::new();
+                for column in &self.options.columns {
+                    object.insert(
+                        column.header().to_string(),
+                        self.json_value(*column, transaction)?,
+                    );
+                }
+                serde_json::to_writer(&mut self.writer, &object)?;
+                self.writer.write_all(b"\n")?;
+            }
+            ExportFormat::Csv | ExportFormat::ExcelCsv => {
+                let fields: Vec<String> = self
+                    .options
+                    .columns
+                    .iter()
+                    .map(|column| self.text_value(*column, transaction))
+                    .collect::<Result<_, _>>()?;
+                self.write_csv_row(&fields)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Writes every record yielded by `transactions`.
+    pub fn write_all<'a>(
+        &mut self,
+        transactions: impl IntoIterator<Item = &'a TransactionStatus>,
+    ) -> Result<(), FapshiError> {
+        transactions.into_iter().try_for_each(|tx| self.write(tx))
+    }
+
+    /// Writes the header if nothing was written yet, flushes, and returns the writer.
+    pub fn finish(mut self) -> Result<W, FapshiError> {
+        self.start()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Exports the transactions matching `query`.
+    ///
+    /// Fapshi's search has no cursor and returns at most 100 transactions, so unless `query`
+    /// sets `limit` (in which case one request is made and at most `limit` records written),
+    /// a search that returns a full page is split into halves of its `start`..=`end` range,
+    /// and a full single day into one search per status.
+    ///
+    /// # Returns
+    /// The number of records written.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` when a full page cannot be split: `query` has no
+    /// `start` and `end`, or a single day has more than 100 transactions of one status.
+    /// Records written before the error stay in the writer.
+    #[cfg(not(feature = "async"))]
+    pub fn export_search(
+        &mut self,
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<usize, FapshiError> {
+        if query.limit.is_some() {
+            let transactions = TransactionApi::search_transactions(client, query)?;
+            self.write_all(&transactions)?;
+            return Ok(transactions.len());
+        }
+        let mut count = 0;
+        TransactionApi::search_pages(client, query, |page| {
+            count += page.len();
+            self.write_all(&page)
+        })?;
+        Ok(count)
+    }
+
+    /// Exports the transactions matching `query`, asynchronously.
+    ///
+    /// Fapshi's search has no cursor and returns at most 100 transactions, so unless `query`
+    /// sets `limit` (in which case one request is made and at most `limit` records written),
+    /// a search that returns a full page is split into halves of its `start`..=`end` range,
+    /// and a full single day into one search per status.
+    ///
+    /// # Returns
+    /// The number of records written.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` when a full page cannot be split: `query` has no
+    /// `start` and `end`, or a single day has more than 100 transactions of one status.
+    /// Records written before the error stay in the writer.
+    #[cfg(feature = "async")]
+    pub async fn export_search(
+        &mut self,
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<usize, FapshiError> {
+        if query.limit.is_some() {
+            let transactions = TransactionApi::search_transactions(client, query).await?;
+            self.write_all(&transactions)?;
+            return Ok(transactions.len());
+        }
+        let mut count = 0;
+        TransactionApi::search_pages(client, query, |page| {
+            count += page.len();
+            self.write_all(&page)
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Exports the transactions of `user_id`.
+    ///
+    /// # Returns
+    /// The number of records written.
+    #[cfg(not(feature = "async"))]
+    pub fn export_user(
+        &mut self,
+        client: &FapshiClient,
+        user_id: &str,
+    ) -> Result<usize, FapshiError> {
+        let transactions = TransactionApi::get_transactions_by_user_id(client, user_id)?;
+        self.write_all(&transactions)?;
+        Ok(transactions.len())
+    }
+
+    /// Exports the transactions of `user_id`, asynchronously.
+    ///
+    /// # Returns
+    /// The number of records written.
+    #[cfg(feature = "async")]
+    pub async fn export_user(
+        &mut self,
+        client: &FapshiClient,
+        user_id: &str,
+    ) -> Result<usize, FapshiError> {
+        let transactions = TransactionApi::get_transactions_by_user_id(client, user_id).await?;
+        self.write_all(&transactions)?;
+        Ok(transactions.len())
+    }
+
+    /// Fetches and exports the transactions with the given IDs, one request at a time.
+    ///
+    /// # Returns
+    /// The number of records written.
+    #[cfg(not(feature = "async"))]
+    pub fn export_ids<'a>(
+        &mut self,
+        client: &FapshiClient,
+        transaction_ids: impl IntoIterator<Item = &'a str>,
+    ) -> Result<usize, FapshiError> {
+        let mut count = 0;
+        for transaction_id in transaction_ids {
+            self.write(&TransactionApi::get_status(client, transaction_id)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Fetches and exports the transactions with the given IDs, one request at a time,
+    /// asynchronously.
+    ///
+    /// # Returns
+    /// The number of records written.
+    #[cfg(feature = "async")]
+    pub async fn export_ids<'a>(
+        &mut self,
+        client: &FapshiClient,
+        transaction_ids: impl IntoIterator<Item = &'a str>,
+    ) -> Result<usize, FapshiError> {
+        let mut count = 0;
+        for transaction_id in transaction_ids {
+            self.write(&TransactionApi::get_status(client, transaction_id).await?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn start(&mut self) -> Result<(), FapshiError> {
+        if self.started {
+            return Ok(());
+        }
+        if let Some(format) = &self.options.date_format {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(invalid_date_format(format));
+            }
+        }
+        self.started = true;
+        match self.options.format {
+            ExportFormat::JsonLines => Ok(()),
+            ExportFormat::Csv | ExportFormat::ExcelCsv => {
+                if self.options.format == ExportFormat::ExcelCsv {
+                    self.writer.write_all("\u{feff}".as_bytes())?;
+                }
+                let headers: Vec<String> = self
+                    .options
+                    .columns
+                    .iter()
+                    .map(|column| column.header().to_string())
+                    .collect();
+                self.write_csv_row(&headers)
+            }
+        }
+    }
+
+    fn write_csv_row(&mut self, fields: &[String]) -> Result<(), FapshiError> {
+        let line_ending = match self.options.format {
+            ExportFormat::ExcelCsv => "\r\n",
+            _ => "\n",
+        };
+        let row = csv_row(fields, self.options.delimiter);
+        self.writer.write_all(row.as_bytes())?;
+        self.writer.write_all(line_ending.as_bytes())?;
+        Ok(())
+    }
+
+    fn text_value(&self, column: Column, tx: &TransactionStatus) -> Result<String, FapshiError> {
+        Ok(match column {
+            Column::Amount => self.format_amount(Some(tx.amount)),
+            Column::Revenue => self.format_amount(tx.revenue),
+            Column::Fee => self.format_amount(tx.fee()),
+            Column::DateInitiated => self.format_date(&tx.date_initiated)?,
+            Column::DateConfirmed => self.format_date(&tx.date_confirmed)?,
+            _ if self.options.format == ExportFormat::ExcelCsv => {
+                escape_formula(string_value(column, tx))
+            }
+            _ => string_value(column, tx),
+        })
+    }
+
+    fn json_value(&self, column: Column, tx: &TransactionStatus) -> Result<Value, FapshiError> {
+        let amount = |amount: Option<f64>| {
+            amount
+                .map(|amount| match self.options.amount_decimals {
+                    Some(decimals) => round(amount, decimals),
+                    None => amount,
+                })
+                .map_or(Value::Null, Value::from)
+        };
+        Ok(match column {
+            Column::Amount => amount(Some(tx.amount)),
+            Column::Revenue => amount(tx.revenue),
+            Column::Fee => amount(tx.fee()),
+            Column::DateInitiated => Value::from(self.format_date(&tx.date_initiated)?),
+            Column::DateConfirmed => Value::from(self.format_date(&tx.date_confirmed)?),
+            _ => Value::from(string_value(column, tx)),
+        })
+    }
+
+    fn format_amount(&self, amount: Option<f64>) -> String {
+        let Some(amount) = amount else {
+            return String::new();
+        };
+        let formatted = match self.options.amount_decimals {
+            Some(decimals) => format!("{amount:.decimals$}"),
+            None => amount.to_string(),
+        };
+        if self.options.decimal_separator == '.' {
+            formatted
+        } else {
+            formatted.replace('.', &self.options.decimal_separator.to_string())
+        }
+    }
+
+    fn format_date(&self, date: &str) -> Result<String, FapshiError> {
+        match (&self.options.date_format, parse_date(date)) {
+            (Some(format), Some(parsed)) => {
+                let mut formatted = String::new();
+                write!(formatted, "{}", parsed.format(format))
+                    .map_err(|_| invalid_date_format(format))?;
+                Ok(formatted)
+            }
+            _ => Ok(date.to_string()),
+        }
+    }
+}
+
+fn invalid_date_format(format: &str) -> FapshiError {
+    FapshiError::InvalidRequest(format!("invalid date format {format:?}"))
+}
+
+fn string_value(column: Column, tx: &TransactionStatus) -> String {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    match column {
+        Column::TransactionId => tx.transaction_id.clone(),
+        Column::Status => tx.status.to_string(),
+        Column::Medium => optional(&tx.medium),
+        Column::ServiceName => tx.service_name.clone(),
+        Column::PayerName => optional(&tx.payer_name),
+        Column::Email => tx.email.clone(),
+        Column::RedirectUrl => tx.redirect_url.clone(),
+        Column::ExternalId => optional(&tx.external_id),
+        Column::UserId => optional(&tx.user_id),
+        Column::FinancialTransactionId => optional(&tx.financial_transaction_id),
+        Column::Amount => tx.amount.to_string(),
+        Column::Revenue => tx.revenue.map(|r| r.to_string()).unwrap_or_default(),
+        Column::Fee => tx.fee().map(|f| f.to_string()).unwrap_or_default(),
+        Column::DateInitiated => tx.date_initiated.clone(),
+        Column::DateConfirmed => tx.date_confirmed.clone(),
+    }
+}
+
+fn round(value: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+/// Prefixes a text field that a spreadsheet would read as a formula with `'`.
+fn escape_formula(field: String) -> String {
+    if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{field}")
+    } else {
+        field
+    }
+}
+
+/// Joins `fields` into a CSV row, quoting fields that contain the delimiter, quotes or newlines.
+pub(crate) fn csv_row(fields: &[String], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    fn transaction() -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": "T1",
+            "status": "SUCCESSFUL",
+            "medium": "mobile money",
+            "serviceName": "Shop",
+            "amount": 1500.0,
+            "revenue": 1470.75,
+            "payerName": "Doe, \"JJ\" Jane",
+            "email": "jane@example.com",
+            "redirectUrl": "",
+            "externalId": "order-1",
+            "dateInitiated": "2025-01-31T09:15:00.000Z",
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    fn export(options: ExportOptions) -> String {
+        let mut exporter = TransactionExporter::new(Vec::new(), options);
+        exporter.write(&transaction()).unwrap();
+        assert_eq!(exporter.written(), 1);
+        String::from_utf8(exporter.finish().unwrap()).unwrap()
+    }
+
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::TransactionId,
+            Column::PayerName,
+            Column::Amount,
+            Column::Fee,
+            Column::DateInitiated,
+        ]
+    }
+
+    #[test]
+    fn csv_has_a_header_and_quotes_special_fields() {
+        let output = export(ExportOptions {
+            columns: columns(),
+            ..Default::default()
+        });
+        assert_eq!(
+            output,
+            "transId,payerName,amount,fee,dateInitiated\n\
+             T1,\"Doe, \"\"JJ\"\" Jane\",1500,29.25,2025-01-31T09:15:00.000Z\n"
+        );
+    }
+
+    #[test]
+    fn excel_csv_has_a_bom_crlf_and_local_number_format() {
+        let output = export(ExportOptions {
+            format: ExportFormat::ExcelCsv,
+            columns: columns(),
+            date_format: Some("%d/%m/%Y %H:%M".to_string()),
+            amount_decimals: Some(2),
+            decimal_separator: ',',
+            delimiter: ';',
+        });
+        assert_eq!(
+            output,
+            "\u{feff}transId;payerName;amount;fee;dateInitiated\r\n\
+             T1;\"Doe, \"\"JJ\"\" Jane\";1500,00;29,25;31/01/2025 09:15\r\n"
+        );
+    }
+
+    #[test]
+    fn excel_csv_prefixes_formulas_in_text_fields() {
+        let mut tx = transaction();
+        tx.payer_name = Some("=HYPERLINK(\"http://x\",\"y\")".to_string());
+        tx.external_id = Some("@SUM(A1)".to_string());
+        tx.user_id = Some("\t+1".to_string());
+        tx.email = "-2+3@example.com".to_string();
+        tx.service_name = "Shop".to_string();
+        let columns = vec![
+            Column::PayerName,
+            Column::ExternalId,
+            Column::UserId,
+            Column::Email,
+            Column::ServiceName,
+            Column::Fee,
+        ];
+        let mut refund = tx.clone();
+        refund.revenue = Some(1600.0);
+        let write = |format| {
+            let mut exporter = TransactionExporter::new(
+                Vec::new(),
+                ExportOptions {
+                    format,
+                    columns: columns.clone(),
+                    ..Default::default()
+                },
+            );
+            exporter.write(&refund).unwrap();
+            String::from_utf8(exporter.finish().unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            write(ExportFormat::ExcelCsv),
+            "\u{feff}payerName,externalId,userId,email,serviceName,fee\r\n\
+             \"'=HYPERLINK(\"\"http://x\"\",\"\"y\"\")\",'@SUM(A1),'\t+1,'-2+3@example.com,Shop,-100\r\n"
+        );
+        // Plain CSV keeps the values as they are, for programs that read them back.
+        assert_eq!(
+            write(ExportFormat::Csv),
+            "payerName,externalId,userId,email,serviceName,fee\n\
+             \"=HYPERLINK(\"\"http://x\"\",\"\"y\"\")\",@SUM(A1),\t+1,-2+3@example.com,Shop,-100\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_line_breaks_and_the_delimiter() {
+        let mut tx = transaction();
+        tx.payer_name = Some("Jane\r\nDoe".to_string());
+        tx.external_id = Some("order;1".to_string());
+        let mut exporter = TransactionExporter::new(
+            Vec::new(),
+            ExportOptions {
+                columns: vec![Column::PayerName, Column::ExternalId],
+                delimiter: ';',
+                ..Default::default()
+            },
+        );
+        exporter.write(&tx).unwrap();
+        assert_eq!(
+            String::from_utf8(exporter.finish().unwrap()).unwrap(),
+            "payerName;externalId\n\"Jane\r\nDoe\";\"order;1\"\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_keep_numbers_and_escape_strings() {
+        let output = export(ExportOptions {
+            format: ExportFormat::JsonLines,
+            columns: columns(),
+            amount_decimals: Some(0),
+            ..Default::default()
+        });
+        assert!(output.ends_with('\n') && output.lines().count() == 1);
+        let record: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            record,
+            json!({
+                "transId": "T1",
+                "payerName": "Doe, \"JJ\" Jane",
+                "amount": 1500.0,
+                "fee": 29.0,
+                "dateInitiated": "2025-01-31T09:15:00.000Z",
+            })
+        );
+    }
+
+    #[test]
+    fn empty_export_still_writes_the_header() {
+        let exporter = TransactionExporter::new(
+            Vec::new(),
+            ExportOptions {
+                columns: vec![Column::TransactionId, Column::Status],
+                ..Default::default()
+            },
+        );
+        assert_eq!(exporter.finish().unwrap(), b"transId,status\n");
+
+        let exporter = TransactionExporter::new(
+            Vec::new(),
+            ExportOptions {
+                format: ExportFormat::JsonLines,
+                ..Default::default()
+            },
+        );
+        assert!(exporter.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_date_format_fails_the_export() {
+        let mut exporter = TransactionExporter::new(
+            Vec::new(),
+            ExportOptions {
+                columns: vec![Column::DateInitiated],
+                date_format: Some("%Q".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            exporter.write(&transaction()),
+            Err(FapshiError::InvalidRequest(_))
+        ));
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn export_search(
+        exporter: &mut TransactionExporter<Vec<u8>>,
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<usize, FapshiError> {
+        exporter.export_search(client, query)
+    }
+
+    #[cfg(feature = "async")]
+    fn export_search(
+        exporter: &mut TransactionExporter<Vec<u8>>,
+        client: &FapshiClient,
+        query: &TransactionSearchQuery,
+    ) -> Result<usize, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(exporter.export_search(client, query))
+    }
+
+    fn search(query: &str, count: usize) -> Interaction {
+        let transactions: Vec<_> = (0..count).map(|_| transaction()).collect();
+        Interaction {
+            request: RecordedRequest {
+                method: "GET".to_string(),
+                path: format!("search?{query}"),
+                headers: BTreeMap::new(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: serde_json::to_string(&transactions).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn full_search_pages_are_split_by_date() {
+        let client = FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(vec![
+                search("start=2025-01-01&end=2025-01-02&limit=100", 100),
+                search("start=2025-01-01&end=2025-01-01&limit=100", 60),
+                search("start=2025-01-02&end=2025-01-02&limit=100", 70),
+            ]));
+        let query = TransactionSearchQuery {
+            start: Some("2025-01-01".to_string()),
+            end: Some("2025-01-02".to_string()),
+            ..Default::default()
+        };
+        let mut exporter = TransactionExporter::new(
+            Vec::new(),
+            ExportOptions {
+                format: ExportFormat::JsonLines,
+                ..Default::default()
+            },
+        );
+        assert_eq!(export_search(&mut exporter, &client, &query).unwrap(), 130);
+        assert_eq!(exporter.written(), 130);
+        assert!(client.cassette().unwrap().unplayed().is_empty());
+
+        let unbounded = FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(vec![search("limit=100", 100)]));
+        let err = export_search(
+            &mut exporter,
+            &unbounded,
+            &TransactionSearchQuery::default(),
+        );
+        assert!(matches!(err, Err(FapshiError::InvalidRequest(_))));
+    }
+}
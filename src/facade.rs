@@ -0,0 +1,802 @@
+use std::collections::VecDeque;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::{
+    api::{
+        balance::BalanceApi, payment::PaymentApi, payout::PayoutApi, transaction::TransactionApi,
+    },
+    client::FapshiClient,
+    error::FapshiError,
+    models::{
+        DirectPaymentRequest, DirectPaymentResponse, PaymentRequest, PaymentResponse,
+        PayoutResponse, Payouts, ServiceBalance, TransactionSearchQuery, TransactionStatus,
+    },
+};
+
+/// All Fapshi operations, as methods, so business logic can be written against this trait
+/// and tested with [`FakeFapshi`] instead of a real [`FapshiClient`].
+///
+/// Each method behaves like the matching associated function of `PaymentApi`,
+/// `TransactionApi`, `BalanceApi` or `PayoutApi`.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     error::FapshiError,
+///     facade::{Fapshi, FakeFapshi},
+///     models::ServiceBalance,
+///     safety::Environment,
+/// };
+///
+/// fn can_pay_out(fapshi: &impl Fapshi, amount: f64) -> Result<bool, FapshiError> {
+///     Ok(fapshi.get_service_balance()?.balance >= amount)
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// println!("{}", can_pay_out(&client, 5000.0)?);
+///
+/// let fake = FakeFapshi::new().on_get_service_balance(|| {
+///     Ok(ServiceBalance {
+///         balance: 1000.0,
+///         currency: "XAF".to_string(),
+///     })
+/// });
+/// assert!(!can_pay_out(&fake, 5000.0)?);
+/// assert_eq!(fake.calls().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(feature = "async"))]
+pub trait Fapshi {
+    /// Creates a payment link. See [`PaymentApi::create_payment`].
+    fn create_payment(&self, request: &PaymentRequest) -> Result<PaymentResponse, FapshiError>;
+
+    /// Initiates a direct payment. See [`PaymentApi::initiate_direct_payment`].
+    fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError>;
+
+    /// Retrieves the status of a transaction. See [`TransactionApi::get_status`].
+    fn get_status(&self, transaction_id: &str) -> Result<TransactionStatus, FapshiError>;
+
+    /// Expires a payment link. See [`TransactionApi::expire_transaction`].
+    fn expire_transaction(&self, transaction_id: &str) -> Result<(), FapshiError>;
+
+    /// Retrieves the transactions of a user. See [`TransactionApi::get_transactions_by_user_id`].
+    fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError>;
+
+    /// Searches transactions. See [`TransactionApi::search_transactions`].
+    fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError>;
+
+    /// Retrieves the service balance. See [`BalanceApi::get_service_balance`].
+    fn get_service_balance(&self) -> Result<ServiceBalance, FapshiError>;
+
+    /// Sends a payout. See [`PayoutApi::send_payout`].
+    fn send_payout(&self, payout: &Payouts) -> Result<PayoutResponse, FapshiError>;
+}
+
+/// All Fapshi operations, as methods, so business logic can be written against this trait
+/// and tested with [`FakeFapshi`] instead of a real [`FapshiClient`].
+///
+/// Each method behaves like the matching associated function of `PaymentApi`,
+/// `TransactionApi`, `BalanceApi` or `PayoutApi`. The returned futures are
+/// `Send`, so implementations can be used from spawned tasks.
+#[cfg(feature = "async")]
+pub trait Fapshi {
+    /// Creates a payment link. See [`PaymentApi::create_payment`].
+    fn create_payment(
+        &self,
+        request: &PaymentRequest,
+    ) -> impl Future<Output = Result<PaymentResponse, FapshiError>> + Send;
+
+    /// Initiates a direct payment. See [`PaymentApi::initiate_direct_payment`].
+    fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> impl Future<Output = Result<DirectPaymentResponse, FapshiError>> + Send;
+
+    /// Retrieves the status of a transaction. See [`TransactionApi::get_status`].
+    fn get_status(
+        &self,
+        transaction_id: &str,
+    ) -> impl Future<Output = Result<TransactionStatus, FapshiError>> + Send;
+
+    /// Expires a payment link. See [`TransactionApi::expire_transaction`].
+    fn expire_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> impl Future<Output = Result<(), FapshiError>> + Send;
+
+    /// Retrieves the transactions of a user. See [`TransactionApi::get_transactions_by_user_id`].
+    fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> impl Future<Output = Result<Vec<TransactionStatus>, FapshiError>> + Send;
+
+    /// Searches transactions. See [`TransactionApi::search_transactions`].
+    fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> impl Future<Output = Result<Vec<TransactionStatus>, FapshiError>> + Send;
+
+    /// Retrieves the service balance. See [`BalanceApi::get_service_balance`].
+    fn get_service_balance(
+        &self,
+    ) -> impl Future<Output = Result<ServiceBalance, FapshiError>> + Send;
+
+    /// Sends a payout. See [`PayoutApi::send_payout`].
+    fn send_payout(
+        &self,
+        payout: &Payouts,
+    ) -> impl Future<Output = Result<PayoutResponse, FapshiError>> + Send;
+}
+
+#[cfg(not(feature = "async"))]
+impl Fapshi for FapshiClient {
+    fn create_payment(&self, request: &PaymentRequest) -> Result<PaymentResponse, FapshiError> {
+        PaymentApi::create_payment(self, request)
+    }
+
+    fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        PaymentApi::initiate_direct_payment(self, request)
+    }
+
+    fn get_status(&self, transaction_id: &str) -> Result<TransactionStatus, FapshiError> {
+        TransactionApi::get_status(self, transaction_id)
+    }
+
+    fn expire_transaction(&self, transaction_id: &str) -> Result<(), FapshiError> {
+        TransactionApi::expire_transaction(self, transaction_id)
+    }
+
+    fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        TransactionApi::get_transactions_by_user_id(self, user_id)
+    }
+
+    fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        TransactionApi::search_transactions(self, query)
+    }
+
+    fn get_service_balance(&self) -> Result<ServiceBalance, FapshiError> {
+        BalanceApi::get_service_balance(self)
+    }
+
+    fn send_payout(&self, payout: &Payouts) -> Result<PayoutResponse, FapshiError> {
+        PayoutApi::send_payout(self, payout)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Fapshi for FapshiClient {
+    async fn create_payment(
+        &self,
+        request: &PaymentRequest,
+    ) -> Result<PaymentResponse, FapshiError> {
+        PaymentApi::create_payment(self, request).await
+    }
+
+    async fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        PaymentApi::initiate_direct_payment(self, request).await
+    }
+
+    async fn get_status(&self, transaction_id: &str) -> Result<TransactionStatus, FapshiError> {
+        TransactionApi::get_status(self, transaction_id).await
+    }
+
+    async fn expire_transaction(&self, transaction_id: &str) -> Result<(), FapshiError> {
+        TransactionApi::expire_transaction(self, transaction_id).await
+    }
+
+    async fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        TransactionApi::get_transactions_by_user_id(self, user_id).await
+    }
+
+    async fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        TransactionApi::search_transactions(self, query).await
+    }
+
+    async fn get_service_balance(&self) -> Result<ServiceBalance, FapshiError> {
+        BalanceApi::get_service_balance(self).await
+    }
+
+    async fn send_payout(&self, payout: &Payouts) -> Result<PayoutResponse, FapshiError> {
+        PayoutApi::send_payout(self, payout).await
+    }
+}
+
+/// A call made on a [`FakeFapshi`], with its arguments.
+#[derive(Debug, Clone)]
+pub enum Call {
+    /// [`Fapshi::create_payment`].
+    CreatePayment(PaymentRequest),
+    /// [`Fapshi::initiate_direct_payment`].
+    InitiateDirectPayment(DirectPaymentRequest),
+    /// [`Fapshi::get_status`], with the transaction ID.
+    GetStatus(String),
+    /// [`Fapshi::expire_transaction`], with the transaction ID.
+    ExpireTransaction(String),
+    /// [`Fapshi::get_transactions_by_user_id`], with the user ID.
+    GetTransactionsByUserId(String),
+    /// [`Fapshi::search_transactions`].
+    SearchTransactions(TransactionSearchQuery),
+    /// [`Fapshi::get_service_balance`].
+    GetServiceBalance,
+    /// [`Fapshi::send_payout`].
+    SendPayout(Payouts),
+}
+
+type Handler<A, T> = Box<dyn Fn(&A) -> Result<T, FapshiError> + Send + Sync>;
+
+/// The programmed answers of one operation: queued one-shot results first, then the handler.
+struct Responder<A: ?Sized, T> {
+    queue: VecDeque<Result<T, FapshiError>>,
+    handler: Option<Handler<A, T>>,
+}
+
+impl<A: ?Sized, T> Default for Responder<A, T> {
+    fn default() -> Self {
+        Responder {
+            queue: VecDeque::new(),
+            handler: None,
+        }
+    }
+}
+
+impl<A: ?Sized, T> Responder<A, T> {
+    fn respond(&mut self, operation: &str, arg: &A) -> Result<T, FapshiError> {
+        if let Some(result) = self.queue.pop_front() {
+            return result;
+        }
+        match &self.handler {
+            Some(handler) => handler(arg),
+            None => Err(FapshiError::InvalidRequest(format!(
+                "FakeFapshi has no response programmed for {operation}"
+            ))),
+        }
+    }
+}
+
+/// A programmable in-memory [`Fapshi`] implementation that records every call.
+///
+/// Program an operation with an `on_*` handler, which answers every call, and/or queue
+/// one-shot results with `push_*`, which are returned first, in order. Calls to an operation
+/// that is not programmed fail with `FapshiError::InvalidRequest`.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     facade::{Call, Fapshi, FakeFapshi},
+///     models::{Status, TransactionStatus},
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let fake = FakeFapshi::new();
+/// let transaction = |status| TransactionStatus {
+///     transaction_id: "trans123".to_string(),
+///     status,
+///     medium: None,
+///     service_name: "Shop".to_string(),
+///     amount: 500.0,
+///     revenue: None,
+///     payer_name: None,
+///     email: String::new(),
+///     redirect_url: String::new(),
+///     external_id: None,
+///     user_id: None,
+///     webhook: None,
+///     financial_transaction_id: None,
+///     date_initiated: String::new(),
+///     date_confirmed: String::new(),
+/// };
+/// fake.push_get_status(Ok(transaction(Status::PENDING)));
+/// fake.push_get_status(Ok(transaction(Status::SUCCESSFUL)));
+///
+/// assert_eq!(fake.get_status("trans123")?.status, Status::PENDING);
+/// assert_eq!(fake.get_status("trans123")?.status, Status::SUCCESSFUL);
+/// assert!(matches!(&fake.calls()[0], Call::GetStatus(id) if id == "trans123"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct FakeFapshi {
+    calls: Mutex<Vec<Call>>,
+    create_payment: Mutex<Responder<PaymentRequest, PaymentResponse>>,
+    initiate_direct_payment: Mutex<Responder<DirectPaymentRequest, DirectPaymentResponse>>,
+    get_status: Mutex<Responder<str, TransactionStatus>>,
+    expire_transaction: Mutex<Responder<str, ()>>,
+    get_transactions_by_user_id: Mutex<Responder<str, Vec<TransactionStatus>>>,
+    search_transactions: Mutex<Responder<TransactionSearchQuery, Vec<TransactionStatus>>>,
+    get_service_balance: Mutex<Responder<(), ServiceBalance>>,
+    send_payout: Mutex<Responder<Payouts, PayoutResponse>>,
+}
+
+impl std::fmt::Debug for FakeFapshi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FakeFapshi")
+            .field("calls", &self.calls())
+            .finish_non_exhaustive()
+    }
+}
+
+impl FakeFapshi {
+    /// Creates a fake with no operation programmed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the calls made so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Forgets the calls made so far.
+    pub fn clear_calls(&self) {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Answers every `create_payment` call with `handler`.
+    pub fn on_create_payment(
+        self,
+        handler: impl Fn(&PaymentRequest) -> Result<PaymentResponse, FapshiError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        set_handler(&self.create_payment, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `create_payment` call.
+    pub fn push_create_payment(&self, result: Result<PaymentResponse, FapshiError>) {
+        push(&self.create_payment, result);
+    }
+
+    /// Answers every `initiate_direct_payment` call with `handler`.
+    pub fn on_initiate_direct_payment(
+        self,
+        handler: impl Fn(&DirectPaymentRequest) -> Result<DirectPaymentResponse, FapshiError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        set_handler(&self.initiate_direct_payment, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `initiate_direct_payment` call.
+    pub fn push_initiate_direct_payment(&self, result: Result<DirectPaymentResponse, FapshiError>) {
+        push(&self.initiate_direct_payment, result);
+    }
+
+    /// Answers every `get_status` call with `handler`, given the transaction ID.
+    pub fn on_get_status(
+        self,
+        handler: impl Fn(&str) -> Result<TransactionStatus, FapshiError> + Send + Sync + 'static,
+    ) -> Self {
+        set_handler(&self.get_status, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `get_status` call.
+    pub fn push_get_status(&self, result: Result<TransactionStatus, FapshiError>) {
+        push(&self.get_status, result);
+    }
+
+    /// Answers every `expire_transaction` call with `handler`, given the transaction ID.
+    pub fn on_expire_transaction(
+        self,
+        handler: impl Fn(&str) -> Result<(), FapshiError> + Send + Sync + 'static,
+    ) -> Self {
+        set_handler(&self.expire_transaction, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `expire_transaction` call.
+    pub fn push_expire_transaction(&self, result: Result<(), FapshiError>) {
+        push(&self.expire_transaction, result);
+    }
+
+    /// Answers every `get_transactions_by_user_id` call with `handler`, given the user ID.
+    pub fn on_get_transactions_by_user_id(
+        self,
+        handler: impl Fn(&str) -> Result<Vec<TransactionStatus>, FapshiError> + Send + Sync + 'static,
+    ) -> Self {
+        set_handler(&self.get_transactions_by_user_id, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `get_transactions_by_user_id` call.
+    pub fn push_get_transactions_by_user_id(
+        &self,
+        result: Result<Vec<TransactionStatus>, FapshiError>,
+    ) {
+        push(&self.get_transactions_by_user_id, result);
+    }
+
+    /// Answers every `search_transactions` call with `handler`.
+    pub fn on_search_transactions(
+        self,
+        handler: impl Fn(&TransactionSearchQuery) -> Result<Vec<TransactionStatus>, FapshiError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        set_handler(&self.search_transactions, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `search_transactions` call.
+    pub fn push_search_transactions(&self, result: Result<Vec<TransactionStatus>, FapshiError>) {
+        push(&self.search_transactions, result);
+    }
+
+    /// Answers every `get_service_balance` call with `handler`.
+    pub fn on_get_service_balance(
+        self,
+        handler: impl Fn() -> Result<ServiceBalance, FapshiError> + Send + Sync + 'static,
+    ) -> Self {
+        set_handler(&self.get_service_balance, Box::new(move |_: &()| handler()));
+        self
+    }
+
+    /// Queues the result of the next `get_service_balance` call.
+    pub fn push_get_service_balance(&self, result: Result<ServiceBalance, FapshiError>) {
+        push(&self.get_service_balance, result);
+    }
+
+    /// Answers every `send_payout` call with `handler`.
+    pub fn on_send_payout(
+        self,
+        handler: impl Fn(&Payouts) -> Result<PayoutResponse, FapshiError> + Send + Sync + 'static,
+    ) -> Self {
+        set_handler(&self.send_payout, Box::new(handler));
+        self
+    }
+
+    /// Queues the result of the next `send_payout` call.
+    pub fn push_send_payout(&self, result: Result<PayoutResponse, FapshiError>) {
+        push(&self.send_payout, result);
+    }
+
+    /// Records `call` and answers it from `responder`.
+    fn answer<A: ?Sized, T>(
+        &self,
+        call: Call,
+        operation: &str,
+        responder: &Mutex<Responder<A, T>>,
+        arg: &A,
+    ) -> Result<T, FapshiError> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(call);
+        responder
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .respond(operation, arg)
+    }
+}
+
+fn set_handler<A: ?Sized, T>(responder: &Mutex<Responder<A, T>>, handler: Handler<A, T>) {
+    responder.lock().unwrap_or_else(|e| e.into_inner()).handler = Some(handler);
+}
+
+fn push<A: ?Sized, T>(responder: &Mutex<Responder<A, T>>, result: Result<T, FapshiError>) {
+    responder
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .queue
+        .push_back(result);
+}
+
+#[cfg(not(feature = "async"))]
+impl Fapshi for FakeFapshi {
+    fn create_payment(&self, request: &PaymentRequest) -> Result<PaymentResponse, FapshiError> {
+        self.answer(
+            Call::CreatePayment(request.clone()),
+            "create_payment",
+            &self.create_payment,
+            request,
+        )
+    }
+
+    fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        self.answer(
+            Call::InitiateDirectPayment(request.clone()),
+            "initiate_direct_payment",
+            &self.initiate_direct_payment,
+            request,
+        )
+    }
+
+    fn get_status(&self, transaction_id: &str) -> Result<TransactionStatus, FapshiError> {
+        self.answer(
+            Call::GetStatus(transaction_id.to_string()),
+            "get_status",
+            &self.get_status,
+            transaction_id,
+        )
+    }
+
+    fn expire_transaction(&self, transaction_id: &str) -> Result<(), FapshiError> {
+        self.answer(
+            Call::ExpireTransaction(transaction_id.to_string()),
+            "expire_transaction",
+            &self.expire_transaction,
+            transaction_id,
+        )
+    }
+
+    fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.answer(
+            Call::GetTransactionsByUserId(user_id.to_string()),
+            "get_transactions_by_user_id",
+            &self.get_transactions_by_user_id,
+            user_id,
+        )
+    }
+
+    fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.answer(
+            Call::SearchTransactions(query.clone()),
+            "search_transactions",
+            &self.search_transactions,
+            query,
+        )
+    }
+
+    fn get_service_balance(&self) -> Result<ServiceBalance, FapshiError> {
+        self.answer(
+            Call::GetServiceBalance,
+            "get_service_balance",
+            &self.get_service_balance,
+            &(),
+        )
+    }
+
+    fn send_payout(&self, payout: &Payouts) -> Result<PayoutResponse, FapshiError> {
+        self.answer(
+            Call::SendPayout(payout.clone()),
+            "send_payout",
+            &self.send_payout,
+            payout,
+        )
+    }
+}
+
+#[cfg(feature = "async")]
+impl Fapshi for FakeFapshi {
+    async fn create_payment(
+        &self,
+        request: &PaymentRequest,
+    ) -> Result<PaymentResponse, FapshiError> {
+        self.answer(
+            Call::CreatePayment(request.clone()),
+            "create_payment",
+            &self.create_payment,
+            request,
+        )
+    }
+
+    async fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        self.answer(
+            Call::InitiateDirectPayment(request.clone()),
+            "initiate_direct_payment",
+            &self.initiate_direct_payment,
+            request,
+        )
+    }
+
+    async fn get_status(&self, transaction_id: &str) -> Result<TransactionStatus, FapshiError> {
+        self.answer(
+            Call::GetStatus(transaction_id.to_string()),
+            "get_status",
+            &self.get_status,
+            transaction_id,
+        )
+    }
+
+    async fn expire_transaction(&self, transaction_id: &str) -> Result<(), FapshiError> {
+        self.answer(
+            Call::ExpireTransaction(transaction_id.to_string()),
+            "expire_transaction",
+            &self.expire_transaction,
+            transaction_id,
+        )
+    }
+
+    async fn get_transactions_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.answer(
+            Call::GetTransactionsByUserId(user_id.to_string()),
+            "get_transactions_by_user_id",
+            &self.get_transactions_by_user_id,
+            user_id,
+        )
+    }
+
+    async fn search_transactions(
+        &self,
+        query: &TransactionSearchQuery,
+    ) -> Result<Vec<TransactionStatus>, FapshiError> {
+        self.answer(
+            Call::SearchTransactions(query.clone()),
+            "search_transactions",
+            &self.search_transactions,
+            query,
+        )
+    }
+
+    async fn get_service_balance(&self) -> Result<ServiceBalance, FapshiError> {
+        self.answer(
+            Call::GetServiceBalance,
+            "get_service_balance",
+            &self.get_service_balance,
+            &(),
+        )
+    }
+
+    async fn send_payout(&self, payout: &Payouts) -> Result<PayoutResponse, FapshiError> {
+        self.answer(
+            Call::SendPayout(payout.clone()),
+            "send_payout",
+            &self.send_payout,
+            payout,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        models::Status,
+        safety::Environment,
+    };
+
+    fn transaction(status: Status) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": "T1",
+            "status": status,
+            "serviceName": "Shop",
+            "amount": 500.0,
+            "email": "",
+            "redirectUrl": "",
+            "dateInitiated": "",
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn status(
+        fapshi: &impl Fapshi,
+        transaction_id: &str,
+    ) -> Result<TransactionStatus, FapshiError> {
+        fapshi.get_status(transaction_id)
+    }
+
+    #[cfg(feature = "async")]
+    fn status(
+        fapshi: &impl Fapshi,
+        transaction_id: &str,
+    ) -> Result<TransactionStatus, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(fapshi.get_status(transaction_id))
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn balance(fapshi: &impl Fapshi) -> Result<ServiceBalance, FapshiError> {
+        fapshi.get_service_balance()
+    }
+
+    #[cfg(feature = "async")]
+    fn balance(fapshi: &impl Fapshi) -> Result<ServiceBalance, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(fapshi.get_service_balance())
+    }
+
+    #[test]
+    fn queued_results_are_returned_before_the_handler() {
+        let fake = FakeFapshi::new().on_get_status(|id| {
+            assert_eq!(id, "T1");
+            Ok(transaction(Status::SUCCESSFUL))
+        });
+        fake.push_get_status(Ok(transaction(Status::PENDING)));
+        fake.push_get_status(Err(FapshiError::ApiError("timeout".to_string())));
+
+        assert_eq!(status(&fake, "T1").unwrap().status, Status::PENDING);
+        assert!(status(&fake, "T1").is_err());
+        assert_eq!(status(&fake, "T1").unwrap().status, Status::SUCCESSFUL);
+        assert_eq!(status(&fake, "T1").unwrap().status, Status::SUCCESSFUL);
+
+        let calls = fake.calls();
+        assert_eq!(calls.len(), 4);
+        assert!(calls
+            .iter()
+            .all(|call| matches!(call, Call::GetStatus(id) if id == "T1")));
+        fake.clear_calls();
+        assert!(fake.calls().is_empty());
+    }
+
+    #[test]
+    fn unprogrammed_operations_fail_but_are_recorded() {
+        let fake = FakeFapshi::new();
+        let err = balance(&fake).unwrap_err();
+        assert!(
+            matches!(err, FapshiError::InvalidRequest(message) if message.contains("get_service_balance"))
+        );
+        assert!(matches!(fake.calls().as_slice(), [Call::GetServiceBalance]));
+    }
+
+    #[test]
+    fn client_implements_the_trait() {
+        let client = FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(vec![Interaction {
+                request: RecordedRequest {
+                    method: "GET".to_string(),
+                    path: "balance".to_string(),
+                    headers: BTreeMap::new(),
+                    body: None,
+                },
+                response: RecordedResponse {
+                    status: 200,
+                    headers: BTreeMap::new(),
+                    body: r#"{"service":"Shop","balance":1500,"currency":"XAF"}"#.to_string(),
+                },
+            }]));
+        assert_eq!(balance(&client).unwrap().balance, 1500.0);
+    }
+}
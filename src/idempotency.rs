@@ -0,0 +1,977 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{payment::PaymentApi, transaction::TransactionApi},
+    client::FapshiClient,
+    error::FapshiError,
+    models::{
+        DirectPaymentRequest, DirectPaymentResponse, PaymentRequest, PaymentResponse, Status,
+        TransactionSearchQuery, TransactionStatus,
+    },
+    store::JsonFile,
+};
+
+/// A payment recorded against an external ID.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StoredPayment {
+    /// A payment link created with `PaymentApi::create_payment`.
+    PaymentLink(PaymentResponse),
+    /// A direct payment initiated with `PaymentApi::initiate_direct_payment`.
+    DirectPayment(DirectPaymentResponse),
+    /// A payment being sent, written before the request so that concurrent or repeated
+    /// requests do not create a second payment. It stays in place if the outcome is unknown.
+    Pending {
+        /// When the request was started.
+        since: DateTime<Utc>,
+    },
+}
+
+impl StoredPayment {
+    /// Returns the Fapshi transaction ID of a recorded payment.
+    pub fn transaction_id(&self) -> Option<&str> {
+        match self {
+            StoredPayment::PaymentLink(response) => Some(&response.transaction_id),
+            StoredPayment::DirectPayment(response) => Some(&response.transaction_id),
+            StoredPayment::Pending { .. } => None,
+        }
+    }
+}
+
+/// Storage for payments already created, keyed by external ID.
+///
+/// Implement this trait to keep idempotency records in your own database. `reserve` must be
+/// atomic (e.g. an insert guarded by a unique key or a conditional update), or two requests
+/// may both send the same payment.
+pub trait IdempotencyStore: Send + Sync {
+    /// Returns the payment recorded for `external_id`, if any.
+    fn get(&self, external_id: &str) -> Result<Option<StoredPayment>, FapshiError>;
+
+    /// Records the payment created for `external_id`.
+    fn put(&self, external_id: &str, payment: &StoredPayment) -> Result<(), FapshiError>;
+
+    /// Atomically records a [`StoredPayment::Pending`] marker started at `since`.
+    ///
+    /// The marker is written if nothing is recorded for `external_id` or, when `replacing` is
+    /// set, if the record is the pending marker started at `replacing`.
+    ///
+    /// # Returns
+    /// `false`, without writing anything, if another record is in the way.
+    fn reserve(
+        &self,
+        external_id: &str,
+        since: DateTime<Utc>,
+        replacing: Option<DateTime<Utc>>,
+    ) -> Result<bool, FapshiError>;
+
+    /// Removes the record for `external_id`, e.g. the marker of a payment Fapshi refused.
+    fn remove(&self, external_id: &str) -> Result<(), FapshiError>;
+}
+
+/// Returns whether a pending marker may be written over `current`.
+fn can_reserve(current: Option<&StoredPayment>, replacing: Option<DateTime<Utc>>) -> bool {
+    match (current, replacing) {
+        (None, _) => true,
+        (Some(StoredPayment::Pending { since }), Some(replacing)) => *since == replacing,
+        _ => false,
+    }
+}
+
+/// An `IdempotencyStore` kept in memory for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct InMemoryIdempotencyStore {
+    entries: Mutex<HashMap<String, StoredPayment>>,
+}
+
+impl InMemoryIdempotencyStore {
+    /// Creates an empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    fn get(&self, external_id: &str) -> Result<Option<StoredPayment>, FapshiError> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.get(external_id).cloned())
+    }
+
+    fn put(&self, external_id: &str, payment: &StoredPayment) -> Result<(), FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(external_id.to_string(), payment.clone());
+        Ok(())
+    }
+
+    fn reserve(
+        &self,
+        external_id: &str,
+        since: DateTime<Utc>,
+        replacing: Option<DateTime<Utc>>,
+    ) -> Result<bool, FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if !can_reserve(entries.get(external_id), replacing) {
+            return Ok(false);
+        }
+        entries.insert(external_id.to_string(), StoredPayment::Pending { since });
+        Ok(true)
+    }
+
+    fn remove(&self, external_id: &str) -> Result<(), FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(external_id);
+        Ok(())
+    }
+}
+
+/// An `IdempotencyStore` persisted as a JSON file, surviving process restarts.
+///
+/// The records are kept in memory once read, and the file is rewritten atomically (write
+/// to a temporary file, then rename) when they change. Reservations are atomic within the
+/// process; do not share the file between processes.
+#[derive(Debug)]
+pub struct FileIdempotencyStore {
+    file: JsonFile<HashMap<String, StoredPayment>>,
+}
+
+impl FileIdempotencyStore {
+    /// Creates a store backed by the file at `path`. The file is created on first write.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileIdempotencyStore {
+            file: JsonFile::new(path),
+        }
+    }
+}
+
+impl IdempotencyStore for FileIdempotencyStore {
+    fn get(&self, external_id: &str) -> Result<Option<StoredPayment>, FapshiError> {
+        self.file.read(|entries| entries.get(external_id).cloned())
+    }
+
+    fn put(&self, external_id: &str, payment: &StoredPayment) -> Result<(), FapshiError> {
+        self.file.update(|entries| {
+            entries.insert(external_id.to_string(), payment.clone());
+            true
+        })?;
+        Ok(())
+    }
+
+    fn reserve(
+        &self,
+        external_id: &str,
+        since: DateTime<Utc>,
+        replacing: Option<DateTime<Utc>>,
+    ) -> Result<bool, FapshiError> {
+        self.file.update(|entries| {
+            if !can_reserve(entries.get(external_id), replacing) {
+                return false;
+            }
+            entries.insert(external_id.to_string(), StoredPayment::Pending { since });
+            true
+        })
+    }
+
+    fn remove(&self, external_id: &str) -> Result<(), FapshiError> {
+        self.file
+            .update(|entries| entries.remove(external_id).is_some())?;
+        Ok(())
+    }
+}
+
+/// Creates payments at most once per external ID.
+///
+/// A payment recorded under the request's `external_id` is returned without calling Fapshi.
+/// Otherwise Fapshi is searched for a transaction with the same external ID, in the user's
+/// transactions if the request has a `user_id`, or else among the transactions of the same
+/// amount since a pending marker was written or within the lookback period. A transaction
+/// found this way is recorded and returned, so a payment is not sent twice even if its
+/// record was lost.
+///
+/// Before a payment is sent, a pending marker is reserved in the store, so a concurrent
+/// request for the same external ID fails with `FapshiError::PaymentInProgress` instead of
+/// creating a second payment. The response replaces the marker; a request Fapshi certainly
+/// did not process removes it. A marker left by a request whose outcome is unknown (a
+/// timeout, a 5xx response or a crash) blocks the external ID until the in-flight timeout
+/// has passed; if Fapshi still has no matching transaction, the payment is then sent again.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     idempotency::{FileIdempotencyStore, IdempotentPayments},
+///     models::PaymentRequest,
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let payments = IdempotentPayments::new(FileIdempotencyStore::new("payments.json"));
+/// let request = PaymentRequest {
+///     amount: 500.0,
+///     email: None,
+///     redirect_url: None,
+///     user_id: Some("customer42".to_string()),
+///     external_id: Some("order-1001".to_string()),
+///     message: "Order 1001".to_string(),
+///     card_only: None,
+/// };
+/// // Calling this twice returns the same payment link.
+/// let response = payments.create_payment(&client, &request)?;
+/// println!("Payment link: {}", response.payment_link);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct IdempotentPayments<S> {
+    store: S,
+    in_flight_timeout: Duration,
+    lookback: Duration,
+}
+
+impl<S: IdempotencyStore> IdempotentPayments<S> {
+    /// Creates a new idempotency layer on top of `store`, with a 2 minute in-flight timeout
+    /// and a 7 day lookback.
+    pub fn new(store: S) -> Self {
+        IdempotentPayments {
+            store,
+            in_flight_timeout: Duration::from_secs(120),
+            lookback: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+
+    /// Sets how long a pending marker blocks other requests for its external ID when no
+    /// matching transaction is found on Fapshi. Keep it above the client's request timeout.
+    pub fn with_in_flight_timeout(mut self, timeout: Duration) -> Self {
+        self.in_flight_timeout = timeout;
+        self
+    }
+
+    /// Sets how far back Fapshi is searched, among the transactions of the same amount, for
+    /// an external ID the store has no record of. Requests with a `user_id` search all of the
+    /// user's transactions instead.
+    pub fn with_lookback(mut self, lookback: Duration) -> Self {
+        self.lookback = lookback;
+        self
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Creates a payment link unless one already exists for the request's `external_id`.
+    ///
+    /// An unpaid (`CREATED`) transaction already on Fapshi for the external ID, e.g. left by
+    /// an interrupted request or recorded in a store that was since lost, is recorded and
+    /// returned instead of creating a second link. Its `payment_link` is empty, as Fapshi does
+    /// not return the link of an existing transaction.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the request has no `external_id`,
+    /// `FapshiError::PaymentInProgress` if another request for it is in flight, and
+    /// `FapshiError::DuplicatePayment` if the external ID belongs to a direct payment or to a
+    /// payment the payer already started paying.
+    #[cfg(not(feature = "async"))]
+    pub fn create_payment(
+        &self,
+        client: &FapshiClient,
+        request: &PaymentRequest,
+    ) -> Result<PaymentResponse, FapshiError> {
+        let external_id = required_external_id(request.external_id.as_deref())?;
+        let pending = match self.store.get(external_id)? {
+            None => None,
+            Some(StoredPayment::PaymentLink(response)) => return Ok(response),
+            Some(StoredPayment::Pending { since }) => Some(since),
+            Some(other) => return Err(duplicate_stored(external_id, &other)),
+        };
+        let sent = find_sent(
+            client,
+            external_id,
+            request.user_id.as_deref(),
+            request.amount,
+            self.search_since(pending),
+        )?;
+        match sent {
+            Some(tx) if tx.status == Status::CREATED => {
+                return self.settle(
+                    external_id,
+                    Ok(link_response_from(tx)),
+                    StoredPayment::PaymentLink,
+                );
+            }
+            Some(tx) => return Err(duplicate_payment(external_id, tx.transaction_id)),
+            None => self.check_interrupted(external_id, pending)?,
+        }
+        self.reserve(external_id, pending)?;
+        let result = PaymentApi::create_payment(client, request);
+        self.settle(external_id, result, StoredPayment::PaymentLink)
+    }
+
+    /// Creates a payment link unless one already exists for the request's `external_id`,
+    /// asynchronously.
+    ///
+    /// An unpaid (`CREATED`) transaction already on Fapshi for the external ID, e.g. left by
+    /// an interrupted request or recorded in a store that was since lost, is recorded and
+    /// returned instead of creating a second link. Its `payment_link` is empty, as Fapshi does
+    /// not return the link of an existing transaction.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the request has no `external_id`,
+    /// `FapshiError::PaymentInProgress` if another request for it is in flight, and
+    /// `FapshiError::DuplicatePayment` if the external ID belongs to a direct payment or to a
+    /// payment the payer already started paying.
+    #[cfg(feature = "async")]
+    pub async fn create_payment(
+        &self,
+        client: &FapshiClient,
+        request: &PaymentRequest,
+    ) -> Result<PaymentResponse, FapshiError> {
+        let external_id = required_external_id(request.external_id.as_deref())?;
+        let pending = match self.store.get(external_id)? {
+            None => None,
+            Some(StoredPayment::PaymentLink(response)) => return Ok(response),
+            Some(StoredPayment::Pending { since }) => Some(since),
+            Some(other) => return Err(duplicate_stored(external_id, &other)),
+        };
+        let sent = find_sent(
+            client,
+            external_id,
+            request.user_id.as_deref(),
+            request.amount,
+            self.search_since(pending),
+        )
+        .await?;
+        match sent {
+            Some(tx) if tx.status == Status::CREATED => {
+                return self.settle(
+                    external_id,
+                    Ok(link_response_from(tx)),
+                    StoredPayment::PaymentLink,
+                );
+            }
+            Some(tx) => return Err(duplicate_payment(external_id, tx.transaction_id)),
+            None => self.check_interrupted(external_id, pending)?,
+        }
+        self.reserve(external_id, pending)?;
+        let result = PaymentApi::create_payment(client, request).await;
+        self.settle(external_id, result, StoredPayment::PaymentLink)
+    }
+
+    /// Initiates a direct payment unless one already exists for the request's `external_id`.
+    ///
+    /// A transaction already on Fapshi for the external ID, e.g. left by an interrupted
+    /// request or recorded in a store that was since lost, is recorded and returned.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the request has no `external_id`,
+    /// `FapshiError::PaymentInProgress` if another request for it is in flight, and
+    /// `FapshiError::DuplicatePayment` if the external ID belongs to a payment link.
+    #[cfg(not(feature = "async"))]
+    pub fn initiate_direct_payment(
+        &self,
+        client: &FapshiClient,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        let external_id = required_external_id(request.external_id.as_deref())?;
+        let pending = match self.store.get(external_id)? {
+            None => None,
+            Some(StoredPayment::DirectPayment(response)) => return Ok(response),
+            Some(StoredPayment::Pending { since }) => Some(since),
+            Some(other) => return Err(duplicate_stored(external_id, &other)),
+        };
+        let sent = find_sent(
+            client,
+            external_id,
+            request.user_id.as_deref(),
+            f64::from(request.amount),
+            self.search_since(pending),
+        )?;
+        if let Some(tx) = sent {
+            return self.settle(
+                external_id,
+                Ok(direct_response_from(tx)),
+                StoredPayment::DirectPayment,
+            );
+        }
+        self.check_interrupted(external_id, pending)?;
+        self.reserve(external_id, pending)?;
+        let result = PaymentApi::initiate_direct_payment(client, request);
+        self.settle(external_id, result, StoredPayment::DirectPayment)
+    }
+
+    /// Initiates a direct payment unless one already exists for the request's `external_id`,
+    /// asynchronously.
+    ///
+    /// A transaction already on Fapshi for the external ID, e.g. left by an interrupted
+    /// request or recorded in a store that was since lost, is recorded and returned.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the request has no `external_id`,
+    /// `FapshiError::PaymentInProgress` if another request for it is in flight, and
+    /// `FapshiError::DuplicatePayment` if the external ID belongs to a payment link.
+    #[cfg(feature = "async")]
+    pub async fn initiate_direct_payment(
+        &self,
+        client: &FapshiClient,
+        request: &DirectPaymentRequest,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        let external_id = required_external_id(request.external_id.as_deref())?;
+        let pending = match self.store.get(external_id)? {
+            None => None,
+            Some(StoredPayment::DirectPayment(response)) => return Ok(response),
+            Some(StoredPayment::Pending { since }) => Some(since),
+            Some(other) => return Err(duplicate_stored(external_id, &other)),
+        };
+        let sent = find_sent(
+            client,
+            external_id,
+            request.user_id.as_deref(),
+            f64::from(request.amount),
+            self.search_since(pending),
+        )
+        .await?;
+        if let Some(tx) = sent {
+            return self.settle(
+                external_id,
+                Ok(direct_response_from(tx)),
+                StoredPayment::DirectPayment,
+            );
+        }
+        self.check_interrupted(external_id, pending)?;
+        self.reserve(external_id, pending)?;
+        let result = PaymentApi::initiate_direct_payment(client, request).await;
+        self.settle(external_id, result, StoredPayment::DirectPayment)
+    }
+
+    /// Fails while a pending marker is younger than the in-flight timeout.
+    fn check_interrupted(
+        &self,
+        external_id: &str,
+        pending: Option<DateTime<Utc>>,
+    ) -> Result<(), FapshiError> {
+        let timeout = TimeDelta::from_std(self.in_flight_timeout).unwrap_or(TimeDelta::MAX);
+        match pending {
+            Some(since) if Utc::now() - since < timeout => Err(in_progress(external_id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// When to search Fapshi from: the pending marker's start, or the lookback period.
+    fn search_since(&self, pending: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        pending.unwrap_or_else(|| {
+            Utc::now() - TimeDelta::from_std(self.lookback).unwrap_or(TimeDelta::MAX)
+        })
+    }
+
+    /// Writes the pending marker, replacing the stale marker started at `replacing`.
+    fn reserve(
+        &self,
+        external_id: &str,
+        replacing: Option<DateTime<Utc>>,
+    ) -> Result<(), FapshiError> {
+        if self.store.reserve(external_id, Utc::now(), replacing)? {
+            Ok(())
+        } else {
+            Err(in_progress(external_id))
+        }
+    }
+
+    /// Records a response in place of the pending marker, or removes the marker if Fapshi
+    /// certainly did not process the request. Other failures leave it for the next call.
+    fn settle<T: Clone>(
+        &self,
+        external_id: &str,
+        result: Result<T, FapshiError>,
+        stored: impl FnOnce(T) -> StoredPayment,
+    ) -> Result<T, FapshiError> {
+        match result {
+            Ok(response) => {
+                self.store.put(external_id, &stored(response.clone()))?;
+                Ok(response)
+            }
+            Err(err) => {
+                if err.is_not_processed() {
+                    self.store.remove(external_id)?;
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Searches Fapshi for a live transaction sent for `external_id` since `since`.
+#[cfg(not(feature = "async"))]
+fn find_sent(
+    client: &FapshiClient,
+    external_id: &str,
+    user_id: Option<&str>,
+    amount: f64,
+    since: DateTime<Utc>,
+) -> Result<Option<TransactionStatus>, FapshiError> {
+    if let Some(user_id) = user_id {
+        let transactions = TransactionApi::get_transactions_by_user_id(client, user_id)?;
+        return Ok(find_live_transaction(transactions, external_id));
+    }
+    let mut found = None;
+    TransactionApi::search_pages(client, &sent_query(amount, since), |page| {
+        found = found.take().or(find_live_transaction(page, external_id));
+        Ok(())
+    })?;
+    Ok(found)
+}
+
+/// Searches Fapshi for a live transaction sent for `external_id` since `since`,
+/// asynchronously.
+#[cfg(feature = "async")]
+async fn find_sent(
+    client: &FapshiClient,
+    external_id: &str,
+    user_id: Option<&str>,
+    amount: f64,
+    since: DateTime<Utc>,
+) -> Result<Option<TransactionStatus>, FapshiError> {
+    if let Some(user_id) = user_id {
+        let transactions = TransactionApi::get_transactions_by_user_id(client, user_id).await?;
+        return Ok(find_live_transaction(transactions, external_id));
+    }
+    let mut found = None;
+    TransactionApi::search_pages(client, &sent_query(amount, since), |page| {
+        found = found.take().or(find_live_transaction(page, external_id));
+        Ok(())
+    })
+    .await?;
+    Ok(found)
+}
+
+/// The search for the transactions of `amount` initiated since `since`. It starts a day
+/// early, as Fapshi may date transactions in another time zone.
+fn sent_query(amount: f64, since: DateTime<Utc>) -> TransactionSearchQuery {
+    let day = |date: DateTime<Utc>| date.date_naive().format("%Y-%m-%d").to_string();
+    TransactionSearchQuery {
+        start: Some(day(since - TimeDelta::days(1))),
+        end: Some(day(Utc::now() + TimeDelta::days(1))),
+        amt: Some(amount),
+        ..Default::default()
+    }
+}
+
+fn required_external_id(external_id: Option<&str>) -> Result<&str, FapshiError> {
+    match external_id {
+        Some(id) if !id.is_empty() => Ok(id),
+        _ => Err(FapshiError::InvalidRequest(
+            "an external_id is required for idempotent payments".to_string(),
+        )),
+    }
+}
+
+/// Finds a transaction for `external_id` that has not failed or expired.
+fn find_live_transaction(
+    transactions: Vec<TransactionStatus>,
+    external_id: &str,
+) -> Option<TransactionStatus> {
+    transactions.into_iter().find(|tx| {
+        tx.external_id.as_deref() == Some(external_id)
+            && !matches!(tx.status, Status::FAILED | Status::EXPIRED)
+    })
+}
+
+fn in_progress(external_id: &str) -> FapshiError {
+    FapshiError::PaymentInProgress {
+        external_id: external_id.to_string(),
+    }
+}
+
+fn duplicate_payment(external_id: &str, transaction_id: String) -> FapshiError {
+    FapshiError::DuplicatePayment {
+        external_id: external_id.to_string(),
+        transaction_id,
+    }
+}
+
+/// The error for an external ID recorded for the other kind of payment.
+pub(crate) fn duplicate_stored(external_id: &str, stored: &StoredPayment) -> FapshiError {
+    match stored.transaction_id() {
+        Some(transaction_id) => duplicate_payment(external_id, transaction_id.to_string()),
+        None => in_progress(external_id),
+    }
+}
+
+/// The response for an unpaid link found on Fapshi, whose link Fapshi does not return.
+fn link_response_from(transaction: TransactionStatus) -> PaymentResponse {
+    PaymentResponse {
+        message: format!("Existing {:?} transaction", transaction.status),
+        payment_link: String::new(),
+        transaction_id: transaction.transaction_id,
+        date_initiated: transaction.date_initiated,
+    }
+}
+
+fn direct_response_from(transaction: TransactionStatus) -> DirectPaymentResponse {
+    DirectPaymentResponse {
+        transaction_id: transaction.transaction_id,
+        message: format!("Existing {:?} transaction", transaction.status),
+        date_initiated: transaction.date_initiated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    fn transaction_json(transaction_id: &str, status: &str, external_id: &str) -> Value {
+        json!({
+            "transId": transaction_id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": 500.0,
+            "email": "",
+            "redirectUrl": "",
+            "externalId": external_id,
+            "dateInitiated": "2025-01-31",
+            "dateConfirmed": "",
+        })
+    }
+
+    fn transaction(transaction_id: &str, status: &str, external_id: &str) -> TransactionStatus {
+        serde_json::from_value(transaction_json(transaction_id, status, external_id)).unwrap()
+    }
+
+    fn interaction(
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        status: u16,
+        response: Value,
+    ) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: method.to_string(),
+                path: path.to_string(),
+                headers: BTreeMap::new(),
+                body,
+            },
+            response: RecordedResponse {
+                status,
+                headers: BTreeMap::new(),
+                body: response.to_string(),
+            },
+        }
+    }
+
+    /// The user's transactions on Fapshi, as searched before sending a payment.
+    fn user_transactions(transactions: Vec<Value>) -> Interaction {
+        interaction(
+            "GET",
+            "transaction/customer42",
+            None,
+            200,
+            Value::Array(transactions),
+        )
+    }
+
+    fn client(interactions: Vec<Interaction>) -> FapshiClient {
+        FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(interactions))
+    }
+
+    fn link_request() -> PaymentRequest {
+        PaymentRequest {
+            amount: 500.0,
+            email: None,
+            redirect_url: None,
+            user_id: Some("customer42".to_string()),
+            external_id: Some("order-1".to_string()),
+            message: "Order 1".to_string(),
+            card_only: None,
+        }
+    }
+
+    fn direct_request() -> DirectPaymentRequest {
+        DirectPaymentRequest {
+            amount: 500.0,
+            phone: "670000000".to_string(),
+            medium: None,
+            name: None,
+            email: None,
+            user_id: Some("customer42".to_string()),
+            external_id: Some("order-1".to_string()),
+            message: None,
+        }
+    }
+
+    fn initiate_pay(status: u16, response: Value) -> Interaction {
+        let body = serde_json::to_value(link_request()).unwrap();
+        interaction("POST", "initiate-pay", Some(body), status, response)
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn create(
+        payments: &IdempotentPayments<InMemoryIdempotencyStore>,
+        client: &FapshiClient,
+    ) -> Result<PaymentResponse, FapshiError> {
+        payments.create_payment(client, &link_request())
+    }
+
+    #[cfg(feature = "async")]
+    fn create(
+        payments: &IdempotentPayments<InMemoryIdempotencyStore>,
+        client: &FapshiClient,
+    ) -> Result<PaymentResponse, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(payments.create_payment(client, &link_request()))
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn direct(
+        payments: &IdempotentPayments<InMemoryIdempotencyStore>,
+        client: &FapshiClient,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        payments.initiate_direct_payment(client, &direct_request())
+    }
+
+    #[cfg(feature = "async")]
+    fn direct(
+        payments: &IdempotentPayments<InMemoryIdempotencyStore>,
+        client: &FapshiClient,
+    ) -> Result<DirectPaymentResponse, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(payments.initiate_direct_payment(client, &direct_request()))
+    }
+
+    fn link(transaction_id: &str) -> StoredPayment {
+        StoredPayment::PaymentLink(PaymentResponse {
+            message: "Request successful".to_string(),
+            payment_link: format!("https://checkout.fapshi.com/link/{transaction_id}"),
+            transaction_id: transaction_id.to_string(),
+            date_initiated: "2025-01-31".to_string(),
+        })
+    }
+
+    fn stored_id(payment: Option<StoredPayment>) -> Option<String> {
+        payment?.transaction_id().map(str::to_string)
+    }
+
+    #[test]
+    fn external_id_is_required() {
+        assert!(required_external_id(None).is_err());
+        assert!(required_external_id(Some("")).is_err());
+        assert_eq!(required_external_id(Some("order-1")).unwrap(), "order-1");
+    }
+
+    #[test]
+    fn only_live_transactions_with_the_external_id_match() {
+        let transactions = vec![
+            transaction("T1", "FAILED", "order-1"),
+            transaction("T2", "SUCCESSFUL", "order-2"),
+            transaction("T3", "EXPIRED", "order-1"),
+            transaction("T4", "PENDING", "order-1"),
+        ];
+        let found = find_live_transaction(transactions.clone(), "order-1").unwrap();
+        assert_eq!(found.transaction_id, "T4");
+        assert!(find_live_transaction(transactions[..3].to_vec(), "order-1").is_none());
+        assert!(find_live_transaction(transactions, "order-3").is_none());
+    }
+
+    #[test]
+    fn in_memory_store_keeps_payments() {
+        let store = InMemoryIdempotencyStore::new();
+        assert!(store.get("order-1").unwrap().is_none());
+        store.put("order-1", &link("T1")).unwrap();
+        assert_eq!(
+            stored_id(store.get("order-1").unwrap()).as_deref(),
+            Some("T1")
+        );
+    }
+
+    #[test]
+    fn file_store_survives_reopening() {
+        let path =
+            std::env::temp_dir().join(format!("fapshi-rs-idempotency-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        FileIdempotencyStore::new(&path)
+            .put("order-1", &link("T1"))
+            .unwrap();
+        FileIdempotencyStore::new(&path)
+            .put("order-2", &link("T2"))
+            .unwrap();
+
+        let reopened = FileIdempotencyStore::new(&path);
+        assert_eq!(
+            stored_id(reopened.get("order-1").unwrap()).as_deref(),
+            Some("T1")
+        );
+        assert_eq!(
+            stored_id(reopened.get("order-2").unwrap()).as_deref(),
+            Some("T2")
+        );
+        assert!(reopened.get("order-3").unwrap().is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reserve_only_replaces_the_expected_marker() {
+        let store = InMemoryIdempotencyStore::new();
+        let first = Utc::now();
+        let second = first + TimeDelta::seconds(1);
+        assert!(store.reserve("order-1", first, None).unwrap());
+        assert!(!store.reserve("order-1", second, None).unwrap());
+        assert!(!store.reserve("order-1", second, Some(second)).unwrap());
+        assert!(store.reserve("order-1", second, Some(first)).unwrap());
+        assert!(matches!(
+            store.get("order-1").unwrap(),
+            Some(StoredPayment::Pending { since }) if since == second
+        ));
+
+        store.put("order-1", &link("T1")).unwrap();
+        assert!(!store.reserve("order-1", second, Some(second)).unwrap());
+        store.remove("order-1").unwrap();
+        assert!(store.get("order-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn reserve_then_settle_records_the_response() {
+        let payments = IdempotentPayments::new(InMemoryIdempotencyStore::new());
+        payments.reserve("order-1", None).unwrap();
+        assert!(matches!(
+            payments.reserve("order-1", None),
+            Err(FapshiError::PaymentInProgress { .. })
+        ));
+        let StoredPayment::PaymentLink(response) = link("T1") else {
+            unreachable!()
+        };
+        payments
+            .settle("order-1", Ok(response), StoredPayment::PaymentLink)
+            .unwrap();
+        assert_eq!(
+            stored_id(payments.store().get("order-1").unwrap()).as_deref(),
+            Some("T1")
+        );
+
+        // A refused request removes its marker; one with an unknown outcome keeps it.
+        payments.reserve("order-2", None).unwrap();
+        let refused = FapshiError::StatusError {
+            status: 400,
+            message: "Invalid amount".to_string(),
+            request_id: None,
+        };
+        assert!(payments
+            .settle::<PaymentResponse>("order-2", Err(refused), StoredPayment::PaymentLink)
+            .is_err());
+        assert!(payments.store().get("order-2").unwrap().is_none());
+        payments.reserve("order-3", None).unwrap();
+        let unknown = FapshiError::StatusError {
+            status: 502,
+            message: "Bad gateway".to_string(),
+            request_id: None,
+        };
+        assert!(payments
+            .settle::<PaymentResponse>("order-3", Err(unknown), StoredPayment::PaymentLink)
+            .is_err());
+        assert!(matches!(
+            payments.store().get("order-3").unwrap(),
+            Some(StoredPayment::Pending { .. })
+        ));
+    }
+
+    #[test]
+    fn stored_responses_are_replayed_without_calling_fapshi() {
+        let payments = IdempotentPayments::new(InMemoryIdempotencyStore::new());
+        let client = client(vec![
+            user_transactions(vec![]),
+            initiate_pay(
+                200,
+                json!({
+                    "message": "Request successful",
+                    "link": "https://checkout.fapshi.com/link/T1",
+                    "transId": "T1",
+                    "dateInitiated": "2025-01-31",
+                }),
+            ),
+        ]);
+
+        let first = create(&payments, &client).unwrap();
+        // The cassette has no interaction left, so a second request to Fapshi would fail.
+        let replayed = create(&payments, &client).unwrap();
+        assert_eq!(replayed.transaction_id, "T1");
+        assert_eq!(replayed.payment_link, first.payment_link);
+        assert!(matches!(
+            direct(&payments, &client),
+            Err(FapshiError::DuplicatePayment { transaction_id, .. }) if transaction_id == "T1"
+        ));
+    }
+
+    #[test]
+    fn pending_markers_are_recovered_from_fapshi() {
+        let payments = IdempotentPayments::new(InMemoryIdempotencyStore::new());
+        let client = client(vec![
+            user_transactions(vec![]),
+            initiate_pay(502, json!({"message": "Bad gateway"})),
+            user_transactions(vec![]),
+            user_transactions(vec![transaction_json("T1", "CREATED", "order-1")]),
+        ]);
+
+        assert!(create(&payments, &client).is_err());
+        assert!(matches!(
+            payments.store().get("order-1").unwrap(),
+            Some(StoredPayment::Pending { .. })
+        ));
+        // Fapshi has no transaction yet and the marker is recent.
+        assert!(matches!(
+            create(&payments, &client),
+            Err(FapshiError::PaymentInProgress { .. })
+        ));
+
+        // The unpaid link is returned rather than expired and created again.
+        let recovered = create(&payments, &client).unwrap();
+        assert_eq!(recovered.transaction_id, "T1");
+        assert!(recovered.payment_link.is_empty());
+        assert_eq!(
+            stored_id(payments.store().get("order-1").unwrap()).as_deref(),
+            Some("T1")
+        );
+    }
+
+    #[test]
+    fn payments_missing_from_the_store_are_recovered_from_fapshi() {
+        let payments = IdempotentPayments::new(InMemoryIdempotencyStore::new());
+        let client = client(vec![
+            user_transactions(vec![
+                transaction_json("T0", "FAILED", "order-1"),
+                transaction_json("T1", "SUCCESSFUL", "order-1"),
+            ]),
+            user_transactions(vec![transaction_json("T2", "PENDING", "order-1")]),
+        ]);
+
+        let recovered = direct(&payments, &client).unwrap();
+        assert_eq!(recovered.transaction_id, "T1");
+        assert_eq!(
+            stored_id(payments.store().get("order-1").unwrap()).as_deref(),
+            Some("T1")
+        );
+
+        // A link the payer already started paying is not created again.
+        payments.store().remove("order-1").unwrap();
+        assert!(matches!(
+            create(&payments, &client),
+            Err(FapshiError::DuplicatePayment { transaction_id, .. }) if transaction_id == "T2"
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, Method, StatusCode};
+
+use crate::error::FapshiError;
+
+/// A request about to be sent by a [`FapshiClient`](crate::client::FapshiClient), as seen
+/// by `before_request` hooks.
+///
+/// Hooks may change any part of it, e.g. add a correlation-id header. The safety checks (dry
+/// run, amount limits) run after the hooks, on the request as they left it.
+#[derive(Debug, Clone)]
+pub struct RequestParts {
+    /// The HTTP method.
+    pub method: Method,
+    /// The API endpoint path, relative to the environment's base URL.
+    pub endpoint: String,
+    /// The request headers, including `apiuser` and `apikey`.
+    pub headers: HeaderMap,
+    /// The JSON body, if any.
+    pub body: Option<String>,
+}
+
+/// The outcome of a request, as seen by `after_response` hooks.
+///
+/// `status`, `headers` and `body` are set when a response was received (or replayed from a
+/// cassette), including non-success ones; `error` is set when none was.
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseParts<'a> {
+    /// The request as it was sent, after the `before_request` hooks.
+    pub request: &'a RequestParts,
+    /// The HTTP status code.
+    pub status: Option<StatusCode>,
+    /// The response headers.
+    pub headers: Option<&'a HeaderMap>,
+    /// The raw response body.
+    pub body: Option<&'a str>,
+    /// The error that prevented a response, e.g. a connection failure.
+    pub error: Option<&'a FapshiError>,
+    /// The time the request took.
+    pub elapsed: Duration,
+}
+
+type BeforeRequest = Arc<dyn Fn(&mut RequestParts) + Send + Sync>;
+type AfterResponse = Arc<dyn Fn(&ResponseParts<'_>) + Send + Sync>;
+type RecordError = Arc<dyn Fn(&FapshiError) + Send + Sync>;
+
+/// The hooks registered on a client, run in registration order.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors {
+    before: Vec<BeforeRequest>,
+    after: Vec<AfterResponse>,
+    record_error: Vec<RecordError>,
+}
+
+impl Interceptors {
+    pub(crate) fn add_before(&mut self, hook: impl Fn(&mut RequestParts) + Send + Sync + 'static) {
+        self.before.push(Arc::new(hook));
+    }
+
+    pub(crate) fn add_after(&mut self, hook: impl Fn(&ResponseParts<'_>) + Send + Sync + 'static) {
+        self.after.push(Arc::new(hook));
+    }
+
+    pub(crate) fn add_record_error(&mut self, hook: impl Fn(&FapshiError) + Send + Sync + 'static) {
+        self.record_error.push(Arc::new(hook));
+    }
+
+    pub(crate) fn before_request(&self, request: &mut RequestParts) {
+        for hook in &self.before {
+            hook(request);
+        }
+    }
+
+    pub(crate) fn after_response(&self, response: &ResponseParts<'_>) {
+        for hook in &self.after {
+            hook(response);
+        }
+    }
+
+    /// Reports an audit record or cassette entry that could not be written after the request
+    /// was sent. Without a hook the error is dropped.
+    pub(crate) fn record_error(&self, err: &FapshiError) {
+        for hook in &self.record_error {
+            hook(err);
+        }
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("before_request", &self.before.len())
+            .field("after_response", &self.after.len())
+            .field("on_record_error", &self.record_error.len())
+            .finish()
+    }
+}
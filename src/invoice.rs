@@ -0,0 +1,516 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{payment::PaymentApi, transaction::TransactionApi},
+    client::FapshiClient,
+    error::FapshiError,
+    models::{
+        validate_id, PaymentRequest, PaymentResponse, Status, TransactionStatus,
+        PAYMENT_LINK_VALIDITY,
+    },
+};
+
+/// A line of an invoice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LineItem {
+    /// What is being billed.
+    pub description: String,
+    /// How many units are billed.
+    pub quantity: u32,
+    /// The price of one unit.
+    pub unit_price: f64,
+}
+
+impl LineItem {
+    /// Returns `quantity * unit_price`.
+    pub fn total(&self) -> f64 {
+        f64::from(self.quantity) * self.unit_price
+    }
+}
+
+/// The customer an invoice is addressed to.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct InvoiceCustomer {
+    /// The customer's name.
+    pub name: Option<String>,
+    /// The customer's email, pre-filled on the payment page.
+    pub email: Option<String>,
+    /// The customer's ID in your system, sent as `userId`.
+    pub user_id: Option<String>,
+}
+
+/// The payment state of an invoice.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    /// Nothing has been paid yet and the invoice is not due.
+    Open,
+    /// Part of the total has been paid and the invoice is not due.
+    PartiallyPaid,
+    /// The total has been paid.
+    Paid,
+    /// The due date has passed and the total has not been paid.
+    Overdue,
+}
+
+/// A payment link issued for an invoice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IssuedLink {
+    /// The transaction ID of the link.
+    pub transaction_id: String,
+    /// The payment link.
+    pub payment_link: String,
+    /// The amount requested by the link.
+    pub amount: f64,
+    /// When the link was issued.
+    pub issued_at: DateTime<Utc>,
+    /// When the link expires.
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A Fapshi transaction carrying the invoice number as its external ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InvoicePayment {
+    /// The Fapshi transaction ID.
+    pub transaction_id: String,
+    /// The transaction amount.
+    pub amount: f64,
+    /// The last known status.
+    pub status: Status,
+}
+
+/// An invoice paid through Fapshi payment links.
+///
+/// The invoice number is used as the `external_id` of every link issued for the invoice, so
+/// any transaction carrying it counts towards the invoice.
+///
+/// # Example
+/// ```no_run
+/// use chrono::{Duration, Utc};
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     invoice::{Invoice, InvoiceApi, InvoiceCustomer, LineItem},
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let mut invoice = Invoice::new(
+///     "INV-2025-0042",
+///     InvoiceCustomer {
+///         name: Some("Jane".to_string()),
+///         email: Some("jane@example.com".to_string()),
+///         user_id: Some("customer42".to_string()),
+///     },
+///     vec![LineItem {
+///         description: "Consulting".to_string(),
+///         quantity: 3,
+///         unit_price: 10_000.0,
+///     }],
+///     Utc::now() + Duration::days(14),
+/// )?;
+/// let link = InvoiceApi::issue(&client, &mut invoice)?;
+/// println!("Pay here: {}", link.payment_link);
+/// // Later, e.g. from a cron job or a webhook handler:
+/// let status = InvoiceApi::refresh(&client, &mut invoice)?;
+/// println!("{}: {:?}, paid {}", invoice.number, status, invoice.amount_paid());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Invoice {
+    /// The invoice number (1-100 characters, alphanumeric with -_), used as `external_id`.
+    pub number: String,
+    /// An optional reference shown in the payment message, e.g. a purchase order number.
+    pub reference: Option<String>,
+    /// The customer billed.
+    pub customer: InvoiceCustomer,
+    /// The billed items.
+    pub items: Vec<LineItem>,
+    /// When the invoice was issued.
+    pub issued_at: DateTime<Utc>,
+    /// When the invoice must be paid.
+    pub due_at: DateTime<Utc>,
+    /// Where the payment page redirects to after payment.
+    pub redirect_url: Option<String>,
+    /// The links issued for the invoice, oldest first.
+    pub links: Vec<IssuedLink>,
+    /// The transactions found for the invoice.
+    pub payments: Vec<InvoicePayment>,
+}
+
+impl Invoice {
+    /// Creates an invoice issued now.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the number cannot be used as an external ID.
+    pub fn new(
+        number: &str,
+        customer: InvoiceCustomer,
+        items: Vec<LineItem>,
+        due_at: DateTime<Utc>,
+    ) -> Result<Self, FapshiError> {
+        validate_id("invoice number", Some(number))?;
+        Ok(Invoice {
+            number: number.to_string(),
+            reference: None,
+            customer,
+            items,
+            issued_at: Utc::now(),
+            due_at,
+            redirect_url: None,
+            links: Vec::new(),
+            payments: Vec::new(),
+        })
+    }
+
+    /// Returns the sum of the line items.
+    pub fn total(&self) -> f64 {
+        self.items.iter().map(LineItem::total).sum()
+    }
+
+    /// Returns the amount of the successful transactions for the invoice.
+    pub fn amount_paid(&self) -> f64 {
+        self.payments
+            .iter()
+            .filter(|payment| payment.status == Status::SUCCESSFUL)
+            .map(|payment| payment.amount)
+            .sum()
+    }
+
+    /// Returns what remains to be paid, never negative.
+    pub fn amount_due(&self) -> f64 {
+        (self.total() - self.amount_paid()).max(0.0)
+    }
+
+    /// Returns the payment state of the invoice at `now`.
+    ///
+    /// An invoice past its due date is `Overdue` even if it was partially paid.
+    pub fn status(&self, now: DateTime<Utc>) -> InvoiceStatus {
+        if self.amount_due() <= 0.0 {
+            InvoiceStatus::Paid
+        } else if now > self.due_at {
+            InvoiceStatus::Overdue
+        } else if self.amount_paid() > 0.0 {
+            InvoiceStatus::PartiallyPaid
+        } else {
+            InvoiceStatus::Open
+        }
+    }
+
+    /// Returns the latest issued link if it can still be paid at `now`.
+    pub fn current_link(&self, now: DateTime<Utc>) -> Option<&IssuedLink> {
+        let link = self.links.last()?;
+        let status = self
+            .payments
+            .iter()
+            .find(|payment| payment.transaction_id == link.transaction_id)
+            .map(|payment| &payment.status);
+        let usable = matches!(status, None | Some(Status::CREATED | Status::PENDING));
+        (usable && link.expires_at > now).then_some(link)
+    }
+
+    /// Returns whether a new link should be issued: something is still due and there is no
+    /// link that can be paid.
+    pub fn needs_link(&self, now: DateTime<Utc>) -> bool {
+        self.amount_due() > 0.0 && self.current_link(now).is_none()
+    }
+
+    /// Builds the payment link request for the amount still due.
+    pub fn payment_request(&self) -> PaymentRequest {
+        let message = match &self.reference {
+            Some(reference) => format!("Invoice {} ({reference})", self.number),
+            None => format!("Invoice {}", self.number),
+        };
+        PaymentRequest {
+            amount: self.amount_due(),
+            email: self.customer.email.clone(),
+            redirect_url: self.redirect_url.clone(),
+            user_id: self.customer.user_id.clone(),
+            external_id: Some(self.number.clone()),
+            message,
+            card_only: None,
+        }
+    }
+
+    /// Records a link created from [`Invoice::payment_request`].
+    pub fn record_link(&mut self, response: &PaymentResponse, amount: f64) -> &IssuedLink {
+        let issued_at = response.initiated_at().unwrap_or_else(Utc::now);
+        self.links.push(IssuedLink {
+            transaction_id: response.transaction_id.clone(),
+            payment_link: response.payment_link.clone(),
+            amount,
+            issued_at,
+            expires_at: response
+                .expires_at()
+                .unwrap_or(issued_at + PAYMENT_LINK_VALIDITY),
+        });
+        self.links.last().expect("a link was just pushed")
+    }
+
+    /// Updates the invoice's payments from transactions; only transactions whose external ID
+    /// is the invoice number are taken into account.
+    pub fn apply_transactions<'a>(
+        &mut self,
+        transactions: impl IntoIterator<Item = &'a TransactionStatus>,
+    ) {
+        for tx in transactions {
+            if tx.external_id.as_deref() != Some(self.number.as_str()) {
+                continue;
+            }
+            let payment = InvoicePayment {
+                transaction_id: tx.transaction_id.clone(),
+                amount: tx.amount,
+                status: tx.status.clone(),
+            };
+            match self
+                .payments
+                .iter_mut()
+                .find(|existing| existing.transaction_id == tx.transaction_id)
+            {
+                Some(existing) => *existing = payment,
+                None => self.payments.push(payment),
+            }
+        }
+    }
+}
+
+/// API for issuing and tracking invoice payment links.
+pub struct InvoiceApi;
+
+impl InvoiceApi {
+    /// Creates a payment link for the amount still due on an invoice, synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `invoice` - The invoice; the new link is appended to its `links`.
+    ///
+    /// # Returns
+    /// A `Result` containing the issued link, or a `FapshiError` if nothing is due or the
+    /// request fails.
+    #[cfg(not(feature = "async"))]
+    pub fn issue<'a>(
+        client: &FapshiClient,
+        invoice: &'a mut Invoice,
+    ) -> Result<&'a IssuedLink, FapshiError> {
+        let request = issue_request(invoice)?;
+        let response = PaymentApi::create_payment(client, &request)?;
+        Ok(invoice.record_link(&response, request.amount))
+    }
+
+    /// Creates a payment link for the amount still due on an invoice, asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `invoice` - The invoice; the new link is appended to its `links`.
+    ///
+    /// # Returns
+    /// A `Result` containing the issued link, or a `FapshiError` if nothing is due or the
+    /// request fails.
+    #[cfg(feature = "async")]
+    pub async fn issue<'a>(
+        client: &FapshiClient,
+        invoice: &'a mut Invoice,
+    ) -> Result<&'a IssuedLink, FapshiError> {
+        let request = issue_request(invoice)?;
+        let response = PaymentApi::create_payment(client, &request).await?;
+        Ok(invoice.record_link(&response, request.amount))
+    }
+
+    /// Updates an invoice from Fapshi and re-issues its link if it expired, synchronously.
+    ///
+    /// Transactions are read from the customer's `user_id` when set, which also finds
+    /// payments made outside the issued links; otherwise the status of each issued link is
+    /// read. If something is still due and the last link expired or failed, a fresh link is
+    /// issued.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `invoice` - The invoice to update.
+    ///
+    /// # Returns
+    /// A `Result` containing the invoice's new status, or a `FapshiError` if a request fails.
+    #[cfg(not(feature = "async"))]
+    pub fn refresh(
+        client: &FapshiClient,
+        invoice: &mut Invoice,
+    ) -> Result<InvoiceStatus, FapshiError> {
+        let transactions = match &invoice.customer.user_id {
+            Some(user_id) => TransactionApi::get_transactions_by_user_id(client, user_id)?,
+            None => invoice
+                .links
+                .iter()
+                .map(|link| TransactionApi::get_status(client, &link.transaction_id))
+                .collect::<Result<_, _>>()?,
+        };
+        invoice.apply_transactions(&transactions);
+        if !invoice.links.is_empty() && invoice.needs_link(Utc::now()) {
+            Self::issue(client, invoice)?;
+        }
+        Ok(invoice.status(Utc::now()))
+    }
+
+    /// Updates an invoice from Fapshi and re-issues its link if it expired, asynchronously.
+    ///
+    /// Transactions are read from the customer's `user_id` when set, which also finds
+    /// payments made outside the issued links; otherwise the status of each issued link is
+    /// read. If something is still due and the last link expired or failed, a fresh link is
+    /// issued.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `invoice` - The invoice to update.
+    ///
+    /// # Returns
+    /// A `Result` containing the invoice's new status, or a `FapshiError` if a request fails.
+    #[cfg(feature = "async")]
+    pub async fn refresh(
+        client: &FapshiClient,
+        invoice: &mut Invoice,
+    ) -> Result<InvoiceStatus, FapshiError> {
+        let transactions = match &invoice.customer.user_id {
+            Some(user_id) => TransactionApi::get_transactions_by_user_id(client, user_id).await?,
+            None => {
+                let mut transactions = Vec::with_capacity(invoice.links.len());
+                for link in &invoice.links {
+                    transactions
+                        .push(TransactionApi::get_status(client, &link.transaction_id).await?);
+                }
+                transactions
+            }
+        };
+        invoice.apply_transactions(&transactions);
+        if !invoice.links.is_empty() && invoice.needs_link(Utc::now()) {
+            Self::issue(client, invoice).await?;
+        }
+        Ok(invoice.status(Utc::now()))
+    }
+}
+
+fn issue_request(invoice: &Invoice) -> Result<PaymentRequest, FapshiError> {
+    if invoice.amount_due() <= 0.0 {
+        return Err(FapshiError::InvalidRequest(format!(
+            "invoice {} has nothing left to pay",
+            invoice.number
+        )));
+    }
+    Ok(invoice.payment_request())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+    use serde_json::json;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap()
+    }
+
+    fn invoice() -> Invoice {
+        let mut invoice = Invoice::new(
+            "INV-42",
+            InvoiceCustomer {
+                email: Some("jane@example.com".to_string()),
+                ..Default::default()
+            },
+            vec![
+                LineItem {
+                    description: "Consulting".to_string(),
+                    quantity: 3,
+                    unit_price: 10_000.0,
+                },
+                LineItem {
+                    description: "Travel".to_string(),
+                    quantity: 1,
+                    unit_price: 5_000.0,
+                },
+            ],
+            at(20, 0),
+        )
+        .unwrap();
+        invoice.reference = Some("PO-7".to_string());
+        invoice
+    }
+
+    fn transaction(id: &str, external_id: &str, amount: f64, status: &str) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": amount,
+            "email": "",
+            "redirectUrl": "",
+            "externalId": external_id,
+            "dateInitiated": "2025-01-10T09:00:00.000Z",
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    fn link(id: &str) -> PaymentResponse {
+        PaymentResponse {
+            message: "Request successful".to_string(),
+            payment_link: format!("https://checkout.fapshi.com/link/{id}"),
+            transaction_id: id.to_string(),
+            date_initiated: "2025-01-10T09:00:00.000Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn invoice_number_must_be_a_valid_external_id() {
+        let invalid = Invoice::new("INV 42!", InvoiceCustomer::default(), Vec::new(), at(20, 0));
+        assert!(matches!(invalid, Err(FapshiError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn payment_request_asks_for_the_amount_due() {
+        let mut invoice = invoice();
+        assert_eq!(invoice.total(), 35_000.0);
+        invoice.apply_transactions(&[
+            transaction("T1", "INV-42", 10_000.0, "SUCCESSFUL"),
+            transaction("T9", "INV-43", 35_000.0, "SUCCESSFUL"),
+        ]);
+        let request = invoice.payment_request();
+        assert_eq!(request.amount, 25_000.0);
+        assert_eq!(request.external_id.as_deref(), Some("INV-42"));
+        assert_eq!(request.message, "Invoice INV-42 (PO-7)");
+        assert_eq!(request.email.as_deref(), Some("jane@example.com"));
+    }
+
+    #[test]
+    fn status_follows_payments_and_the_due_date() {
+        let mut invoice = invoice();
+        assert_eq!(invoice.status(at(10, 0)), InvoiceStatus::Open);
+        invoice.apply_transactions(&[transaction("T1", "INV-42", 10_000.0, "SUCCESSFUL")]);
+        assert_eq!(invoice.status(at(10, 0)), InvoiceStatus::PartiallyPaid);
+        assert_eq!(invoice.status(at(21, 0)), InvoiceStatus::Overdue);
+        invoice.apply_transactions(&[transaction("T2", "INV-42", 25_000.0, "SUCCESSFUL")]);
+        assert_eq!(invoice.status(at(21, 0)), InvoiceStatus::Paid);
+        assert_eq!(invoice.amount_due(), 0.0);
+        assert!(matches!(
+            issue_request(&invoice),
+            Err(FapshiError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn a_new_link_is_needed_once_the_last_one_is_unusable() {
+        let mut invoice = invoice();
+        assert!(invoice.needs_link(at(10, 9)));
+        let issued = invoice.record_link(&link("T1"), 35_000.0).clone();
+        assert_eq!(issued.expires_at, at(11, 9));
+        assert_eq!(invoice.current_link(at(10, 10)), Some(&issued));
+        assert!(!invoice.needs_link(at(10, 10)));
+        assert!(invoice.needs_link(at(11, 9) + TimeDelta::seconds(1)));
+
+        invoice.apply_transactions(&[transaction("T1", "INV-42", 35_000.0, "FAILED")]);
+        assert!(invoice.needs_link(at(10, 10)));
+        invoice.apply_transactions(&[transaction("T1", "INV-42", 35_000.0, "SUCCESSFUL")]);
+        assert_eq!(invoice.payments.len(), 1);
+        assert!(!invoice.needs_link(at(10, 10)));
+    }
+}
@@ -1,9 +1,14 @@
 pub mod api;
+pub mod audit;
+pub mod batch;
+pub mod cache;
+pub mod cassette;
+pub mod circuit_breaker;
 /// Fapshi SDK for Rust
 ///
 /// This crate provides a convenient and type-safe interface for interacting with the Fapshi payment service API.
 /// It supports creating payment links, querying transaction statuses, expiring transactions, retrieving transactions by user ID,
-/// initiating direct payments, searching transactions, configuring webhooks, and checking service balance.
+/// initiating direct payments, searching transactions, checking webhook endpoints, and checking service balance.
 ///
 /// # Features
 /// - Authenticated API requests using `apiuser` and `apikey`.
@@ -13,40 +18,57 @@ pub mod api;
 /// - Optional async support with the `async` feature, enabling asynchronous API calls for integration with async runtimes.
 ///
 /// # Example (Synchronous)
-/// ```
-/// use fapshi_sdk::{FapshiClient, models::PaymentRequest, api::payment::PaymentApi};
+/// ```no_run
+/// use fapshi_rs::{
+///     api::payment::PaymentApi,
+///     client::FapshiClient,
+///     models::PaymentRequest,
+///     safety::Environment,
+/// };
 ///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// use std::env;
 /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
 /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-/// let client = FapshiClient::new(&api_user, &api_key, true)?;
+/// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
 /// let request = PaymentRequest {
 ///     amount: 100.0,
-///     currency: "USD".to_string(),
-///     description: "Test payment".to_string(),
-///     customer_email: Some("test@example.com".to_string()),
+///     email: Some("test@example.com".to_string()),
+///     redirect_url: None,
 ///     user_id: None,
+///     external_id: None,
+///     message: "Test payment".to_string(),
+///     card_only: None,
 /// };
 /// let response = PaymentApi::create_payment(&client, &request).unwrap();
 /// println!("Payment link: {}", response.payment_link);
+/// # Ok(())
+/// # }
 /// ```
 ///
 /// # Example (Asynchronous)
-/// ```
-/// use fapshi_sdk::{FapshiClient, models::PaymentRequest, api::payment::PaymentApi};
+/// ```ignore
+/// use fapshi_rs::{
+///     api::payment::PaymentApi,
+///     client::FapshiClient,
+///     models::PaymentRequest,
+///     safety::Environment,
+/// };
 ///
 /// # #[tokio::main]
 /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// use std::env;
 /// let api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER not set");
 /// let api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY not set");
-/// let client = FapshiClient::new(&api_user, &api_key, true)?;
+/// let client = FapshiClient::with_environment(&api_user, &api_key, Environment::Sandbox)?;
 /// let request = PaymentRequest {
 ///     amount: 100.0,
-///     currency: "USD".to_string(),
-///     description: "Test payment".to_string(),
-///     customer_email: Some("test@example.com".to_string()),
+///     email: Some("test@example.com".to_string()),
+///     redirect_url: None,
 ///     user_id: None,
+///     external_id: None,
+///     message: "Test payment".to_string(),
+///     card_only: None,
 /// };
 /// let response = PaymentApi::create_payment(&client, &request).await.unwrap();
 /// println!("Payment link: {}", response.payment_link);
@@ -54,5 +76,26 @@ pub mod api;
 /// # }
 /// ```
 pub mod client;
+pub mod endpoint;
 pub mod error;
-pub mod models;
\ No newline at end of file
+pub mod expiry;
+pub mod export;
+pub mod facade;
+pub mod idempotency;
+pub mod interceptor;
+pub mod invoice;
+pub mod metrics;
+pub mod models;
+pub mod monitor;
+pub mod preview;
+#[cfg(feature = "qrcode")]
+pub mod qr;
+pub mod reconciliation;
+pub mod reporting;
+pub mod safety;
+#[cfg(feature = "tower")]
+pub mod service;
+mod store;
+pub mod subscription;
+#[cfg(feature = "testing")]
+pub mod testing;
@@ -0,0 +1,422 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::Method;
+
+use crate::{
+    circuit_breaker::CircuitState,
+    endpoint,
+    models::{Status, TransactionStatus},
+};
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS.len()];
+        }
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[index] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: BTreeMap<(String, String), u64>,
+    latency: BTreeMap<String, Histogram>,
+    retries: BTreeMap<String, u64>,
+    circuit_state: Option<CircuitState>,
+    webhook_events: BTreeMap<String, u64>,
+    payment_outcomes: BTreeMap<(String, String), u64>,
+}
+
+/// Counters and histograms about a client's use of the Fapshi API, with a built-in
+/// Prometheus text encoder.
+///
+/// Enable them with [`FapshiClient::with_metrics`](crate::client::FapshiClient::with_metrics);
+/// requests are then counted per endpoint and status code, and timed per endpoint. Endpoints
+/// are labelled with their catalogue name (e.g. `payment-status`), so transaction IDs do not
+/// end up in label values. Retries, webhook events and payment outcomes happen outside the
+/// client, so they are recorded by calling [`Metrics::record_retry`],
+/// [`Metrics::record_webhook_event`] and [`Metrics::record_payment_outcome`].
+///
+/// | Metric | Type | Labels |
+/// |---|---|---|
+/// | `fapshi_requests_total` | counter | `endpoint`, `status` (code, or `error` for transport errors) |
+/// | `fapshi_request_duration_seconds` | histogram | `endpoint` |
+/// | `fapshi_retries_total` | counter | `operation` |
+/// | `fapshi_circuit_state` | gauge | none; 0 closed, 1 half-open, 2 open |
+/// | `fapshi_webhook_events_total` | counter | `status` |
+/// | `fapshi_payment_outcomes_total` | counter | `medium`, `status` |
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{api::balance::BalanceApi, client::FapshiClient, safety::Environment};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?
+///         .with_metrics();
+/// BalanceApi::get_service_balance(&client)?;
+/// // Serve this from your /metrics endpoint.
+/// print!("{}", client.metrics().unwrap().encode_prometheus());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    /// Creates an empty set of metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a request that got a response (`status` is `Some`) or failed in transport.
+    pub fn record_request(
+        &self,
+        method: &Method,
+        path: &str,
+        status: Option<u16>,
+        elapsed: Duration,
+    ) {
+        let endpoint = endpoint_label(method, path);
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+        let mut registry = self.registry();
+        *registry
+            .requests
+            .entry((endpoint.clone(), status))
+            .or_default() += 1;
+        registry
+            .latency
+            .entry(endpoint)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records a retried call, e.g. from your own retry loop or a retry layer.
+    pub fn record_retry(&self, operation: &str) {
+        *self
+            .registry()
+            .retries
+            .entry(operation.to_string())
+            .or_default() += 1;
+    }
+
+    /// Records the current state of the circuit breaker.
+    pub fn record_circuit_state(&self, state: CircuitState) {
+        self.registry().circuit_state = Some(state);
+    }
+
+    /// Records a webhook event received from Fapshi. Events with a final status also count as
+    /// a payment outcome.
+    pub fn record_webhook_event(&self, event: &TransactionStatus) {
+        *self
+            .registry()
+            .webhook_events
+            .entry(event.status.to_string())
+            .or_default() += 1;
+        if !matches!(event.status, Status::CREATED | Status::PENDING) {
+            self.record_payment_outcome(event);
+        }
+    }
+
+    /// Records the final status of a payment, by medium. Call it once per transaction, e.g.
+    /// when your status polling sees a final status.
+    pub fn record_payment_outcome(&self, transaction: &TransactionStatus) {
+        let medium = transaction
+            .medium
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        *self
+            .registry()
+            .payment_outcomes
+            .entry((medium, transaction.status.to_string()))
+            .or_default() += 1;
+    }
+
+    /// Returns the number of requests recorded for an endpoint name and status label.
+    pub fn request_count(&self, endpoint: &str, status: &str) -> u64 {
+        self.registry()
+            .requests
+            .get(&(endpoint.to_string(), status.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Encodes all metrics in the Prometheus text exposition format.
+    pub fn encode_prometheus(&self) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "fapshi_requests_total",
+            "counter",
+            "Requests sent to the Fapshi API.",
+        );
+        for ((endpoint, status), count) in &registry.requests {
+            let _ = writeln!(
+                out,
+                "fapshi_requests_total{{endpoint=\"{}\",status=\"{}\"}} {count}",
+                escape(endpoint),
+                escape(status)
+            );
+        }
+
+        header(
+            &mut out,
+            "fapshi_request_duration_seconds",
+            "histogram",
+            "Latency of requests to the Fapshi API.",
+        );
+        for (endpoint, histogram) in &registry.latency {
+            let endpoint = escape(endpoint);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "fapshi_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "fapshi_request_duration_seconds_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "fapshi_request_duration_seconds_sum{{endpoint=\"{endpoint}\"}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "fapshi_request_duration_seconds_count{{endpoint=\"{endpoint}\"}} {}",
+                histogram.count
+            );
+        }
+
+        header(
+            &mut out,
+            "fapshi_retries_total",
+            "counter",
+            "Retried Fapshi calls.",
+        );
+        for (operation, count) in &registry.retries {
+            let _ = writeln!(
+                out,
+                "fapshi_retries_total{{operation=\"{}\"}} {count}",
+                escape(operation)
+            );
+        }
+
+        if let Some(state) = registry.circuit_state {
+            header(
+                &mut out,
+                "fapshi_circuit_state",
+                "gauge",
+                "Circuit breaker state: 0 closed, 1 half-open, 2 open.",
+            );
+            let value = match state {
+                CircuitState::Closed => 0,
+                CircuitState::HalfOpen => 1,
+                CircuitState::Open => 2,
+            };
+            let _ = writeln!(out, "fapshi_circuit_state {value}");
+        }
+
+        header(
+            &mut out,
+            "fapshi_webhook_events_total",
+            "counter",
+            "Webhook events received from Fapshi.",
+        );
+        for (status, count) in &registry.webhook_events {
+            let _ = writeln!(
+                out,
+                "fapshi_webhook_events_total{{status=\"{}\"}} {count}",
+                escape(status)
+            );
+        }
+
+        header(
+            &mut out,
+            "fapshi_payment_outcomes_total",
+            "counter",
+            "Final payment statuses by medium.",
+        );
+        for ((medium, status), count) in &registry.payment_outcomes {
+            let _ = writeln!(
+                out,
+                "fapshi_payment_outcomes_total{{medium=\"{}\",status=\"{}\"}} {count}",
+                escape(medium),
+                escape(status)
+            );
+        }
+        out
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Labels a request with its catalogue name, or `other` for paths outside the catalogue.
+fn endpoint_label(method: &Method, path: &str) -> String {
+    endpoint::identify(method, path)
+        .map(|info| info.name.to_string())
+        .unwrap_or_else(|| "other".to_string())
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        api::balance::BalanceApi,
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        client::FapshiClient,
+        error::FapshiError,
+        models::ServiceBalance,
+        safety::Environment,
+    };
+
+    fn transaction(status: &str, medium: Option<&str>) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": "T1",
+            "status": status,
+            "medium": medium,
+            "serviceName": "Shop",
+            "amount": 500.0,
+            "email": "",
+            "redirectUrl": "",
+            "dateInitiated": "",
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn requests_are_labelled_by_endpoint_name() {
+        let metrics = Metrics::new();
+        let elapsed = Duration::from_millis(200);
+        metrics.record_request(&Method::GET, "payment-status/T1", Some(200), elapsed);
+        metrics.record_request(&Method::GET, "payment-status/T2", Some(200), elapsed);
+        metrics.record_request(
+            &Method::GET,
+            "payment-status/T3",
+            None,
+            Duration::from_secs(20),
+        );
+        metrics.record_request(&Method::GET, "unknown/path", Some(404), elapsed);
+        assert_eq!(metrics.request_count("payment-status", "200"), 2);
+        assert_eq!(metrics.request_count("payment-status", "error"), 1);
+        assert_eq!(metrics.request_count("other", "404"), 1);
+
+        let text = metrics.encode_prometheus();
+        assert!(!text.contains("T1"));
+        assert!(
+            text.contains("fapshi_requests_total{endpoint=\"payment-status\",status=\"200\"} 2\n")
+        );
+        assert!(text.contains(
+            "fapshi_request_duration_seconds_bucket{endpoint=\"payment-status\",le=\"0.1\"} 0\n"
+        ));
+        assert!(text.contains(
+            "fapshi_request_duration_seconds_bucket{endpoint=\"payment-status\",le=\"0.25\"} 2\n"
+        ));
+        assert!(text.contains(
+            "fapshi_request_duration_seconds_bucket{endpoint=\"payment-status\",le=\"+Inf\"} 3\n"
+        ));
+        assert!(
+            text.contains("fapshi_request_duration_seconds_count{endpoint=\"payment-status\"} 3\n")
+        );
+    }
+
+    #[test]
+    fn webhook_events_with_a_final_status_count_as_outcomes() {
+        let metrics = Metrics::new();
+        metrics.record_webhook_event(&transaction("PENDING", Some("mobile money")));
+        metrics.record_webhook_event(&transaction("SUCCESSFUL", Some("mobile money")));
+        metrics.record_payment_outcome(&transaction("FAILED", None));
+        metrics.record_retry("get_status \"poll\"");
+        metrics.record_circuit_state(CircuitState::Open);
+
+        let text = metrics.encode_prometheus();
+        assert!(text.contains("fapshi_webhook_events_total{status=\"PENDING\"} 1\n"));
+        assert!(text.contains(
+            "fapshi_payment_outcomes_total{medium=\"mobile money\",status=\"SUCCESSFUL\"} 1\n"
+        ));
+        assert!(!text.contains("medium=\"mobile money\",status=\"PENDING\""));
+        assert!(text
+            .contains("fapshi_payment_outcomes_total{medium=\"unknown\",status=\"FAILED\"} 1\n"));
+        assert!(text.contains("fapshi_retries_total{operation=\"get_status \\\"poll\\\"\"} 1\n"));
+        assert!(text.contains("# TYPE fapshi_circuit_state gauge\nfapshi_circuit_state 2\n"));
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
+        BalanceApi::get_service_balance(client)
+    }
+
+    #[cfg(feature = "async")]
+    fn balance(client: &FapshiClient) -> Result<ServiceBalance, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(BalanceApi::get_service_balance(client))
+    }
+
+    #[test]
+    fn client_counts_its_requests() {
+        let client = FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(vec![Interaction {
+                request: RecordedRequest {
+                    method: "GET".to_string(),
+                    path: "balance".to_string(),
+                    headers: BTreeMap::new(),
+                    body: None,
+                },
+                response: RecordedResponse {
+                    status: 200,
+                    headers: BTreeMap::new(),
+                    body: r#"{"service":"Shop","balance":1500,"currency":"XAF"}"#.to_string(),
+                },
+            }]))
+            .with_metrics();
+        balance(&client).unwrap();
+        assert_eq!(client.metrics().unwrap().request_count("balance", "200"), 1);
+    }
+}
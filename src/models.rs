@@ -1,7 +1,12 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::error::FapshiError;
+
 /// Request payload for creating a payment link.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PaymentRequest {
     /// The payment amount should be greater than 100.
     pub amount: f64,
@@ -43,6 +48,22 @@ pub struct PaymentResponse {
     pub date_initiated: String,
 }
 
+/// How long a payment link stays valid after it is created.
+pub const PAYMENT_LINK_VALIDITY: TimeDelta = TimeDelta::hours(24);
+
+impl PaymentResponse {
+    /// Returns `date_initiated` as a UTC timestamp, if it can be parsed.
+    pub fn initiated_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(&self.date_initiated)
+    }
+
+    /// Returns when the payment link stops accepting payments.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.initiated_at()
+            .map(|initiated| initiated + PAYMENT_LINK_VALIDITY)
+    }
+}
+
 /// Transaction status information.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionStatus {
@@ -78,6 +99,41 @@ pub struct TransactionStatus {
     #[serde(rename = "dateConfirmed")]
     pub date_confirmed: String,
 }
+impl TransactionStatus {
+    /// Returns `date_initiated` as a UTC timestamp, if it can be parsed.
+    pub fn initiated_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(&self.date_initiated)
+    }
+
+    /// Returns `date_confirmed` as a UTC timestamp, if the transaction was confirmed.
+    pub fn confirmed_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(&self.date_confirmed)
+    }
+
+    /// Returns when the payment link of this transaction stops accepting payments.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.initiated_at()
+            .map(|initiated| initiated + PAYMENT_LINK_VALIDITY)
+    }
+
+    /// Returns the Fapshi fee, i.e. `amount` minus `revenue`, if the revenue is known.
+    pub fn fee(&self) -> Option<f64> {
+        self.revenue.map(|revenue| self.amount - revenue)
+    }
+}
+
+/// Parses a date as returned by Fapshi (RFC 3339, or a naive UTC date-time).
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(date) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+        .map(|naive| naive.and_utc())
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
 pub enum Status {
     #[default]
@@ -88,6 +144,19 @@ pub enum Status {
     EXPIRED,
 }
 
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Status::CREATED => "CREATED",
+            Status::PENDING => "PENDING",
+            Status::SUCCESSFUL => "SUCCESSFUL",
+            Status::FAILED => "FAILED",
+            Status::EXPIRED => "EXPIRED",
+        };
+        f.write_str(status)
+    }
+}
+
 impl From<String> for Status {
     fn from(s: String) -> Self {
         match s.to_lowercase().as_str() {
@@ -153,6 +222,22 @@ pub struct DirectPaymentRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>, // Optional, renamed from description
 }
+
+impl DirectPaymentRequest {
+    /// Checks the request against Fapshi's documented constraints before it is sent.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` describing the first invalid field.
+    pub fn validate(&self) -> Result<(), FapshiError> {
+        validate_amount(f64::from(self.amount))?;
+        validate_phone(&self.phone)?;
+        if let Some(medium) = &self.medium {
+            validate_medium(medium)?;
+        }
+        validate_id("userId", self.user_id.as_deref())?;
+        validate_id("externalId", self.external_id.as_deref())
+    }
+}
 /// Response payload for a direct payment request.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DirectPaymentResponse {
@@ -166,24 +251,106 @@ pub struct DirectPaymentResponse {
     pub date_initiated: String,
 }
 
+/// The most transactions a single search returns. Fapshi returns 10 when `limit` is unset.
+pub const SEARCH_LIMIT_MAX: u32 = 100;
+
 /// Query parameters for searching transactions.
+///
+/// Unset fields are left out of the query, so `TransactionSearchQuery::default()` matches
+/// every transaction.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct TransactionSearchQuery {
     /// Transaction status (e.g., "created", "successful", "failed", "expired").
-    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
     /// Payment medium (e.g., "mobile money", "orange money").
-    pub medium: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
     /// Name of the user performing the payment (optional).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Start date (yyyy-mm-dd) for transactions.
-    pub start: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
     /// End date (yyyy-mm-dd) for transactions.
-    pub end: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
     /// Exact transaction amount.
-    pub amt: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amt: Option<f64>,
     /// Maximum number of transactions to return
-    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Sort order by date: "asc" or "desc".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+impl TransactionSearchQuery {
+    /// Encodes the set fields as a URL query string (without the leading `?`).
+    pub fn to_query_string(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(status) = &self.status {
+            query.append_pair("status", &status.to_string().to_lowercase());
+        }
+        let pairs = [
+            ("medium", self.medium.clone()),
+            ("name", self.name.clone()),
+            ("start", self.start.clone()),
+            ("end", self.end.clone()),
+            ("amt", self.amt.map(|amt| amt.to_string())),
+            ("limit", self.limit.map(|limit| limit.to_string())),
+            ("sort", self.sort.clone()),
+        ];
+        for (key, value) in pairs {
+            if let Some(value) = value {
+                query.append_pair(key, &value);
+            }
+        }
+        query.finish()
+    }
+
+    /// Splits the query into narrower ones that together match the same transactions, for
+    /// when a search returns a full page: halves the `start`..=`end` date range, or, for a
+    /// single day, searches each status separately.
+    ///
+    /// The queries are in the order their results should be written (by date, following
+    /// `sort`). Returns `None` when the query has no parseable date range or cannot be
+    /// narrowed further.
+    pub(crate) fn narrow(&self) -> Option<Vec<TransactionSearchQuery>> {
+        let parse =
+            |date: &Option<String>| NaiveDate::parse_from_str(date.as_deref()?, "%Y-%m-%d").ok();
+        let (start, end) = (parse(&self.start)?, parse(&self.end)?);
+        let ranged = |start: NaiveDate, end: NaiveDate| TransactionSearchQuery {
+            start: Some(start.format("%Y-%m-%d").to_string()),
+            end: Some(end.format("%Y-%m-%d").to_string()),
+            ..self.clone()
+        };
+        let mut narrowed = if start < end {
+            let middle = start + TimeDelta::days((end - start).num_days() / 2);
+            vec![ranged(start, middle), ranged(middle.succ_opt()?, end)]
+        } else if self.status.is_none() {
+            [
+                Status::CREATED,
+                Status::PENDING,
+                Status::SUCCESSFUL,
+                Status::FAILED,
+                Status::EXPIRED,
+            ]
+            .into_iter()
+            .map(|status| TransactionSearchQuery {
+                status: Some(status),
+                ..self.clone()
+            })
+            .collect()
+        } else {
+            return None;
+        };
+        if self.sort.as_deref() == Some("desc") {
+            narrowed.reverse();
+        }
+        Some(narrowed)
+    }
 }
 
 /// Response payload for a transaction search or user ID query.
@@ -203,18 +370,96 @@ pub struct ServiceBalance {
     pub currency: String,
 }
 
+/// Request payload for sending money to a mobile money or Orange money account.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Payouts {
     /// amount to be sent to the user.
     pub amount: f64,
     /// phone number to which the amount will be sent e.g., 67XXXXXXX, 69XXXXXXX, 65XXXXXXX.
+    #[serde(rename = "phone")]
     pub phone_number: String,
     /// medium can either be “mobile money” for MTN numbers or “orange money” for Orange numbers.
     pub medium: String,
     /// name of the user receiving the payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// email of the user receiving the payment
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    /// The user ID in the caller's system (1-100 characters, alphanumeric with -_).
+    #[serde(rename = "userId", skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// The external ID for reconciliation (1-100 characters, alphanumeric with -_).
+    #[serde(rename = "externalId", skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
     /// contains a message describing the reason for the payout.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
 }
+
+impl Payouts {
+    /// Checks the payout against Fapshi's documented constraints before it is sent.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` describing the first invalid field.
+    pub fn validate(&self) -> Result<(), FapshiError> {
+        validate_amount(self.amount)?;
+        validate_phone(&self.phone_number)?;
+        validate_medium(&self.medium)?;
+        validate_id("userId", self.user_id.as_deref())?;
+        validate_id("externalId", self.external_id.as_deref())
+    }
+}
+
+/// Response payload for a payout request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PayoutResponse {
+    /// The unique transaction ID.
+    #[serde(rename = "transId")]
+    pub transaction_id: String,
+    /// The status of the payout request.
+    pub message: String,
+    /// date initiated
+    #[serde(rename = "dateInitiated")]
+    pub date_initiated: String,
+}
+
+fn validate_amount(amount: f64) -> Result<(), FapshiError> {
+    if amount < 100.0 || amount.fract() != 0.0 {
+        return Err(FapshiError::InvalidRequest(format!(
+            "amount {amount} must be a whole number of at least 100"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_phone(phone: &str) -> Result<(), FapshiError> {
+    if phone.len() != 9 || !phone.starts_with('6') || !phone.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(FapshiError::InvalidRequest(format!(
+            "phone {phone:?} must be a 9-digit Cameroonian number like 67XXXXXXX"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_medium(medium: &str) -> Result<(), FapshiError> {
+    if medium != "mobile money" && medium != "orange money" {
+        return Err(FapshiError::InvalidRequest(format!(
+            "medium {medium:?} must be \"mobile money\" or \"orange money\""
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_id(field: &str, id: Option<&str>) -> Result<(), FapshiError> {
+    let Some(id) = id else { return Ok(()) };
+    let valid_chars = id
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if id.is_empty() || id.len() > 100 || !valid_chars {
+        return Err(FapshiError::InvalidRequest(format!(
+            "{field} {id:?} must be 1-100 characters of letters, digits, '-' or '_'"
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,526 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+#[cfg(not(feature = "async"))]
+use reqwest::blocking::Client;
+#[cfg(feature = "async")]
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::balance::BalanceApi, client::FapshiClient, error::FapshiError, models::ServiceBalance,
+};
+
+/// Timeout for alerts POSTed to the alert webhook.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration for a [`BalanceMonitor`].
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    /// Time between two balance checks in [`BalanceMonitor::run`].
+    pub interval: Duration,
+    /// Number of samples kept to compute the burn rate.
+    pub history_len: usize,
+    /// Balances below which a [`AlertKind::BelowThreshold`] alert is raised.
+    pub thresholds: Vec<f64>,
+    /// Raise a [`AlertKind::ProjectedDepletion`] alert when the balance is projected to run
+    /// out within this window.
+    pub depletion_window: Option<Duration>,
+    /// URL that alerts are POSTed to as JSON, without Fapshi credentials. Alerts that
+    /// cannot be delivered are kept and sent again, in order, on the next check.
+    pub webhook_url: Option<String>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig {
+            interval: Duration::from_secs(300),
+            history_len: 12,
+            thresholds: Vec::new(),
+            depletion_window: None,
+            webhook_url: None,
+        }
+    }
+}
+
+/// A balance observed by a [`BalanceMonitor`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BalanceSample {
+    /// When the balance was read.
+    pub at: DateTime<Utc>,
+    /// The balance at that time.
+    pub balance: f64,
+}
+
+/// Why an [`Alert`] was raised.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The balance dropped below a configured threshold.
+    BelowThreshold {
+        /// The threshold that was crossed.
+        threshold: f64,
+    },
+    /// At the current burn rate, the balance runs out within the configured window.
+    ProjectedDepletion {
+        /// Projected seconds until the balance reaches zero.
+        seconds_left: u64,
+    },
+}
+
+/// An alert raised by a [`BalanceMonitor`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Why the alert was raised.
+    #[serde(flatten)]
+    pub kind: AlertKind,
+    /// The balance that triggered the alert.
+    pub balance: f64,
+    /// The currency of the balance.
+    pub currency: String,
+    /// Spending per hour over the monitor's history, if known.
+    pub burn_rate_per_hour: Option<f64>,
+    /// When the balance was read.
+    pub at: DateTime<Utc>,
+}
+
+type AlertCallback = Box<dyn Fn(&Alert) + Send + Sync>;
+type ErrorCallback = Box<dyn Fn(&FapshiError) + Send + Sync>;
+
+/// Polls the service balance and raises alerts when it runs low.
+///
+/// Each threshold alerts once when the balance crosses below it, and again only after the
+/// balance has gone back above it. The depletion alert behaves the same way with respect to
+/// the depletion window.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     monitor::{BalanceMonitor, MonitorConfig},
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let mut monitor = BalanceMonitor::new(MonitorConfig {
+///     thresholds: vec![50_000.0, 10_000.0],
+///     depletion_window: Some(Duration::from_secs(6 * 3600)),
+///     ..Default::default()
+/// })
+/// .on_alert(|alert| eprintln!("Balance alert: {:?}", alert));
+/// monitor.run(&client);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BalanceMonitor {
+    config: MonitorConfig,
+    history: VecDeque<BalanceSample>,
+    alert_callbacks: Vec<AlertCallback>,
+    error_callbacks: Vec<ErrorCallback>,
+    depletion_alerted: bool,
+    undelivered: VecDeque<Alert>,
+}
+
+impl BalanceMonitor {
+    /// Creates a monitor with an empty history.
+    pub fn new(config: MonitorConfig) -> Self {
+        BalanceMonitor {
+            config,
+            history: VecDeque::new(),
+            alert_callbacks: Vec::new(),
+            error_callbacks: Vec::new(),
+            depletion_alerted: false,
+            undelivered: VecDeque::new(),
+        }
+    }
+
+    /// Registers a callback invoked with every alert.
+    pub fn on_alert(mut self, callback: impl Fn(&Alert) + Send + Sync + 'static) -> Self {
+        self.alert_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Registers a callback invoked when a check fails in [`BalanceMonitor::run`].
+    pub fn on_error(mut self, callback: impl Fn(&FapshiError) + Send + Sync + 'static) -> Self {
+        self.error_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Returns the alerts not yet delivered to the webhook, oldest first.
+    pub fn undelivered(&self) -> &VecDeque<Alert> {
+        &self.undelivered
+    }
+
+    /// Returns the monitor's configuration.
+    pub fn config(&self) -> &MonitorConfig {
+        &self.config
+    }
+
+    /// Returns the recorded samples, oldest first.
+    pub fn history(&self) -> &VecDeque<BalanceSample> {
+        &self.history
+    }
+
+    /// Returns the balance spent per hour between the oldest and newest samples.
+    ///
+    /// The value is negative when the balance grew. `None` until two samples taken at
+    /// different times have been recorded.
+    pub fn burn_rate(&self) -> Option<f64> {
+        let (first, last) = (self.history.front()?, self.history.back()?);
+        let hours = (last.at - first.at).num_milliseconds() as f64 / 3_600_000.0;
+        (hours > 0.0).then(|| (first.balance - last.balance) / hours)
+    }
+
+    /// Returns how long the latest balance lasts at the current burn rate.
+    ///
+    /// `None` if the burn rate is unknown or the balance is not decreasing.
+    pub fn projected_depletion(&self) -> Option<Duration> {
+        let rate = self.burn_rate().filter(|rate| *rate > 0.0)?;
+        let balance = self.history.back()?.balance.max(0.0);
+        Some(Duration::from_secs_f64(balance / rate * 3600.0))
+    }
+
+    /// Records a balance read at `at` and returns the alerts it raises.
+    ///
+    /// Callbacks and the webhook are not invoked; [`BalanceMonitor::check`] does that.
+    pub fn record(&mut self, balance: &ServiceBalance, at: DateTime<Utc>) -> Vec<Alert> {
+        let previous = self.history.back().map(|sample| sample.balance);
+        self.history.push_back(BalanceSample {
+            at,
+            balance: balance.balance,
+        });
+        while self.history.len() > self.config.history_len.max(2) {
+            self.history.pop_front();
+        }
+
+        let burn_rate = self.burn_rate();
+        let alert = |kind| Alert {
+            kind,
+            balance: balance.balance,
+            currency: balance.currency.clone(),
+            burn_rate_per_hour: burn_rate,
+            at,
+        };
+
+        let mut alerts: Vec<Alert> = self
+            .config
+            .thresholds
+            .iter()
+            .filter(|threshold| {
+                balance.balance < **threshold && previous.is_none_or(|p| p >= **threshold)
+            })
+            .map(|threshold| {
+                alert(AlertKind::BelowThreshold {
+                    threshold: *threshold,
+                })
+            })
+            .collect();
+
+        let left = self.projected_depletion();
+        match (left, self.config.depletion_window) {
+            (Some(left), Some(window)) if left <= window => {
+                if !self.depletion_alerted {
+                    self.depletion_alerted = true;
+                    alerts.push(alert(AlertKind::ProjectedDepletion {
+                        seconds_left: left.as_secs(),
+                    }));
+                }
+            }
+            _ => self.depletion_alerted = false,
+        }
+        alerts
+    }
+
+    /// Reads the balance once, records it and dispatches any alerts, synchronously.
+    ///
+    /// Callbacks run first. The alerts are then POSTed to the webhook, after any left
+    /// undelivered by earlier checks; an alert that cannot be sent stays queued, with the
+    /// ones after it, until a later check delivers it.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    ///
+    /// # Returns
+    /// A `Result` containing the raised alerts, or a `FapshiError` if the balance could not
+    /// be read or an alert could not be delivered to the webhook.
+    #[cfg(not(feature = "async"))]
+    pub fn check(&mut self, client: &FapshiClient) -> Result<Vec<Alert>, FapshiError> {
+        let balance = BalanceApi::get_service_balance(client)?;
+        let alerts = self.record(&balance, Utc::now());
+        self.notify(&alerts);
+        let Some(url) = &self.config.webhook_url else {
+            return Ok(alerts);
+        };
+        self.undelivered.extend(alerts.iter().cloned());
+        if !self.undelivered.is_empty() {
+            let http = Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+            while let Some(alert) = self.undelivered.front() {
+                http.post(url).json(alert).send()?.error_for_status()?;
+                self.undelivered.pop_front();
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// Reads the balance once, records it and dispatches any alerts, asynchronously.
+    ///
+    /// Callbacks run first. The alerts are then POSTed to the webhook, after any left
+    /// undelivered by earlier checks; an alert that cannot be sent stays queued, with the
+    /// ones after it, until a later check delivers it.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    ///
+    /// # Returns
+    /// A `Result` containing the raised alerts, or a `FapshiError` if the balance could not
+    /// be read or an alert could not be delivered to the webhook.
+    #[cfg(feature = "async")]
+    pub async fn check(&mut self, client: &FapshiClient) -> Result<Vec<Alert>, FapshiError> {
+        let balance = BalanceApi::get_service_balance(client).await?;
+        let alerts = self.record(&balance, Utc::now());
+        self.notify(&alerts);
+        let Some(url) = &self.config.webhook_url else {
+            return Ok(alerts);
+        };
+        self.undelivered.extend(alerts.iter().cloned());
+        if !self.undelivered.is_empty() {
+            let http = Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+            while let Some(alert) = self.undelivered.front() {
+                http.post(url)
+                    .json(alert)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                self.undelivered.pop_front();
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// Checks the balance every `interval`, forever, blocking the current thread.
+    ///
+    /// Failed checks are passed to the [`BalanceMonitor::on_error`] callbacks and polling
+    /// continues. Run the monitor on its own thread.
+    #[cfg(not(feature = "async"))]
+    pub fn run(&mut self, client: &FapshiClient) {
+        loop {
+            if let Err(err) = self.check(client) {
+                self.report_error(&err);
+            }
+            std::thread::sleep(self.config.interval);
+        }
+    }
+
+    /// Checks the balance every `interval`, forever.
+    ///
+    /// Failed checks are passed to the [`BalanceMonitor::on_error`] callbacks and polling
+    /// continues. Spawn the returned future on its own task.
+    #[cfg(feature = "async")]
+    pub async fn run(&mut self, client: &FapshiClient) {
+        loop {
+            if let Err(err) = self.check(client).await {
+                self.report_error(&err);
+            }
+            tokio::time::sleep(self.config.interval).await;
+        }
+    }
+
+    fn notify(&self, alerts: &[Alert]) {
+        for alert in alerts {
+            for callback in &self.alert_callbacks {
+                callback(alert);
+            }
+        }
+    }
+
+    fn report_error(&self, err: &FapshiError) {
+        for callback in &self.error_callbacks {
+            callback(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    fn balance(amount: f64) -> ServiceBalance {
+        ServiceBalance {
+            balance: amount,
+            currency: "XAF".to_string(),
+        }
+    }
+
+    fn hour(h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 31, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn thresholds_alert_once_per_crossing() {
+        let mut monitor = BalanceMonitor::new(MonitorConfig {
+            thresholds: vec![10_000.0, 5_000.0],
+            ..Default::default()
+        });
+        assert!(monitor.record(&balance(12_000.0), hour(0)).is_empty());
+        let alerts = monitor.record(&balance(8_000.0), hour(1));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].kind,
+            AlertKind::BelowThreshold {
+                threshold: 10_000.0
+            }
+        );
+        assert_eq!(alerts[0].currency, "XAF");
+        assert!(monitor.record(&balance(7_000.0), hour(2)).is_empty());
+        assert!(monitor.record(&balance(11_000.0), hour(3)).is_empty());
+        let alerts = monitor.record(&balance(4_000.0), hour(4));
+        assert_eq!(alerts.len(), 2);
+    }
+
+    #[test]
+    fn burn_rate_and_depletion_follow_the_history() {
+        let mut monitor = BalanceMonitor::new(MonitorConfig {
+            history_len: 3,
+            depletion_window: Some(Duration::from_secs(3 * 3600)),
+            ..Default::default()
+        });
+        assert!(monitor.record(&balance(10_000.0), hour(0)).is_empty());
+        assert_eq!(monitor.burn_rate(), None);
+        assert!(monitor.record(&balance(9_000.0), hour(1)).is_empty());
+        assert_eq!(monitor.burn_rate(), Some(1_000.0));
+        assert_eq!(
+            monitor.projected_depletion(),
+            Some(Duration::from_secs(9 * 3600))
+        );
+
+        let alerts = monitor.record(&balance(2_000.0), hour(2));
+        assert_eq!(
+            alerts[0].kind,
+            AlertKind::ProjectedDepletion { seconds_left: 1800 }
+        );
+        assert_eq!(alerts[0].burn_rate_per_hour, Some(4_000.0));
+        assert!(monitor.record(&balance(1_000.0), hour(3)).is_empty());
+        assert_eq!(monitor.history().len(), 3);
+    }
+
+    fn balance_client(balances: &[f64]) -> FapshiClient {
+        let interactions = balances
+            .iter()
+            .map(|balance| Interaction {
+                request: RecordedRequest {
+                    method: "GET".to_string(),
+                    path: "balance".to_string(),
+                    headers: BTreeMap::new(),
+                    body: None,
+                },
+                response: RecordedResponse {
+                    status: 200,
+                    headers: BTreeMap::new(),
+                    body: format!(r#"{{"balance":{balance},"currency":"XAF"}}"#),
+                },
+            })
+            .collect();
+        FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(interactions))
+    }
+
+    /// Answers one webhook request per status in `statuses` and returns the posted alerts.
+    fn webhook(statuses: Vec<u16>) -> (String, JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alerts", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            statuses
+                .into_iter()
+                .map(|status| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(&stream);
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == "\r\n" {
+                            break;
+                        }
+                        if let Some(value) =
+                            line.to_ascii_lowercase().strip_prefix("content-length:")
+                        {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    write!(
+                        stream,
+                        "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    )
+                    .unwrap();
+                    serde_json::from_slice(&body).unwrap()
+                })
+                .collect()
+        });
+        (url, server)
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn check(
+        monitor: &mut BalanceMonitor,
+        client: &FapshiClient,
+    ) -> Result<Vec<Alert>, FapshiError> {
+        monitor.check(client)
+    }
+
+    #[cfg(feature = "async")]
+    fn check(
+        monitor: &mut BalanceMonitor,
+        client: &FapshiClient,
+    ) -> Result<Vec<Alert>, FapshiError> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(monitor.check(client))
+    }
+
+    #[test]
+    fn undelivered_alerts_are_sent_again_on_the_next_check() {
+        let (url, server) = webhook(vec![503, 200, 200]);
+        let mut monitor = BalanceMonitor::new(MonitorConfig {
+            thresholds: vec![10_000.0, 5_000.0],
+            webhook_url: Some(url),
+            ..Default::default()
+        });
+        let client = balance_client(&[8_000.0, 4_000.0]);
+
+        assert!(matches!(
+            check(&mut monitor, &client),
+            Err(FapshiError::HttpError(_))
+        ));
+        assert_eq!(monitor.undelivered().len(), 1);
+
+        let alerts = check(&mut monitor, &client).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert!(monitor.undelivered().is_empty());
+        let thresholds: Vec<_> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|alert| alert["threshold"].as_f64().unwrap())
+            .collect();
+        assert_eq!(thresholds, [10_000.0, 10_000.0, 5_000.0]);
+    }
+}
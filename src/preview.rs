@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cassette::REDACTED, safety::Environment};
+
+/// Request headers whose values never appear in a preview.
+const REDACTED_HEADERS: &[&str] = &["apikey", "authorization"];
+
+/// The exact HTTP request the client would send, with the API key redacted.
+///
+/// Returned by [`FapshiClient::preview`](crate::client::FapshiClient::preview) and carried by
+/// `FapshiError::DryRun` for calls made in dry-run mode.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{client::FapshiClient, endpoint::PaymentStatus, safety::Environment};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let preview = client.preview(&PaymentStatus("trans123"))?;
+/// println!("{}", preview.to_curl());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RequestPreview {
+    /// The environment the request goes to.
+    pub environment: Environment,
+    /// The HTTP method.
+    pub method: String,
+    /// The full URL.
+    pub url: String,
+    /// The request headers, with the API key replaced by `"[REDACTED]"`.
+    pub headers: BTreeMap<String, String>,
+    /// The JSON body, if any.
+    pub body: Option<String>,
+}
+
+impl RequestPreview {
+    /// Builds a preview, redacting the secret headers.
+    pub(crate) fn new<'a>(
+        environment: Environment,
+        method: &str,
+        url: &str,
+        headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+        body: Option<&str>,
+    ) -> Self {
+        RequestPreview {
+            environment,
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| {
+                    let name = name.to_ascii_lowercase();
+                    let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                        REDACTED.to_string()
+                    } else {
+                        value.to_string()
+                    };
+                    (name, value)
+                })
+                .collect(),
+            body: body.map(str::to_string),
+        }
+    }
+
+    /// Renders the request as a `curl` command that can be shared safely.
+    ///
+    /// Replace `[REDACTED]` with the API key to run it.
+    pub fn to_curl(&self) -> String {
+        let mut command = format!("curl -X {} {}", self.method, shell_quote(&self.url));
+        for (name, value) in &self.headers {
+            command.push_str(" \\\n  -H ");
+            command.push_str(&shell_quote(&format!("{name}: {value}")));
+        }
+        if let Some(body) = &self.body {
+            command.push_str(" \\\n  --data ");
+            command.push_str(&shell_quote(body));
+        }
+        command
+    }
+}
+
+impl fmt::Display for RequestPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({})", self.method, self.url, self.environment)?;
+        if let Some(body) = &self.body {
+            write!(f, " {body}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes a value for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
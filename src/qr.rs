@@ -0,0 +1,469 @@
+use std::fmt::Write as _;
+use std::io;
+
+use qrcode::{Color, QrCode};
+
+use crate::{error::FapshiError, models::PaymentResponse};
+
+/// Width, in modules, of the blank margin the QR specification asks for around the code.
+const QUIET_ZONE: usize = 4;
+
+/// Width of a PNG caption character in font pixels: a 5-pixel glyph and a gap.
+const GLYPH_WIDTH: usize = 6;
+
+/// Height of a PNG caption line in font pixels: a 7-pixel glyph and a gap.
+const LINE_HEIGHT: usize = 9;
+
+/// QR error-correction level: higher levels survive more damage but make denser codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EcLevel {
+    /// Recovers about 7% of the code.
+    Low,
+    /// Recovers about 15% of the code.
+    #[default]
+    Medium,
+    /// Recovers about 25% of the code.
+    Quartile,
+    /// Recovers about 30% of the code; best for printed codes that may get dirty or torn.
+    High,
+}
+
+impl From<EcLevel> for qrcode::EcLevel {
+    fn from(level: EcLevel) -> Self {
+        match level {
+            EcLevel::Low => qrcode::EcLevel::L,
+            EcLevel::Medium => qrcode::EcLevel::M,
+            EcLevel::Quartile => qrcode::EcLevel::Q,
+            EcLevel::High => qrcode::EcLevel::H,
+        }
+    }
+}
+
+/// Options for rendering a [`PaymentQr`].
+#[derive(Debug, Clone)]
+pub struct QrOptions {
+    /// Size of one QR module in pixels (PNG) or user units (SVG). Ignored by terminal output.
+    pub module_size: u32,
+    /// Whether to draw the blank margin around the code.
+    pub quiet_zone: bool,
+    /// The error-correction level.
+    pub ec_level: EcLevel,
+    /// Text shown under the code, e.g. built with [`payment_caption`].
+    ///
+    /// PNG output draws it with a built-in 5x7 pixel font, wrapped to the width of the code;
+    /// accented Latin letters lose their accents and other characters outside printable
+    /// ASCII are drawn as `?`. SVG and terminal output use the text as given.
+    pub caption: Option<String>,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        QrOptions {
+            module_size: 8,
+            quiet_zone: true,
+            ec_level: EcLevel::default(),
+            caption: None,
+        }
+    }
+}
+
+/// Builds a caption showing the amount in XAF and, if given, the order reference.
+pub fn payment_caption(amount: f64, reference: Option<&str>) -> String {
+    let amount = if amount.fract() == 0.0 {
+        format!("{amount:.0} XAF")
+    } else {
+        format!("{amount:.2} XAF")
+    };
+    match reference {
+        Some(reference) => format!("{amount} - {reference}"),
+        None => amount,
+    }
+}
+
+/// A QR code encoding a payment link.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     api::payment::PaymentApi,
+///     client::FapshiClient,
+///     models::PaymentRequest,
+///     qr::{payment_caption, EcLevel, PaymentQr, QrOptions},
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let request = PaymentRequest {
+///     amount: 5000.0,
+///     email: None,
+///     redirect_url: None,
+///     user_id: None,
+///     external_id: Some("order-42".to_string()),
+///     message: "Order 42".to_string(),
+///     card_only: None,
+/// };
+/// let response = PaymentApi::create_payment(&client, &request)?;
+/// let qr = PaymentQr::from_response(
+///     &response,
+///     QrOptions {
+///         ec_level: EcLevel::High,
+///         caption: Some(payment_caption(request.amount, request.external_id.as_deref())),
+///         ..Default::default()
+///     },
+/// )?;
+/// std::fs::write("order-42.svg", qr.to_svg())?;
+/// print!("{}", qr.to_terminal());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PaymentQr {
+    modules: Vec<bool>,
+    width: usize,
+    options: QrOptions,
+}
+
+impl PaymentQr {
+    /// Encodes `link` as a QR code.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the link is too long to encode.
+    pub fn new(link: &str, options: QrOptions) -> Result<Self, FapshiError> {
+        let code = QrCode::with_error_correction_level(link, options.ec_level.into())
+            .map_err(|err| FapshiError::InvalidRequest(format!("cannot encode QR code: {err}")))?;
+        Ok(PaymentQr {
+            width: code.width(),
+            modules: code
+                .into_colors()
+                .into_iter()
+                .map(|color| color == Color::Dark)
+                .collect(),
+            options,
+        })
+    }
+
+    /// Encodes the `payment_link` of a created payment.
+    pub fn from_response(
+        response: &PaymentResponse,
+        options: QrOptions,
+    ) -> Result<Self, FapshiError> {
+        Self::new(&response.payment_link, options)
+    }
+
+    /// Returns the number of modules on each side, without the quiet zone.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Renders the code as a grayscale PNG image, with the caption drawn underneath.
+    pub fn to_png(&self) -> Result<Vec<u8>, FapshiError> {
+        let mut bytes = Vec::new();
+        self.write_png(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Writes the code as a grayscale PNG image to `writer`, with the caption drawn
+    /// underneath.
+    pub fn write_png(&self, writer: impl io::Write) -> Result<(), FapshiError> {
+        let scale = self.options.module_size.max(1) as usize;
+        let side = self.side() * scale;
+        let caption = self
+            .options
+            .caption
+            .as_deref()
+            .map(|caption| caption_rows(caption, side, scale))
+            .unwrap_or_default();
+        let height = side + caption.len();
+        let mut encoder = png::Encoder::new(writer, side as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut pixels = Vec::with_capacity(side * height);
+        for y in 0..side {
+            for x in 0..side {
+                pixels.push(if self.is_dark(x / scale, y / scale) {
+                    0
+                } else {
+                    255
+                });
+            }
+        }
+        for row in &caption {
+            pixels.extend(row.iter().map(|&dark| if dark { 0 } else { 255 }));
+        }
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&pixels).map_err(png_error)?;
+        writer.finish().map_err(png_error)
+    }
+
+    /// Renders the code as a standalone SVG document, with the caption underneath.
+    pub fn to_svg(&self) -> String {
+        let unit = self.options.module_size.max(1) as usize;
+        let side = self.side();
+        let caption_height = if self.options.caption.is_some() { 4 } else { 0 };
+        let (width, height) = (side * unit, (side + caption_height) * unit);
+
+        let mut path = String::new();
+        for y in 0..side {
+            for x in 0..side {
+                if self.is_dark(x, y) {
+                    let _ = write!(path, "M{x} {y}h1v1h-1z");
+                }
+            }
+        }
+
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<?xml version="1.0" encoding="UTF-8"?><svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{width}" height="{height}" viewBox="0 0 {side} {}" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="white"/><path fill="black" d="{path}"/>"#,
+            side + caption_height
+        );
+        if let Some(caption) = &self.options.caption {
+            let _ = write!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="2" text-anchor="middle" fill="black">{}</text>"#,
+                side as f64 / 2.0,
+                side as f64 + 2.0,
+                escape_xml(caption)
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Renders the code with ANSI background colours for display in a terminal.
+    ///
+    /// Each module is two characters wide so the code looks square. The caption is printed
+    /// on the line after the code.
+    pub fn to_terminal(&self) -> String {
+        let side = self.side();
+        let mut out = String::new();
+        for y in 0..side {
+            for x in 0..side {
+                out.push_str(if self.is_dark(x, y) {
+                    "\x1b[40m  "
+                } else {
+                    "\x1b[47m  "
+                });
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        if let Some(caption) = &self.options.caption {
+            out.push_str(caption);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Side length in modules, including the quiet zone when enabled.
+    fn side(&self) -> usize {
+        self.width + 2 * self.margin()
+    }
+
+    fn margin(&self) -> usize {
+        if self.options.quiet_zone {
+            QUIET_ZONE
+        } else {
+            0
+        }
+    }
+
+    /// Whether the module at `(x, y)`, in coordinates including the quiet zone, is dark.
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        let margin = self.margin();
+        let (x, y) = (x.wrapping_sub(margin), y.wrapping_sub(margin));
+        x < self.width && y < self.width && self.modules[y * self.width + x]
+    }
+}
+
+fn png_error(err: png::EncodingError) -> FapshiError {
+    match err {
+        png::EncodingError::IoError(err) => FapshiError::IoError(err),
+        other => FapshiError::IoError(io::Error::other(other)),
+    }
+}
+
+/// Draws `caption` in the built-in font, centred and wrapped to `width` pixels, with each
+/// font pixel a quarter of a module. Returns the pixel rows, top to bottom; `true` is dark.
+fn caption_rows(caption: &str, width: usize, module_size: usize) -> Vec<Vec<bool>> {
+    let pixel = (module_size / 4).max(1);
+    let lines = wrap(caption, (width / (GLYPH_WIDTH * pixel)).max(1));
+    let mut rows = vec![vec![false; width]; (2 + lines.len() * LINE_HEIGHT) * pixel];
+    for (line_index, line) in lines.iter().enumerate() {
+        let left = width.saturating_sub(line.len() * GLYPH_WIDTH * pixel) / 2;
+        let top = (2 + line_index * LINE_HEIGHT) * pixel;
+        for (char_index, &c) in line.iter().enumerate() {
+            let glyph = FONT[c as usize - ' ' as usize];
+            for (column, bits) in glyph.iter().enumerate() {
+                for bit in (0..7).filter(|bit| bits >> bit & 1 == 1) {
+                    let x = left + (char_index * GLYPH_WIDTH + column) * pixel;
+                    let y = top + bit * pixel;
+                    for row in &mut rows[y..y + pixel] {
+                        for dark in row.iter_mut().skip(x).take(pixel) {
+                            *dark = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Splits `text` into lines of at most `columns` characters the font can draw, breaking at
+/// spaces where possible.
+fn wrap(text: &str, columns: usize) -> Vec<Vec<char>> {
+    let mut lines: Vec<Vec<char>> = Vec::new();
+    let mut line = Vec::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().map(drawable).collect();
+        if !line.is_empty() && line.len() + 1 + word.len() <= columns {
+            line.push(' ');
+            line.append(&mut word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > columns {
+            lines.push(word.drain(..columns).collect());
+        }
+        line = word;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Maps a character to one in the built-in font: accented Latin letters lose their accent
+/// and anything else outside printable ASCII becomes `?`.
+fn drawable(c: char) -> char {
+    match c {
+        ' '..='~' => c,
+        'à' | 'á' | 'â' | 'ä' => 'a',
+        'À' | 'Á' | 'Â' | 'Ä' => 'A',
+        'ç' => 'c',
+        'Ç' => 'C',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'È' | 'É' | 'Ê' | 'Ë' => 'E',
+        'î' | 'ï' => 'i',
+        'Î' | 'Ï' => 'I',
+        'ô' | 'ö' => 'o',
+        'Ô' | 'Ö' => 'O',
+        'ù' | 'û' | 'ü' => 'u',
+        'Ù' | 'Û' | 'Ü' => 'U',
+        'ÿ' => 'y',
+        '–' | '—' => '-',
+        _ => '?',
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A 5x7 pixel font for printable ASCII, from `' '` to `'~'`. Each glyph is five columns,
+/// left to right, with the top row in the least significant bit.
+#[rustfmt::skip]
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // "'"
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\\\\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
@@ -0,0 +1,831 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::transaction::TransactionApi,
+    client::FapshiClient,
+    error::FapshiError,
+    export::csv_row,
+    models::{Status, TransactionSearchQuery, TransactionStatus},
+};
+
+/// An order or payment as recorded in your own ledger.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    /// The external ID sent to Fapshi with the payment (e.g., your order ID).
+    pub external_id: String,
+    /// The amount you expect to have been paid.
+    pub amount: f64,
+    /// The status you recorded, if any. Entries without a status are not status-checked.
+    pub status: Option<Status>,
+    /// The Fapshi transaction ID you recorded, if any. Takes precedence over the external ID
+    /// when matching.
+    pub transaction_id: Option<String>,
+}
+
+/// A source of ledger entries to reconcile.
+///
+/// Implement this trait to read entries straight from your order database.
+pub trait Ledger {
+    /// Returns every entry in the period being reconciled.
+    fn entries(&self) -> Result<Vec<LedgerEntry>, FapshiError>;
+}
+
+impl Ledger for Vec<LedgerEntry> {
+    fn entries(&self) -> Result<Vec<LedgerEntry>, FapshiError> {
+        Ok(self.clone())
+    }
+}
+
+impl Ledger for [LedgerEntry] {
+    fn entries(&self) -> Result<Vec<LedgerEntry>, FapshiError> {
+        Ok(self.to_vec())
+    }
+}
+
+/// A ledger imported from CSV.
+///
+/// The first row is a header. The `external_id` and `amount` columns are required and every
+/// row must have an external ID; `status` and `transaction_id` (or `transId`) are optional.
+/// Statuses must be Fapshi statuses (any case). Other columns are ignored.
+///
+/// Amounts use `.` as the decimal separator unless another one is given, and must not
+/// contain digit grouping: `"1,000"` is rejected rather than read as one thousand or one.
+#[derive(Debug, Clone)]
+pub struct CsvLedger {
+    entries: Vec<LedgerEntry>,
+}
+
+impl CsvLedger {
+    /// Parses a ledger from CSV read from `reader`, with `.` as the decimal separator.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if a required column is missing, a row has more
+    /// fields than the header, an external ID is empty, a status is not a Fapshi status, or
+    /// an amount is not a number with at most one decimal separator and no other separator.
+    pub fn from_reader(reader: impl Read) -> Result<Self, FapshiError> {
+        Self::from_reader_with_decimal_separator(reader, '.')
+    }
+
+    /// Parses a ledger from CSV read from `reader`, with amounts using `decimal_separator`
+    /// (e.g., `','` for French locales).
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if a required column is missing, a row has more
+    /// fields than the header, an external ID is empty, a status is not a Fapshi status, or
+    /// an amount is not a number with at most one decimal separator and no other separator.
+    pub fn from_reader_with_decimal_separator(
+        mut reader: impl Read,
+        decimal_separator: char,
+    ) -> Result<Self, FapshiError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Self::parse(text.trim_start_matches('\u{feff}'), decimal_separator)
+    }
+
+    /// Parses a ledger from the CSV file at `path`, with `.` as the decimal separator.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, FapshiError> {
+        Self::from_path_with_decimal_separator(path, '.')
+    }
+
+    /// Parses a ledger from the CSV file at `path`, with amounts using `decimal_separator`.
+    pub fn from_path_with_decimal_separator(
+        path: impl AsRef<Path>,
+        decimal_separator: char,
+    ) -> Result<Self, FapshiError> {
+        Self::from_reader_with_decimal_separator(std::fs::File::open(path)?, decimal_separator)
+    }
+
+    fn parse(text: &str, decimal_separator: char) -> Result<Self, FapshiError> {
+        let mut records = parse_csv(text).into_iter();
+        let header = records.next().unwrap_or_default();
+        let column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|h| names.iter().any(|name| h.trim().eq_ignore_ascii_case(name)))
+        };
+        let missing =
+            |name: &str| FapshiError::InvalidRequest(format!("ledger has no {name} column"));
+        let external_id =
+            column(&["external_id", "externalId"]).ok_or_else(|| missing("external_id"))?;
+        let amount = column(&["amount"]).ok_or_else(|| missing("amount"))?;
+        let status = column(&["status"]);
+        let transaction_id = column(&["transaction_id", "transId"]);
+
+        let field = |record: &[String], index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let mut entries = Vec::new();
+        for (line, record) in records.enumerate() {
+            if record.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+            if record.len() > header.len() {
+                return Err(FapshiError::InvalidRequest(format!(
+                    "ledger row {} has {} fields but the header has {} (unquoted separator \
+                     in an amount?)",
+                    line + 2,
+                    record.len(),
+                    header.len()
+                )));
+            }
+            let raw_amount = field(&record, Some(amount)).unwrap_or_default();
+            let parsed_amount = parse_amount(&raw_amount, decimal_separator).ok_or_else(|| {
+                FapshiError::InvalidRequest(format!(
+                    "ledger row {}: invalid amount {raw_amount:?} (decimal separator {:?}, \
+                     no digit grouping)",
+                    line + 2,
+                    decimal_separator
+                ))
+            })?;
+            let entry_id = field(&record, Some(external_id)).ok_or_else(|| {
+                FapshiError::InvalidRequest(format!("ledger row {}: empty external_id", line + 2))
+            })?;
+            let entry_status = match field(&record, status) {
+                Some(raw_status) => Some(parse_status(&raw_status).ok_or_else(|| {
+                    FapshiError::InvalidRequest(format!(
+                        "ledger row {}: unknown status {raw_status:?}",
+                        line + 2
+                    ))
+                })?),
+                None => None,
+            };
+            entries.push(LedgerEntry {
+                external_id: entry_id,
+                amount: parsed_amount,
+                status: entry_status,
+                transaction_id: field(&record, transaction_id),
+            });
+        }
+        Ok(CsvLedger { entries })
+    }
+}
+
+/// Parses a Fapshi status name, ignoring case. Unlike `Status::from`, unknown names are
+/// rejected rather than read as `CREATED`.
+fn parse_status(raw: &str) -> Option<Status> {
+    [
+        Status::CREATED,
+        Status::PENDING,
+        Status::SUCCESSFUL,
+        Status::FAILED,
+        Status::EXPIRED,
+    ]
+    .into_iter()
+    .find(|status| status.to_string().eq_ignore_ascii_case(raw))
+}
+
+/// Parses an amount whose only separator is at most one `decimal_separator`.
+fn parse_amount(raw: &str, decimal_separator: char) -> Option<f64> {
+    let digits = raw.strip_prefix('-').unwrap_or(raw);
+    let mut parts = digits.split(decimal_separator);
+    let whole = parts.next()?;
+    let fraction = parts.next();
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if parts.next().is_some() || !is_digits(whole) || !fraction.is_none_or(is_digits) {
+        return None;
+    }
+    raw.replacen(decimal_separator, ".", 1).parse().ok()
+}
+
+impl Ledger for CsvLedger {
+    fn entries(&self) -> Result<Vec<LedgerEntry>, FapshiError> {
+        Ok(self.entries.clone())
+    }
+}
+
+/// The outcome of reconciling one record.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The ledger entry and the Fapshi transaction agree.
+    Matched,
+    /// The amounts differ. Reported even if the statuses also differ.
+    AmountMismatch,
+    /// The amounts agree but the statuses differ.
+    StatusMismatch,
+    /// Fapshi has a transaction that is not in the ledger.
+    MissingInLedger,
+    /// The ledger has an entry with no transaction on Fapshi.
+    MissingOnFapshi,
+}
+
+impl Outcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Matched => "matched",
+            Outcome::AmountMismatch => "amount_mismatch",
+            Outcome::StatusMismatch => "status_mismatch",
+            Outcome::MissingInLedger => "missing_in_ledger",
+            Outcome::MissingOnFapshi => "missing_on_fapshi",
+        }
+    }
+}
+
+/// One line of a [`ReconciliationReport`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReconciliationRecord {
+    /// How the ledger entry and Fapshi transaction compare.
+    pub outcome: Outcome,
+    /// The external ID, from the ledger or the transaction.
+    pub external_id: Option<String>,
+    /// The Fapshi transaction ID, if a transaction was found.
+    pub transaction_id: Option<String>,
+    /// The amount in the ledger.
+    pub ledger_amount: Option<f64>,
+    /// The amount on Fapshi.
+    pub fapshi_amount: Option<f64>,
+    /// The status in the ledger.
+    pub ledger_status: Option<Status>,
+    /// The status on Fapshi.
+    pub fapshi_status: Option<Status>,
+}
+
+/// Options for [`reconcile`].
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileOptions {
+    /// The largest amount difference still considered a match.
+    pub amount_tolerance: f64,
+}
+
+/// Summary counts of a [`ReconciliationReport`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconciliationSummary {
+    /// Records that matched.
+    pub matched: usize,
+    /// Records whose amounts differ.
+    pub amount_mismatch: usize,
+    /// Records whose statuses differ.
+    pub status_mismatch: usize,
+    /// Fapshi transactions missing from the ledger.
+    pub missing_in_ledger: usize,
+    /// Ledger entries missing on Fapshi.
+    pub missing_on_fapshi: usize,
+}
+
+/// The result of reconciling a ledger against Fapshi transactions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReconciliationReport {
+    /// Every reconciled record, ledger entries first, then unmatched Fapshi transactions.
+    pub records: Vec<ReconciliationRecord>,
+}
+
+impl ReconciliationReport {
+    /// Returns the records with the given outcome.
+    pub fn by_outcome(&self, outcome: Outcome) -> impl Iterator<Item = &ReconciliationRecord> {
+        self.records.iter().filter(move |r| r.outcome == outcome)
+    }
+
+    /// Returns `true` if every record matched.
+    pub fn is_clean(&self) -> bool {
+        self.records.iter().all(|r| r.outcome == Outcome::Matched)
+    }
+
+    /// Counts the records per outcome.
+    pub fn summary(&self) -> ReconciliationSummary {
+        let mut summary = ReconciliationSummary::default();
+        for record in &self.records {
+            *match record.outcome {
+                Outcome::Matched => &mut summary.matched,
+                Outcome::AmountMismatch => &mut summary.amount_mismatch,
+                Outcome::StatusMismatch => &mut summary.status_mismatch,
+                Outcome::MissingInLedger => &mut summary.missing_in_ledger,
+                Outcome::MissingOnFapshi => &mut summary.missing_on_fapshi,
+            } += 1;
+        }
+        summary
+    }
+
+    /// Writes the records as CSV with a header row.
+    pub fn write_csv(&self, mut writer: impl Write) -> Result<(), FapshiError> {
+        let header = [
+            "outcome",
+            "external_id",
+            "transaction_id",
+            "ledger_amount",
+            "fapshi_amount",
+            "ledger_status",
+            "fapshi_status",
+        ]
+        .map(str::to_string);
+        writeln!(writer, "{}", csv_row(&header, ','))?;
+        for record in &self.records {
+            let text = |value: &Option<String>| value.clone().unwrap_or_default();
+            let amount = |value: Option<f64>| value.map(|a| a.to_string()).unwrap_or_default();
+            let status =
+                |value: &Option<Status>| value.as_ref().map(|s| s.to_string()).unwrap_or_default();
+            let row = [
+                record.outcome.as_str().to_string(),
+                text(&record.external_id),
+                text(&record.transaction_id),
+                amount(record.ledger_amount),
+                amount(record.fapshi_amount),
+                status(&record.ledger_status),
+                status(&record.fapshi_status),
+            ];
+            writeln!(writer, "{}", csv_row(&row, ','))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the report, with its summary, as pretty-printed JSON.
+    pub fn write_json(&self, writer: impl Write) -> Result<(), FapshiError> {
+        #[derive(Serialize)]
+        struct Document<'a> {
+            summary: ReconciliationSummary,
+            records: &'a [ReconciliationRecord],
+        }
+        serde_json::to_writer_pretty(
+            writer,
+            &Document {
+                summary: self.summary(),
+                records: &self.records,
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// Reconciles ledger entries against Fapshi transactions.
+///
+/// Entries are matched on `transaction_id` when the ledger has one, otherwise on
+/// `external_id`. When several transactions share an external ID (e.g., a failed attempt
+/// followed by a successful one), the successful one, or else the most recent one, is used;
+/// the others are not reported as missing from the ledger.
+///
+/// # Example
+/// ```
+/// use fapshi_rs::reconciliation::{reconcile, LedgerEntry, Outcome, ReconcileOptions};
+///
+/// let ledger = vec![LedgerEntry {
+///     external_id: "order-1".to_string(),
+///     amount: 500.0,
+///     status: None,
+///     transaction_id: None,
+/// }];
+/// let report = reconcile(&ledger, &[], &ReconcileOptions::default());
+/// assert_eq!(report.records[0].outcome, Outcome::MissingOnFapshi);
+/// ```
+pub fn reconcile(
+    entries: &[LedgerEntry],
+    transactions: &[TransactionStatus],
+    options: &ReconcileOptions,
+) -> ReconciliationReport {
+    let by_id: HashMap<&str, &TransactionStatus> = transactions
+        .iter()
+        .map(|tx| (tx.transaction_id.as_str(), tx))
+        .collect();
+    let mut by_external_id: HashMap<&str, Vec<&TransactionStatus>> = HashMap::new();
+    for tx in transactions {
+        if let Some(external_id) = &tx.external_id {
+            by_external_id.entry(external_id).or_default().push(tx);
+        }
+    }
+
+    let mut accounted: HashSet<&str> = HashSet::new();
+    let mut records = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let candidates = by_external_id.get(entry.external_id.as_str());
+        let found = match &entry.transaction_id {
+            Some(id) => by_id.get(id.as_str()).copied(),
+            None => candidates.and_then(|candidates| best_candidate(candidates)),
+        };
+        for tx in candidates.into_iter().flatten() {
+            accounted.insert(&tx.transaction_id);
+        }
+        let Some(tx) = found else {
+            records.push(ReconciliationRecord {
+                outcome: Outcome::MissingOnFapshi,
+                external_id: Some(entry.external_id.clone()),
+                transaction_id: entry.transaction_id.clone(),
+                ledger_amount: Some(entry.amount),
+                fapshi_amount: None,
+                ledger_status: entry.status.clone(),
+                fapshi_status: None,
+            });
+            continue;
+        };
+        accounted.insert(&tx.transaction_id);
+        let outcome = if (entry.amount - tx.amount).abs() > options.amount_tolerance + f64::EPSILON
+        {
+            Outcome::AmountMismatch
+        } else if entry
+            .status
+            .as_ref()
+            .is_some_and(|status| *status != tx.status)
+        {
+            Outcome::StatusMismatch
+        } else {
+            Outcome::Matched
+        };
+        records.push(ReconciliationRecord {
+            outcome,
+            external_id: Some(entry.external_id.clone()),
+            transaction_id: Some(tx.transaction_id.clone()),
+            ledger_amount: Some(entry.amount),
+            fapshi_amount: Some(tx.amount),
+            ledger_status: entry.status.clone(),
+            fapshi_status: Some(tx.status.clone()),
+        });
+    }
+
+    for tx in transactions {
+        if !accounted.contains(tx.transaction_id.as_str()) {
+            records.push(ReconciliationRecord {
+                outcome: Outcome::MissingInLedger,
+                external_id: tx.external_id.clone(),
+                transaction_id: Some(tx.transaction_id.clone()),
+                ledger_amount: None,
+                fapshi_amount: Some(tx.amount),
+                ledger_status: None,
+                fapshi_status: Some(tx.status.clone()),
+            });
+        }
+    }
+    ReconciliationReport { records }
+}
+
+/// Reconciles a ledger against the Fapshi transactions matching `query`.
+///
+/// Without a `limit`, every matching transaction is fetched: searches that return a full page
+/// are split by date range (and by status for a single day). With a `limit`, one search is
+/// made, so Fapshi transactions beyond it are not reconciled.
+///
+/// # Errors
+/// Returns `FapshiError::InvalidRequest` if a full page cannot be split further (no date
+/// range, or one day with more transactions per status than a search returns).
+#[cfg(not(feature = "async"))]
+pub fn reconcile_search(
+    client: &FapshiClient,
+    ledger: &(impl Ledger + ?Sized),
+    query: &TransactionSearchQuery,
+    options: &ReconcileOptions,
+) -> Result<ReconciliationReport, FapshiError> {
+    let transactions = if query.limit.is_some() {
+        TransactionApi::search_transactions(client, query)?
+    } else {
+        let mut transactions = Vec::new();
+        TransactionApi::search_pages(client, query, |page| {
+            transactions.extend(page);
+            Ok(())
+        })?;
+        transactions
+    };
+    Ok(reconcile(&ledger.entries()?, &transactions, options))
+}
+
+/// Reconciles a ledger against the Fapshi transactions matching `query`, asynchronously.
+///
+/// Without a `limit`, every matching transaction is fetched: searches that return a full page
+/// are split by date range (and by status for a single day). With a `limit`, one search is
+/// made, so Fapshi transactions beyond it are not reconciled.
+///
+/// # Errors
+/// Returns `FapshiError::InvalidRequest` if a full page cannot be split further (no date
+/// range, or one day with more transactions per status than a search returns).
+#[cfg(feature = "async")]
+pub async fn reconcile_search(
+    client: &FapshiClient,
+    ledger: &(impl Ledger + ?Sized),
+    query: &TransactionSearchQuery,
+    options: &ReconcileOptions,
+) -> Result<ReconciliationReport, FapshiError> {
+    let transactions = if query.limit.is_some() {
+        TransactionApi::search_transactions(client, query).await?
+    } else {
+        let mut transactions = Vec::new();
+        TransactionApi::search_pages(client, query, |page| {
+            transactions.extend(page);
+            Ok(())
+        })
+        .await?;
+        transactions
+    };
+    Ok(reconcile(&ledger.entries()?, &transactions, options))
+}
+
+/// Reconciles a ledger against the Fapshi transactions of the given users.
+#[cfg(not(feature = "async"))]
+pub fn reconcile_users<'a>(
+    client: &FapshiClient,
+    ledger: &(impl Ledger + ?Sized),
+    user_ids: impl IntoIterator<Item = &'a str>,
+    options: &ReconcileOptions,
+) -> Result<ReconciliationReport, FapshiError> {
+    let mut transactions = Vec::new();
+    for user_id in user_ids {
+        transactions.extend(TransactionApi::get_transactions_by_user_id(
+            client, user_id,
+        )?);
+    }
+    Ok(reconcile(&ledger.entries()?, &transactions, options))
+}
+
+/// Reconciles a ledger against the Fapshi transactions of the given users, asynchronously.
+#[cfg(feature = "async")]
+pub async fn reconcile_users<'a>(
+    client: &FapshiClient,
+    ledger: &(impl Ledger + ?Sized),
+    user_ids: impl IntoIterator<Item = &'a str>,
+    options: &ReconcileOptions,
+) -> Result<ReconciliationReport, FapshiError> {
+    let mut transactions = Vec::new();
+    for user_id in user_ids {
+        transactions.extend(TransactionApi::get_transactions_by_user_id(client, user_id).await?);
+    }
+    Ok(reconcile(&ledger.entries()?, &transactions, options))
+}
+
+/// Picks the successful transaction, or else the most recently initiated one.
+fn best_candidate<'a>(candidates: &[&'a TransactionStatus]) -> Option<&'a TransactionStatus> {
+    candidates
+        .iter()
+        .find(|tx| tx.status == Status::SUCCESSFUL)
+        .or_else(|| candidates.iter().max_by_key(|tx| tx.initiated_at()))
+        .copied()
+}
+
+/// Splits CSV text into records, honouring quoted fields.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn transaction(
+        id: &str,
+        external_id: &str,
+        amount: f64,
+        status: &str,
+        date: &str,
+    ) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": amount,
+            "email": "",
+            "redirectUrl": "",
+            "externalId": external_id,
+            "dateInitiated": date,
+            "dateConfirmed": "",
+        }))
+        .unwrap()
+    }
+
+    fn entry(external_id: &str, amount: f64, status: Option<Status>) -> LedgerEntry {
+        LedgerEntry {
+            external_id: external_id.to_string(),
+            amount,
+            status,
+            transaction_id: None,
+        }
+    }
+
+    #[test]
+    fn csv_fields_may_be_quoted() {
+        let records = parse_csv("a,\"b, \"\"c\"\"\"\r\n\"multi\nline\",d");
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_string(), "b, \"c\"".to_string()],
+                vec!["multi\nline".to_string(), "d".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_ledger_reads_aliased_columns_and_skips_blank_rows() {
+        let csv = "\u{feff}Amount,externalId,note,transId,status\n\
+                   1500,order-1,first,T1,successful\n\
+                   ,,,,\n\
+                   2.5,order-2,,,\n";
+        let ledger = CsvLedger::from_reader(csv.as_bytes()).unwrap();
+        let entries = ledger.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            LedgerEntry {
+                external_id: "order-1".to_string(),
+                amount: 1500.0,
+                status: Some(Status::SUCCESSFUL),
+                transaction_id: Some("T1".to_string()),
+            }
+        );
+        assert_eq!(entries[1], entry("order-2", 2.5, None));
+    }
+
+    #[test]
+    fn csv_ledger_rejects_missing_columns_and_bad_amounts() {
+        let missing = CsvLedger::from_reader("external_id,total\norder-1,5\n".as_bytes());
+        assert!(matches!(missing, Err(FapshiError::InvalidRequest(m)) if m.contains("amount")));
+
+        let invalid =
+            CsvLedger::from_reader("external_id,amount\norder-1,5\norder-2,abc\n".as_bytes());
+        assert!(matches!(invalid, Err(FapshiError::InvalidRequest(m)) if m.contains("row 3")));
+    }
+
+    #[test]
+    fn csv_ledger_rejects_unknown_statuses_and_empty_external_ids() {
+        let statuses = "external_id,amount,status
+order-1,5,Pending
+order-2,5,paid
+";
+        let unknown = CsvLedger::from_reader(statuses.as_bytes());
+        assert!(matches!(
+            unknown,
+            Err(FapshiError::InvalidRequest(m)) if m.contains("row 3") && m.contains("\"paid\"")
+        ));
+
+        let ledger = CsvLedger::from_reader(
+            "external_id,amount,status
+order-1,5,EXPIRED
+"
+            .as_bytes(),
+        );
+        assert_eq!(
+            ledger.unwrap().entries().unwrap(),
+            vec![entry("order-1", 5.0, Some(Status::EXPIRED))]
+        );
+
+        let empty = CsvLedger::from_reader(
+            "external_id,amount
+order-1,5
+  ,7
+"
+            .as_bytes(),
+        );
+        assert!(matches!(
+            empty,
+            Err(FapshiError::InvalidRequest(m)) if m.contains("row 3") && m.contains("external_id")
+        ));
+    }
+
+    #[test]
+    fn reconcile_reports_each_outcome() {
+        let ledger = vec![
+            entry("order-1", 500.0, Some(Status::SUCCESSFUL)),
+            entry("order-2", 700.0, None),
+            entry("order-3", 900.0, Some(Status::SUCCESSFUL)),
+            entry("order-4", 100.0, None),
+        ];
+        let date = "2025-01-31T09:15:00.000Z";
+        let transactions = vec![
+            transaction("T1", "order-1", 500.0, "SUCCESSFUL", date),
+            transaction("T2", "order-2", 650.0, "SUCCESSFUL", date),
+            transaction("T3", "order-3", 900.0, "FAILED", date),
+            transaction("T5", "order-5", 300.0, "SUCCESSFUL", date),
+        ];
+        let report = reconcile(&ledger, &transactions, &ReconcileOptions::default());
+        let outcomes: Vec<_> = report.records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Matched,
+                Outcome::AmountMismatch,
+                Outcome::StatusMismatch,
+                Outcome::MissingOnFapshi,
+                Outcome::MissingInLedger,
+            ]
+        );
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.summary(),
+            ReconciliationSummary {
+                matched: 1,
+                amount_mismatch: 1,
+                status_mismatch: 1,
+                missing_in_ledger: 1,
+                missing_on_fapshi: 1,
+            }
+        );
+        assert_eq!(
+            report
+                .by_outcome(Outcome::MissingInLedger)
+                .next()
+                .unwrap()
+                .transaction_id
+                .as_deref(),
+            Some("T5")
+        );
+    }
+
+    #[test]
+    fn amounts_within_the_tolerance_match() {
+        let ledger = vec![entry("order-1", 500.0, None)];
+        let transactions = vec![transaction("T1", "order-1", 499.5, "SUCCESSFUL", "")];
+        let strict = reconcile(&ledger, &transactions, &ReconcileOptions::default());
+        assert_eq!(strict.records[0].outcome, Outcome::AmountMismatch);
+        let lenient = reconcile(
+            &ledger,
+            &transactions,
+            &ReconcileOptions {
+                amount_tolerance: 0.5,
+            },
+        );
+        assert!(lenient.is_clean());
+    }
+
+    #[test]
+    fn the_successful_attempt_is_matched_and_retries_are_not_missing() {
+        let ledger = vec![entry("order-1", 500.0, Some(Status::SUCCESSFUL))];
+        let transactions = vec![
+            transaction("T1", "order-1", 500.0, "FAILED", "2025-01-31T09:15:00.000Z"),
+            transaction(
+                "T2",
+                "order-1",
+                500.0,
+                "SUCCESSFUL",
+                "2025-01-31T09:20:00.000Z",
+            ),
+            transaction(
+                "T3",
+                "order-1",
+                500.0,
+                "EXPIRED",
+                "2025-01-31T09:25:00.000Z",
+            ),
+        ];
+        let report = reconcile(&ledger, &transactions, &ReconcileOptions::default());
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].transaction_id.as_deref(), Some("T2"));
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn a_recorded_transaction_id_takes_precedence() {
+        let mut pinned = entry("order-1", 500.0, None);
+        pinned.transaction_id = Some("T1".to_string());
+        let transactions = vec![
+            transaction("T1", "order-1", 500.0, "FAILED", "2025-01-31T09:15:00.000Z"),
+            transaction(
+                "T2",
+                "order-1",
+                500.0,
+                "SUCCESSFUL",
+                "2025-01-31T09:20:00.000Z",
+            ),
+        ];
+        let report = reconcile(&[pinned], &transactions, &ReconcileOptions::default());
+        assert_eq!(report.records[0].transaction_id.as_deref(), Some("T1"));
+        assert_eq!(report.records.len(), 1);
+    }
+
+    #[test]
+    fn amounts_have_one_explicit_decimal_separator() {
+        assert_eq!(parse_amount("1500", '.'), Some(1500.0));
+        assert_eq!(parse_amount("-2.50", '.'), Some(-2.5));
+        assert_eq!(parse_amount("2,50", ','), Some(2.5));
+        for invalid in ["1,000", "1.000.000", "1 000", "", ".5", "5.", "abc"] {
+            assert_eq!(parse_amount(invalid, '.'), None, "{invalid:?}");
+        }
+        assert_eq!(parse_amount("1.000", ','), None);
+    }
+
+    #[test]
+    fn csv_ledger_uses_the_given_decimal_separator() {
+        let csv = "external_id,amount\norder-1,\"1500,50\"\n";
+        let ledger = CsvLedger::from_reader_with_decimal_separator(csv.as_bytes(), ',').unwrap();
+        assert_eq!(ledger.entries().unwrap()[0].amount, 1500.5);
+
+        let unquoted = CsvLedger::from_reader("external_id,amount\norder-1,1,500\n".as_bytes());
+        assert!(matches!(unquoted, Err(FapshiError::InvalidRequest(m)) if m.contains("3 fields")));
+        let grouped = CsvLedger::from_reader("external_id,amount\norder-1,\"1,500\"\n".as_bytes());
+        assert!(matches!(grouped, Err(FapshiError::InvalidRequest(m)) if m.contains("row 2")));
+    }
+}
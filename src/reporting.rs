@@ -0,0 +1,445 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::FapshiError,
+    export::csv_row,
+    models::{Status, TransactionStatus},
+};
+
+/// The length of the periods totals are grouped by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    /// Calendar days.
+    Day,
+    /// ISO weeks, starting on Monday.
+    Week,
+    /// Calendar months.
+    Month,
+}
+
+impl Period {
+    /// Returns the first day of the period containing `date`.
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+            Period::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Money totals over a set of transactions.
+///
+/// Amounts, revenue and fees only include successful transactions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Totals {
+    /// Number of transactions, whatever their status.
+    pub count: usize,
+    /// Number of successful transactions.
+    pub successful: usize,
+    /// Amount paid by customers.
+    pub amount: f64,
+    /// Amount credited to the service.
+    pub revenue: f64,
+    /// Fapshi fees (amount minus revenue).
+    pub fees: f64,
+}
+
+impl Totals {
+    /// Share of transactions that succeeded, between 0 and 1.
+    pub fn success_rate(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.successful as f64 / self.count as f64
+        }
+    }
+
+    fn add(&mut self, tx: &TransactionStatus) {
+        self.count += 1;
+        if tx.status == Status::SUCCESSFUL {
+            self.successful += 1;
+            self.amount += tx.amount;
+            self.revenue += tx.revenue.unwrap_or(tx.amount);
+            self.fees += tx.fee().unwrap_or(0.0);
+        }
+    }
+}
+
+/// Totals for one period.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeriodTotals {
+    /// The first day of the period.
+    pub start: NaiveDate,
+    /// The totals for the period.
+    pub totals: Totals,
+}
+
+/// Totals for one payment medium (e.g., "mobile money" for MTN, "orange money" for Orange).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MediumTotals {
+    /// The medium as reported by Fapshi, or "unknown".
+    pub medium: String,
+    /// The totals for the medium.
+    pub totals: Totals,
+}
+
+/// Totals for one user ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserTotals {
+    /// The user ID given when the payment was created.
+    pub user_id: String,
+    /// The totals for the user.
+    pub totals: Totals,
+}
+
+/// Settlement and revenue report over a set of transactions.
+///
+/// Transactions are assigned to periods by confirmation date, or by initiation date if they
+/// were never confirmed; transactions with neither date are only counted in the overall totals.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     api::transaction::TransactionApi,
+///     client::FapshiClient,
+///     models::TransactionSearchQuery,
+///     reporting::{Period, TransactionReport},
+///     safety::Environment,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let transactions =
+///     TransactionApi::search_transactions(&client, &TransactionSearchQuery::default())?;
+/// let report = TransactionReport::build(&transactions, Period::Month);
+/// println!("{}", report.to_markdown());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransactionReport {
+    /// The period length used for `by_period`.
+    pub period: Period,
+    /// Totals over all transactions.
+    pub totals: Totals,
+    /// Totals per period, oldest first.
+    pub by_period: Vec<PeriodTotals>,
+    /// Totals per payment medium, by medium name.
+    pub by_medium: Vec<MediumTotals>,
+    /// Totals per user ID, largest amount first. Transactions without a user ID are omitted.
+    pub by_user: Vec<UserTotals>,
+    /// Average time in seconds between initiation and confirmation of successful transactions.
+    pub average_confirmation_secs: Option<f64>,
+}
+
+impl TransactionReport {
+    /// Builds the report from `transactions`, grouping totals by `period`.
+    pub fn build(transactions: &[TransactionStatus], period: Period) -> Self {
+        let mut totals = Totals::default();
+        let mut by_period: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+        let mut by_medium: BTreeMap<String, Totals> = BTreeMap::new();
+        let mut by_user: BTreeMap<String, Totals> = BTreeMap::new();
+        let mut latency_sum = 0.0;
+        let mut latency_samples = 0usize;
+
+        for tx in transactions {
+            totals.add(tx);
+            if let Some(date) = tx.confirmed_at().or_else(|| tx.initiated_at()) {
+                by_period
+                    .entry(period.start_of(date.date_naive()))
+                    .or_default()
+                    .add(tx);
+            }
+            let medium = tx
+                .medium
+                .as_deref()
+                .map(|m| m.trim().to_lowercase())
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| "unknown".to_string());
+            by_medium.entry(medium).or_default().add(tx);
+            if let Some(user_id) = &tx.user_id {
+                by_user.entry(user_id.clone()).or_default().add(tx);
+            }
+            if tx.status == Status::SUCCESSFUL {
+                if let (Some(initiated), Some(confirmed)) = (tx.initiated_at(), tx.confirmed_at()) {
+                    latency_sum += (confirmed - initiated).num_milliseconds() as f64 / 1000.0;
+                    latency_samples += 1;
+                }
+            }
+        }
+
+        let mut by_user: Vec<UserTotals> = by_user
+            .into_iter()
+            .map(|(user_id, totals)| UserTotals { user_id, totals })
+            .collect();
+        by_user.sort_by(|a, b| b.totals.amount.total_cmp(&a.totals.amount));
+
+        TransactionReport {
+            period,
+            totals,
+            by_period: by_period
+                .into_iter()
+                .map(|(start, totals)| PeriodTotals { start, totals })
+                .collect(),
+            by_medium: by_medium
+                .into_iter()
+                .map(|(medium, totals)| MediumTotals { medium, totals })
+                .collect(),
+            by_user,
+            average_confirmation_secs: (latency_samples > 0)
+                .then(|| latency_sum / latency_samples as f64),
+        }
+    }
+
+    /// Writes the per-period totals as CSV.
+    pub fn write_period_csv(&self, writer: impl Write) -> Result<(), FapshiError> {
+        let rows = self
+            .by_period
+            .iter()
+            .map(|p| (p.start.to_string(), &p.totals));
+        write_totals_csv(writer, "period_start", rows)
+    }
+
+    /// Writes the per-medium totals as CSV.
+    pub fn write_medium_csv(&self, writer: impl Write) -> Result<(), FapshiError> {
+        let rows = self.by_medium.iter().map(|m| (m.medium.clone(), &m.totals));
+        write_totals_csv(writer, "medium", rows)
+    }
+
+    /// Writes the per-user totals as CSV.
+    pub fn write_user_csv(&self, writer: impl Write) -> Result<(), FapshiError> {
+        let rows = self.by_user.iter().map(|u| (u.user_id.clone(), &u.totals));
+        write_totals_csv(writer, "user_id", rows)
+    }
+
+    /// Renders the whole report as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Transaction report\n");
+        let _ = writeln!(out, "| | |\n|---|---|");
+        let _ = writeln!(out, "| Transactions | {} |", self.totals.count);
+        let _ = writeln!(out, "| Successful | {} |", self.totals.successful);
+        let _ = writeln!(
+            out,
+            "| Success rate | {:.1}% |",
+            self.totals.success_rate() * 100.0
+        );
+        let _ = writeln!(out, "| Amount | {} |", self.totals.amount);
+        let _ = writeln!(out, "| Revenue | {} |", self.totals.revenue);
+        let _ = writeln!(out, "| Fees | {} |", self.totals.fees);
+        if let Some(secs) = self.average_confirmation_secs {
+            let _ = writeln!(out, "| Average confirmation time | {secs:.1}s |");
+        }
+        let period = match self.period {
+            Period::Day => "Day",
+            Period::Week => "Week of",
+            Period::Month => "Month of",
+        };
+        let rows = self
+            .by_period
+            .iter()
+            .map(|p| (p.start.to_string(), &p.totals));
+        markdown_totals(&mut out, "By period", period, rows);
+        let rows = self.by_medium.iter().map(|m| (m.medium.clone(), &m.totals));
+        markdown_totals(&mut out, "By medium", "Medium", rows);
+        let rows = self.by_user.iter().map(|u| (u.user_id.clone(), &u.totals));
+        markdown_totals(&mut out, "By user", "User ID", rows);
+        out
+    }
+}
+
+fn write_totals_csv<'a>(
+    mut writer: impl Write,
+    key: &str,
+    rows: impl Iterator<Item = (String, &'a Totals)>,
+) -> Result<(), FapshiError> {
+    let header = [
+        key,
+        "count",
+        "successful",
+        "success_rate",
+        "amount",
+        "revenue",
+        "fees",
+    ]
+    .map(str::to_string);
+    writeln!(writer, "{}", csv_row(&header, ','))?;
+    for (label, totals) in rows {
+        let row = [
+            label,
+            totals.count.to_string(),
+            totals.successful.to_string(),
+            format!("{:.4}", totals.success_rate()),
+            totals.amount.to_string(),
+            totals.revenue.to_string(),
+            totals.fees.to_string(),
+        ];
+        writeln!(writer, "{}", csv_row(&row, ','))?;
+    }
+    Ok(())
+}
+
+fn markdown_totals<'a>(
+    out: &mut String,
+    title: &str,
+    key: &str,
+    rows: impl Iterator<Item = (String, &'a Totals)>,
+) {
+    let _ = writeln!(out, "\n## {title}\n");
+    let _ = writeln!(
+        out,
+        "| {key} | Count | Successful | Success rate | Amount | Revenue | Fees |"
+    );
+    let _ = writeln!(out, "|---|---:|---:|---:|---:|---:|---:|");
+    for (label, totals) in rows {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {:.1}% | {} | {} | {} |",
+            label.replace('|', "\\|"),
+            totals.count,
+            totals.successful,
+            totals.success_rate() * 100.0,
+            totals.amount,
+            totals.revenue,
+            totals.fees
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn transaction(
+        status: &str,
+        amount: f64,
+        medium: &str,
+        user: Option<&str>,
+        initiated: &str,
+        confirmed: &str,
+    ) -> TransactionStatus {
+        serde_json::from_value(json!({
+            "transId": "T1",
+            "status": status,
+            "medium": medium,
+            "serviceName": "Shop",
+            "amount": amount,
+            "revenue": amount * 0.98,
+            "email": "",
+            "redirectUrl": "",
+            "userId": user,
+            "dateInitiated": initiated,
+            "dateConfirmed": confirmed,
+        }))
+        .unwrap()
+    }
+
+    fn transactions() -> Vec<TransactionStatus> {
+        vec![
+            transaction(
+                "SUCCESSFUL",
+                1000.0,
+                "mobile money",
+                Some("u1"),
+                "2025-01-30T10:00:00.000Z",
+                "2025-01-30T10:00:30.000Z",
+            ),
+            transaction(
+                "FAILED",
+                500.0,
+                "Orange Money",
+                Some("u2"),
+                "2025-02-03T08:00:00.000Z",
+                "",
+            ),
+            transaction(
+                "SUCCESSFUL",
+                2000.0,
+                "orange money",
+                Some("u2"),
+                "2025-02-03T09:00:00.000Z",
+                "2025-02-03T09:01:30.000Z",
+            ),
+            transaction("CREATED", 300.0, "", None, "", ""),
+        ]
+    }
+
+    #[test]
+    fn periods_start_on_the_first_day() {
+        let date = NaiveDate::from_ymd_opt(2025, 2, 6).unwrap();
+        assert_eq!(Period::Day.start_of(date), date);
+        assert_eq!(
+            Period::Week.start_of(date),
+            NaiveDate::from_ymd_opt(2025, 2, 3).unwrap()
+        );
+        assert_eq!(
+            Period::Month.start_of(date),
+            NaiveDate::from_ymd_opt(2025, 2, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn totals_only_count_money_from_successful_transactions() {
+        let report = TransactionReport::build(&transactions(), Period::Month);
+        assert_eq!(report.totals.count, 4);
+        assert_eq!(report.totals.successful, 2);
+        assert_eq!(report.totals.amount, 3000.0);
+        assert_eq!(report.totals.revenue, 2940.0);
+        assert_eq!(report.totals.fees, 60.0);
+        assert_eq!(report.totals.success_rate(), 0.5);
+        assert_eq!(report.average_confirmation_secs, Some(60.0));
+    }
+
+    #[test]
+    fn transactions_are_grouped_by_period_medium_and_user() {
+        let report = TransactionReport::build(&transactions(), Period::Week);
+        let periods: Vec<_> = report
+            .by_period
+            .iter()
+            .map(|p| (p.start.to_string(), p.totals.count))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![("2025-01-27".to_string(), 1), ("2025-02-03".to_string(), 2)]
+        );
+
+        let media: Vec<_> = report
+            .by_medium
+            .iter()
+            .map(|m| (m.medium.as_str(), m.totals.count))
+            .collect();
+        assert_eq!(
+            media,
+            vec![("mobile money", 1), ("orange money", 2), ("unknown", 1)]
+        );
+
+        let users: Vec<_> = report.by_user.iter().map(|u| u.user_id.as_str()).collect();
+        assert_eq!(users, vec!["u2", "u1"]);
+    }
+
+    #[test]
+    fn period_totals_are_written_as_csv_and_markdown() {
+        let report = TransactionReport::build(&transactions(), Period::Month);
+        let mut csv = Vec::new();
+        report.write_period_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "period_start,count,successful,success_rate,amount,revenue,fees\n\
+             2025-01-01,1,1,1.0000,1000,980,20\n\
+             2025-02-01,2,1,0.5000,2000,1960,40\n"
+        );
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| Success rate | 50.0% |"));
+        assert!(markdown.contains("| 2025-02-01 | 2 | 1 | 50.0% | 2000 | 1960 | 40 |"));
+    }
+}
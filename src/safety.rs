@@ -0,0 +1,149 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::FapshiError,
+    models::{DirectPaymentRequest, Payouts},
+};
+
+/// The Fapshi environment a client talks to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Environment {
+    /// The sandbox, where no real money moves.
+    Sandbox,
+    /// The live environment. Clients only use it when asked for explicitly.
+    Live,
+}
+
+impl Environment {
+    /// Returns the base URL of the environment's API.
+    pub fn base_url(self) -> &'static str {
+        match self {
+            Environment::Sandbox => "https://sandbox.fapshi.com",
+            Environment::Live => "https://live.fapshi.com",
+        }
+    }
+
+    /// Returns the other environment.
+    pub fn other(self) -> Self {
+        match self {
+            Environment::Sandbox => Environment::Live,
+            Environment::Live => Environment::Sandbox,
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Environment::Sandbox => "sandbox",
+            Environment::Live => "live",
+        })
+    }
+}
+
+/// Ceilings on the amounts a live client may request.
+///
+/// Every request whose JSON body has an `amount` (payment links, direct payments, payouts)
+/// counts. Limits only apply in the live environment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AmountLimits {
+    /// The largest amount a single request may carry.
+    pub per_request: Option<f64>,
+    /// The largest total a client and its clones may request per UTC day.
+    pub per_day: Option<f64>,
+}
+
+/// Tracks the amounts requested today against [`AmountLimits`].
+#[derive(Debug)]
+pub(crate) struct AmountGuard {
+    limits: AmountLimits,
+    spent: Mutex<(NaiveDate, f64)>,
+}
+
+impl AmountGuard {
+    pub(crate) fn new(limits: AmountLimits) -> Self {
+        AmountGuard {
+            limits,
+            spent: Mutex::new((Utc::now().date_naive(), 0.0)),
+        }
+    }
+
+    /// Reserves `amount` against the limits.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the amount exceeds either limit.
+    pub(crate) fn reserve(&self, amount: f64) -> Result<(), FapshiError> {
+        self.admit(amount, true)
+    }
+
+    /// Checks `amount` against the limits without reserving it.
+    pub(crate) fn check(&self, amount: f64) -> Result<(), FapshiError> {
+        self.admit(amount, false)
+    }
+
+    fn admit(&self, amount: f64, reserve: bool) -> Result<(), FapshiError> {
+        if let Some(limit) = self.limits.per_request {
+            if amount > limit {
+                return Err(FapshiError::InvalidRequest(format!(
+                    "amount {amount} exceeds the per-request limit of {limit} in live mode"
+                )));
+            }
+        }
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        let today = Utc::now().date_naive();
+        if spent.0 != today {
+            *spent = (today, 0.0);
+        }
+        if let Some(limit) = self.limits.per_day {
+            if spent.1 + amount > limit {
+                return Err(FapshiError::InvalidRequest(format!(
+                    "amount {amount} would bring today's total to {}, over the daily limit of {limit} in live mode",
+                    spent.1 + amount
+                )));
+            }
+        }
+        if reserve {
+            spent.1 += amount;
+        }
+        Ok(())
+    }
+
+    /// Gives back a reservation for a request that had no effect on Fapshi.
+    pub(crate) fn release(&self, amount: f64) {
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        spent.1 = (spent.1 - amount).max(0.0);
+    }
+
+    /// Returns the total reserved today.
+    pub(crate) fn spent_today(&self) -> f64 {
+        let spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        if spent.0 == Utc::now().date_naive() {
+            spent.1
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Reads the `amount` field of a JSON request body, if it has one.
+pub(crate) fn body_amount(body: Option<&str>) -> Option<f64> {
+    let value: serde_json::Value = serde_json::from_str(body?).ok()?;
+    value.get("amount")?.as_f64()
+}
+
+/// Runs the local validation of the request types that have one.
+pub(crate) fn validate_body(endpoint: &str, body: Option<&str>) -> Result<(), FapshiError> {
+    let Some(body) = body else {
+        return Ok(());
+    };
+    match endpoint {
+        "direct-pay" => serde_json::from_str::<DirectPaymentRequest>(body)?.validate(),
+        "payout" => serde_json::from_str::<Payouts>(body)?.validate(),
+        _ => Ok(()),
+    }
+}
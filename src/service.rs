@@ -0,0 +1,745 @@
+//! A Fapshi client built on [`tower::Service`], and Fapshi-specific [`tower::Layer`]s.
+//!
+//! [`TowerClient`] sends every request through a `Service<http::Request<String>>`, so timeouts,
+//! retries, tracing or authentication layers from the `tower` ecosystem apply to Fapshi calls
+//! like to any other outbound HTTP. [`ReqwestService`] is the transport at the bottom of the
+//! stack. The layers in this module bring the SDK's own behaviour to such a stack:
+//!
+//! * [`DecodeErrorsLayer`] turns non-success responses into `FapshiError::StatusError`, so
+//!   outer layers (e.g. a retry policy) see Fapshi errors as errors.
+//! * [`IdempotencyLayer`] answers payment requests whose external ID was already paid from an
+//!   [`IdempotencyStore`] instead of creating a second payment.
+//! * [`RateLimitLayer`] spaces out requests to respect a maximum rate.
+//!
+//! Errors travel through the stack as [`BoxError`]s; a `FapshiError` raised by a layer comes
+//! back out of `TowerClient` unchanged, and any other error becomes
+//! `FapshiError::ServiceError`.
+
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use chrono::Utc;
+use http::{Request, Response};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{de::DeserializeOwned, Serialize};
+use tower::{Layer, Service};
+
+use crate::{
+    audit::{AuditSink, AuditedCall},
+    batch::RateLimiter,
+    client::{self, ApiResponse, Method},
+    endpoint::{self, Endpoint},
+    error::FapshiError,
+    idempotency::{duplicate_stored, IdempotencyStore, StoredPayment},
+    interceptor::Interceptors,
+    models::{DirectPaymentRequest, DirectPaymentResponse, PaymentRequest, PaymentResponse},
+    safety::Environment,
+};
+
+/// The error type used between the services of a stack.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The future returned by the services of this module.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<T, BoxError>> + Send>>;
+
+/// A Fapshi client that sends its requests through a `tower` service.
+///
+/// It covers every operation of the [endpoint catalogue](crate::endpoint) through
+/// [`TowerClient::call`], and any other request through [`TowerClient::request`]. The API
+/// key headers are added to each request before it enters the service.
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     endpoint::Balance,
+///     idempotency::InMemoryIdempotencyStore,
+///     safety::Environment,
+///     service::{
+///         DecodeErrorsLayer, IdempotencyLayer, RateLimitLayer, ReqwestService, TowerClient,
+///     },
+/// };
+/// use tower::ServiceBuilder;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let service = ServiceBuilder::new()
+///     .layer(DecodeErrorsLayer)
+///     .layer(IdempotencyLayer::new(InMemoryIdempotencyStore::new()))
+///     .layer(RateLimitLayer::new(5.0)?)
+///     .service(ReqwestService::new());
+/// let client = TowerClient::new(service, "your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let balance = client.call(&Balance).await?;
+/// println!("Balance: {}", balance.balance);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TowerClient<S> {
+    service: S,
+    environment: Environment,
+    base_url: String,
+    headers: HeaderMap,
+    api_user: String,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    audit_actor: Option<String>,
+    interceptors: Interceptors,
+}
+
+impl<S> TowerClient<S> {
+    /// Creates a client that sends its requests through `service`.
+    ///
+    /// # Arguments
+    /// * `service` - The service stack, usually ending with a [`ReqwestService`].
+    /// * `api_user` - The API user ID obtained from the Fapshi dashboard.
+    /// * `api_key` - The API key obtained from the Fapshi dashboard.
+    /// * `environment` - The environment to use; `Environment::Live` moves real money.
+    ///
+    /// # Returns
+    /// A `Result` containing the `TowerClient` or a `FapshiError` if the credentials are not
+    /// valid header values.
+    pub fn new(
+        service: S,
+        api_user: &str,
+        api_key: &str,
+        environment: Environment,
+    ) -> Result<Self, FapshiError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("apiuser", HeaderValue::from_str(api_user)?);
+        headers.insert("apikey", HeaderValue::from_str(api_key)?);
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        Ok(TowerClient {
+            service,
+            environment,
+            base_url: environment.base_url().to_string(),
+            headers,
+            api_user: api_user.to_string(),
+            audit_sink: None,
+            audit_actor: None,
+            interceptors: Interceptors::default(),
+        })
+    }
+
+    /// Returns the environment the client talks to.
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    /// Records every payment link, direct payment, expiry and payout sent through the client
+    /// before and after sending it, like
+    /// [`FapshiClient::with_audit_sink`](crate::client::FapshiClient::with_audit_sink).
+    ///
+    /// # Arguments
+    /// * `sink` - Where the records go, e.g. a
+    ///   [`JsonLinesAuditSink`](crate::audit::JsonLinesAuditSink).
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sets who the audit records name as the actor. Defaults to the API user.
+    pub fn with_audit_actor(mut self, actor: &str) -> Self {
+        self.audit_actor = Some(actor.to_string());
+        self
+    }
+
+    /// Returns the actor named in audit records.
+    pub fn audit_actor(&self) -> &str {
+        self.audit_actor.as_deref().unwrap_or(&self.api_user)
+    }
+
+    /// Registers a hook called when an audit record cannot be written after a request was
+    /// sent, like [`FapshiClient::on_record_error`](crate::client::FapshiClient::on_record_error).
+    pub fn on_record_error(mut self, hook: impl Fn(&FapshiError) + Send + Sync + 'static) -> Self {
+        self.interceptors.add_record_error(hook);
+        self
+    }
+
+    /// Returns the underlying service.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S> TowerClient<S>
+where
+    S: Service<Request<String>, Response = Response<String>> + Clone,
+    S::Error: Into<BoxError>,
+{
+    /// Executes a Fapshi API operation from the endpoint catalogue.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The operation, carrying its path parameters and request body.
+    ///
+    /// # Returns
+    /// A `Result` containing the deserialized response or a `FapshiError`.
+    pub async fn call<E: Endpoint>(&self, endpoint: &E) -> Result<E::Response, FapshiError> {
+        Ok(self
+            .request(E::METHOD, &endpoint.path(), endpoint.body())
+            .await?
+            .body)
+    }
+
+    /// Sends a request with an optional JSON body through the service and returns the full
+    /// decoded response.
+    ///
+    /// # Arguments
+    /// * `method` - The HTTP method.
+    /// * `endpoint` - The API endpoint path, relative to the environment's base URL.
+    /// * `body` - The request payload, serialized as JSON if present.
+    ///
+    /// # Returns
+    /// A `Result` containing an `ApiResponse` or a `FapshiError`.
+    pub async fn request<B, T>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<ApiResponse<T>, FapshiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let body = body.map(serde_json::to_string).transpose()?;
+        let audited = self.audit_sink.as_ref().and_then(|sink| {
+            let call = AuditedCall::identify(
+                &method,
+                endpoint,
+                body.as_deref(),
+                self.audit_actor(),
+                self.environment,
+            )?;
+            Some((sink, call))
+        });
+        let mut request = Request::new(body.unwrap_or_default());
+        *request.method_mut() = method;
+        *request.uri_mut() = format!("{}/{}", self.base_url, endpoint)
+            .parse()
+            .map_err(|err| {
+                FapshiError::InvalidRequest(format!("invalid endpoint {endpoint}: {err}"))
+            })?;
+        *request.headers_mut() = self.headers.clone();
+
+        if let Some((sink, call)) = &audited {
+            call.initiated(sink.as_ref())?;
+        }
+        let mut service = self.service.clone();
+        let result = match poll_fn(|cx| service.poll_ready(cx)).await {
+            Ok(()) => service.call(request).await.map_err(into_fapshi_error),
+            Err(err) => Err(into_fapshi_error(err)),
+        };
+        if let Some((sink, call)) = audited {
+            let response = result
+                .as_ref()
+                .map(|response| (response.status(), response.body().as_str()));
+            if let Err(err) = call.finished(sink.as_ref(), response) {
+                self.interceptors.record_error(&err);
+            }
+        }
+        let (parts, text) = result?.into_parts();
+        client::decode_response(parts.status, parts.headers, &text)
+    }
+}
+
+/// Recovers a `FapshiError` raised inside the stack, or wraps any other error.
+fn into_fapshi_error(err: impl Into<BoxError>) -> FapshiError {
+    match err.into().downcast::<FapshiError>() {
+        Ok(err) => *err,
+        Err(err) => FapshiError::ServiceError(err),
+    }
+}
+
+/// The HTTP transport at the bottom of a [`TowerClient`] stack, backed by `reqwest`.
+///
+/// Transport failures are reported as `FapshiError::HttpError`.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestService {
+    client: reqwest::Client,
+}
+
+impl ReqwestService {
+    /// Creates a transport with a default `reqwest` client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a transport using an existing `reqwest` client, e.g. one with custom TLS
+    /// settings or a proxy.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        ReqwestService { client }
+    }
+}
+
+impl Service<Request<String>> for ReqwestService {
+    type Response = Response<String>;
+    type Error = BoxError;
+    type Future = BoxFuture<Response<String>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let request = reqwest::Request::try_from(request).map_err(FapshiError::from)?;
+            let response = client.execute(request).await.map_err(FapshiError::from)?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let mut decoded = Response::new(response.text().await.map_err(FapshiError::from)?);
+            *decoded.status_mut() = status;
+            *decoded.headers_mut() = headers;
+            Ok(decoded)
+        })
+    }
+}
+
+/// A layer turning non-success responses into `FapshiError::StatusError`.
+///
+/// Place it inside retry or circuit-breaking layers so that they see Fapshi's 4xx and 5xx
+/// answers as errors. `TowerClient` decodes them the same way without it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DecodeErrorsLayer;
+
+impl<S> Layer<S> for DecodeErrorsLayer {
+    type Service = DecodeErrors<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DecodeErrors { inner }
+    }
+}
+
+/// The service produced by [`DecodeErrorsLayer`].
+#[derive(Debug, Clone)]
+pub struct DecodeErrors<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<String>> for DecodeErrors<S>
+where
+    S: Service<Request<String>, Response = Response<String>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<String>;
+    type Error = BoxError;
+    type Future = BoxFuture<Response<String>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = future.await.map_err(Into::into)?;
+            if !response.status().is_success() {
+                let err =
+                    client::status_error(response.status(), response.headers(), response.body());
+                return Err(err.into());
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// A layer spacing out requests to respect a maximum rate.
+///
+/// Clones of the layer, and every service it produced, share the same budget.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    /// Allows at most `per_second` requests per second.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if `per_second` is not a positive number, or so
+    /// small that the interval between requests overflows a `Duration`.
+    pub fn new(per_second: f64) -> Result<Self, FapshiError> {
+        Ok(RateLimitLayer {
+            limiter: Arc::new(RateLimiter::new(per_second)?),
+        })
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+        }
+    }
+}
+
+/// The service produced by [`RateLimitLayer`].
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request<String>> for RateLimit<S>
+where
+    S: Service<Request<String>, Response = Response<String>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    type Response = Response<String>;
+    type Error = BoxError;
+    type Future = BoxFuture<Response<String>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let delay = self.limiter.reserve();
+        // Keep the service that was polled ready; the clone is polled for the next call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            inner.call(request).await.map_err(Into::into)
+        })
+    }
+}
+
+/// A layer answering repeated payment requests from an [`IdempotencyStore`].
+///
+/// Payment link (`initiate-pay`) and direct payment (`direct-pay`) requests carrying an
+/// external ID are looked up in the store. A payment already recorded for the external ID is
+/// returned as a `200` response without reaching Fapshi. Otherwise a pending marker is
+/// reserved before the request is sent and replaced by the successful response, or removed
+/// if Fapshi refused the request with a 4xx status or it was never sent. Other requests pass
+/// through untouched.
+///
+/// A request for an external ID with a pending marker fails with
+/// `FapshiError::PaymentInProgress`. A marker left by a timeout, 5xx response or crash stays
+/// until removed from the store; [`crate::idempotency::IdempotentPayments`] resolves such
+/// markers by searching Fapshi.
+#[derive(Debug)]
+pub struct IdempotencyLayer<St> {
+    store: Arc<St>,
+}
+
+impl<St> IdempotencyLayer<St> {
+    /// Creates the layer on top of `store`.
+    pub fn new(store: St) -> Self {
+        IdempotencyLayer {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &St {
+        &self.store
+    }
+}
+
+impl<St> Clone for IdempotencyLayer<St> {
+    fn clone(&self) -> Self {
+        IdempotencyLayer {
+            store: Arc::clone(&self.store),
+        }
+    }
+}
+
+impl<S, St> Layer<S> for IdempotencyLayer<St> {
+    type Service = Idempotency<S, St>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Idempotency {
+            inner,
+            store: Arc::clone(&self.store),
+        }
+    }
+}
+
+/// The service produced by [`IdempotencyLayer`].
+#[derive(Debug)]
+pub struct Idempotency<S, St> {
+    inner: S,
+    store: Arc<St>,
+}
+
+impl<S: Clone, St> Clone for Idempotency<S, St> {
+    fn clone(&self) -> Self {
+        Idempotency {
+            inner: self.inner.clone(),
+            store: Arc::clone(&self.store),
+        }
+    }
+}
+
+/// The kind of payment an idempotent request creates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PaymentKind {
+    PaymentLink,
+    DirectPayment,
+}
+
+impl<S, St> Service<Request<String>> for Idempotency<S, St>
+where
+    S: Service<Request<String>, Response = Response<String>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    St: IdempotencyStore + 'static,
+{
+    type Response = Response<String>;
+    type Error = BoxError;
+    type Future = BoxFuture<Response<String>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let Some((kind, external_id)) = payment_key(&request) else {
+            let future = self.inner.call(request);
+            return Box::pin(async move { future.await.map_err(Into::into) });
+        };
+        let reserved = self
+            .store
+            .get(&external_id)
+            .and_then(|stored| match stored {
+                Some(stored) => match stored_body(kind, &stored) {
+                    Some(body) => body.map(Some),
+                    None => Err(duplicate_stored(&external_id, &stored)),
+                },
+                None => match self.store.reserve(&external_id, Utc::now(), None)? {
+                    true => Ok(None),
+                    false => Err(FapshiError::PaymentInProgress {
+                        external_id: external_id.clone(),
+                    }),
+                },
+            });
+        match reserved {
+            Ok(Some(body)) => return Box::pin(async move { Ok(Response::new(body)) }),
+            Ok(None) => {}
+            Err(err) => return Box::pin(async move { Err(err.into()) }),
+        }
+        let future = self.inner.call(request);
+        let store = Arc::clone(&self.store);
+        Box::pin(async move {
+            let response = match future.await.map_err(Into::into) {
+                Ok(response) => response,
+                Err(err) => {
+                    let refused = err
+                        .downcast_ref::<FapshiError>()
+                        .is_some_and(FapshiError::is_not_processed);
+                    if refused {
+                        store.remove(&external_id)?;
+                    }
+                    return Err(err);
+                }
+            };
+            if response.status().is_client_error() {
+                store.remove(&external_id)?;
+            }
+            if response.status().is_success() {
+                let stored = match kind {
+                    PaymentKind::PaymentLink => {
+                        serde_json::from_str::<PaymentResponse>(response.body())
+                            .map(StoredPayment::PaymentLink)
+                    }
+                    PaymentKind::DirectPayment => {
+                        serde_json::from_str::<DirectPaymentResponse>(response.body())
+                            .map(StoredPayment::DirectPayment)
+                    }
+                };
+                // A body that does not decode is left for the client to report.
+                if let Ok(stored) = stored {
+                    store.put(&external_id, &stored)?;
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Returns the payment kind and external ID of an idempotent payment request.
+fn payment_key(request: &Request<String>) -> Option<(PaymentKind, String)> {
+    let path = request.uri().path().trim_start_matches('/');
+    let body = request.body();
+    match endpoint::identify(request.method(), path)?.name {
+        "initiate-pay" => Some((
+            PaymentKind::PaymentLink,
+            serde_json::from_str::<PaymentRequest>(body)
+                .ok()?
+                .external_id?,
+        )),
+        "direct-pay" => Some((
+            PaymentKind::DirectPayment,
+            serde_json::from_str::<DirectPaymentRequest>(body)
+                .ok()?
+                .external_id?,
+        )),
+        _ => None,
+    }
+}
+
+/// Serializes a stored payment as the response body of a request of `kind`, if it matches.
+fn stored_body(kind: PaymentKind, stored: &StoredPayment) -> Option<Result<String, FapshiError>> {
+    let body = match (kind, stored) {
+        (PaymentKind::PaymentLink, StoredPayment::PaymentLink(response)) => {
+            serde_json::to_string(response)
+        }
+        (PaymentKind::DirectPayment, StoredPayment::DirectPayment(response)) => {
+            serde_json::to_string(response)
+        }
+        _ => return None,
+    };
+    Some(body.map_err(FapshiError::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use tower::ServiceBuilder;
+
+    use super::*;
+    use crate::{
+        endpoint::{Balance, InitiatePay},
+        idempotency::InMemoryIdempotencyStore,
+    };
+
+    /// Answers requests with queued responses and records what it was sent.
+    #[derive(Clone, Default)]
+    struct Fake {
+        responses: Arc<Mutex<VecDeque<(u16, String)>>>,
+        requests: Arc<Mutex<Vec<Request<String>>>>,
+    }
+
+    impl Fake {
+        fn answer(self, status: u16, body: &str) -> Self {
+            self.responses
+                .lock()
+                .unwrap()
+                .push_back((status, body.to_string()));
+            self
+        }
+
+        fn requests(&self) -> Vec<(String, String)> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|r| (r.uri().to_string(), r.body().clone()))
+                .collect()
+        }
+    }
+
+    impl Service<Request<String>> for Fake {
+        type Response = Response<String>;
+        type Error = BoxError;
+        type Future = BoxFuture<Response<String>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<String>) -> Self::Future {
+            assert_eq!(request.headers()["apiuser"], "user");
+            self.requests.lock().unwrap().push(request);
+            let next = self.responses.lock().unwrap().pop_front();
+            Box::pin(async move {
+                let (status, body) = next.ok_or("no response queued")?;
+                let mut response = Response::new(body);
+                *response.status_mut() = http::StatusCode::from_u16(status)?;
+                Ok(response)
+            })
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    fn payment_request() -> PaymentRequest {
+        PaymentRequest {
+            amount: 500.0,
+            email: None,
+            redirect_url: None,
+            user_id: None,
+            external_id: Some("order-1".to_string()),
+            message: "Order 1".to_string(),
+            card_only: None,
+        }
+    }
+
+    const LINK: &str = r#"{"message":"Request successful","link":"https://checkout.fapshi.com/link/T1","transId":"T1","dateInitiated":"2025-01-31"}"#;
+
+    #[test]
+    fn requests_go_through_the_service() {
+        let fake =
+            Fake::default().answer(200, r#"{"service":"Shop","balance":1500,"currency":"XAF"}"#);
+        let client = TowerClient::new(fake.clone(), "user", "key", Environment::Sandbox).unwrap();
+        let balance = block_on(client.call(&Balance)).unwrap();
+        assert_eq!(balance.balance, 1500.0);
+        assert_eq!(
+            fake.requests(),
+            [(
+                "https://sandbox.fapshi.com/balance".to_string(),
+                String::new()
+            )]
+        );
+    }
+
+    #[test]
+    fn failures_come_back_as_status_errors() {
+        let fake = Fake::default().answer(400, r#"{"message":"Invalid amount"}"#);
+        let service = ServiceBuilder::new().layer(DecodeErrorsLayer).service(fake);
+        let client = TowerClient::new(service, "user", "key", Environment::Sandbox).unwrap();
+        let err = block_on(client.call(&Balance)).unwrap_err();
+        assert!(
+            matches!(err, FapshiError::StatusError { status: 400, message, .. } if message == "Invalid amount")
+        );
+
+        let client =
+            TowerClient::new(Fake::default(), "user", "key", Environment::Sandbox).unwrap();
+        let err = block_on(client.call(&Balance)).unwrap_err();
+        assert!(matches!(err, FapshiError::ServiceError(_)));
+    }
+
+    #[test]
+    fn idempotency_layer_answers_repeated_payments_from_the_store() {
+        let fake = Fake::default().answer(200, LINK);
+        let service = ServiceBuilder::new()
+            .layer(IdempotencyLayer::new(InMemoryIdempotencyStore::new()))
+            .service(fake.clone());
+        let client = TowerClient::new(service, "user", "key", Environment::Sandbox).unwrap();
+        let request = payment_request();
+        let first = block_on(client.call(&InitiatePay(&request))).unwrap();
+        let second = block_on(client.call(&InitiatePay(&request))).unwrap();
+        assert_eq!(first.transaction_id, "T1");
+        assert_eq!(second.transaction_id, "T1");
+        assert_eq!(fake.requests().len(), 1);
+    }
+
+    #[test]
+    fn rate_limit_layer_spaces_out_requests() {
+        let fake = Fake::default()
+            .answer(200, r#"{"service":"Shop","balance":1,"currency":"XAF"}"#)
+            .answer(200, r#"{"service":"Shop","balance":2,"currency":"XAF"}"#);
+        assert!(RateLimitLayer::new(0.0).is_err());
+        let service = ServiceBuilder::new()
+            .layer(RateLimitLayer::new(20.0).unwrap())
+            .service(fake);
+        let client = TowerClient::new(service, "user", "key", Environment::Sandbox).unwrap();
+        let started = Instant::now();
+        block_on(async {
+            client.call(&Balance).await.unwrap();
+            client.call(&Balance).await.unwrap();
+        });
+        assert!(started.elapsed() >= Duration::from_millis(45));
+    }
+}
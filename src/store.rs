@@ -0,0 +1,151 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::FapshiError;
+
+/// A value persisted as a JSON file, shared by the file-backed stores.
+///
+/// The file is read on first use and the value kept in memory; `update` rewrites the file
+/// atomically (write to a temporary file, then rename) only when the value changed. The
+/// file must not be shared between processes.
+#[derive(Debug)]
+pub(crate) struct JsonFile<T> {
+    path: PathBuf,
+    value: Mutex<Option<T>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonFile<T> {
+    /// Creates a handle on the file at `path`. The file is created on first write.
+    pub(crate) fn new(path: impl AsRef<Path>) -> Self {
+        JsonFile {
+            path: path.as_ref().to_path_buf(),
+            value: Mutex::new(None),
+        }
+    }
+
+    /// Passes the value to `read`, loading it from the file on first use.
+    pub(crate) fn read<R>(&self, read: impl FnOnce(&T) -> R) -> Result<R, FapshiError> {
+        let mut value = self.lock();
+        Ok(read(self.loaded(&mut value)?))
+    }
+
+    /// Applies `change` to the value and rewrites the file if it returns `true`.
+    ///
+    /// If the file cannot be written, the in-memory value is dropped and read again from
+    /// the file on next use, so it never holds a change that was not saved.
+    pub(crate) fn update(&self, change: impl FnOnce(&mut T) -> bool) -> Result<bool, FapshiError> {
+        let mut value = self.lock();
+        let loaded = self.loaded(&mut value)?;
+        if !change(loaded) {
+            return Ok(false);
+        }
+        if let Err(err) = write_atomically(&self.path, loaded) {
+            *value = None;
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<T>> {
+        self.value.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn loaded<'a>(&self, value: &'a mut Option<T>) -> Result<&'a mut T, FapshiError> {
+        if value.is_none() {
+            *value = Some(read_or_default(&self.path)?);
+        }
+        Ok(value.get_or_insert_with(T::default))
+    }
+}
+
+/// Reads a JSON file, or returns the default value if it is missing or empty.
+fn read_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, FapshiError> {
+    match fs::read_to_string(path) {
+        Ok(contents) if contents.trim().is_empty() => Ok(T::default()),
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes `value` as pretty JSON to a temporary file next to `path`, then renames it over
+/// `path`, so readers never see a partly written file.
+pub(crate) fn write_atomically(path: &Path, value: &impl Serialize) -> Result<(), FapshiError> {
+    let tmp = temp_path(path);
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// The temporary file for `path`: its full file name with `.tmp` appended (`x.json.tmp`),
+/// so files that differ only by extension do not share one.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fapshi-rs-store-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn temp_names_keep_the_whole_file_name() {
+        assert_eq!(
+            temp_path(Path::new("data/payments.json")),
+            Path::new("data/payments.json.tmp")
+        );
+        assert_eq!(
+            temp_path(Path::new("payments.cache")),
+            Path::new("payments.cache.tmp")
+        );
+    }
+
+    #[test]
+    fn updates_are_written_only_on_change_and_survive_reopening() {
+        let path = path("update.json");
+        let _ = fs::remove_file(&path);
+        let file = JsonFile::<BTreeMap<String, u32>>::new(&path);
+        assert!(file.read(BTreeMap::is_empty).unwrap());
+        assert!(!file.update(|_| false).unwrap());
+        assert!(!path.exists());
+
+        assert!(file
+            .update(|map| map.insert("a".to_string(), 1).is_none())
+            .unwrap());
+        assert!(!temp_path(&path).exists());
+        let reopened = JsonFile::<BTreeMap<String, u32>>::new(&path);
+        assert_eq!(reopened.read(|map| map.get("a").copied()).unwrap(), Some(1));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn the_value_is_kept_in_memory_after_loading() {
+        let path = path("memory.json");
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        let file = JsonFile::<BTreeMap<String, u32>>::new(&path);
+        assert_eq!(file.read(BTreeMap::len).unwrap(), 1);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(file.read(|map| map.get("a").copied()).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn a_failed_write_is_not_kept_in_memory() {
+        let dir = path("missing-dir");
+        let file = JsonFile::<BTreeMap<String, u32>>::new(dir.join("store.json"));
+        assert!(file
+            .update(|map| map.insert("a".to_string(), 1).is_none())
+            .is_err());
+        assert!(file.read(BTreeMap::is_empty).unwrap());
+    }
+}
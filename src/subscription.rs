@@ -0,0 +1,1288 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Months, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{payment::PaymentApi, transaction::TransactionApi},
+    client::FapshiClient,
+    error::FapshiError,
+    models::{DirectPaymentRequest, PaymentRequest, Status, TransactionStatus},
+    store::JsonFile,
+};
+
+/// How often a plan is billed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingInterval {
+    /// Every given number of days.
+    Days(u32),
+    /// Every given number of weeks.
+    Weeks(u32),
+    /// Every given number of calendar months. Billing on the 31st falls back to the last
+    /// day of shorter months, and returns to the 31st in longer ones.
+    Months(u32),
+}
+
+impl BillingInterval {
+    /// Returns the billing date following `date`.
+    ///
+    /// Chaining calls drifts for monthly intervals (Jan 31, Feb 28, Mar 28...); use
+    /// [`BillingInterval::nth_after`] with a fixed anchor to compute a schedule.
+    pub fn next_after(&self, date: DateTime<Utc>) -> DateTime<Utc> {
+        self.nth_after(date, 1)
+    }
+
+    /// Returns the billing date `n` intervals after `anchor`, e.g. the due date of period
+    /// `n + 1` of a subscription that started at `anchor`.
+    pub fn nth_after(&self, anchor: DateTime<Utc>, n: u32) -> DateTime<Utc> {
+        let next = match self {
+            BillingInterval::Days(days) => {
+                anchor.checked_add_signed(TimeDelta::days(i64::from(*days) * i64::from(n)))
+            }
+            BillingInterval::Weeks(weeks) => {
+                anchor.checked_add_signed(TimeDelta::weeks(i64::from(*weeks) * i64::from(n)))
+            }
+            BillingInterval::Months(months) => months
+                .checked_mul(n)
+                .and_then(|months| anchor.checked_add_months(Months::new(months))),
+        };
+        next.unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// A subscription plan.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// Your identifier for the plan.
+    pub id: String,
+    /// The plan name, used as the payment message.
+    pub name: String,
+    /// The amount charged each period.
+    pub amount: f64,
+    /// How often the plan is billed.
+    pub interval: BillingInterval,
+    /// How long after a charge is due the subscription may stay unpaid before it lapses.
+    pub grace_period: Duration,
+}
+
+/// The customer billed by a subscription.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Customer {
+    /// The customer's ID in your system, sent as `userId` with every charge.
+    pub user_id: String,
+    /// The phone number charged by direct payments.
+    pub phone: String,
+    /// The payment medium ("mobile money" or "orange money"), if known.
+    pub medium: Option<String>,
+    /// The customer's name.
+    pub name: Option<String>,
+    /// The customer's email, also used for fallback payment links.
+    pub email: Option<String>,
+}
+
+/// How failed charges are retried.
+#[derive(Debug, Clone)]
+pub struct DunningPolicy {
+    /// Delays before each retry after a FAILED or EXPIRED charge. The number of delays is
+    /// the number of retries.
+    pub retry_delays: Vec<Duration>,
+    /// Once retries are exhausted, create a payment link the customer can pay instead.
+    pub fallback_to_link: bool,
+    /// Where the payment link redirects to after payment.
+    pub redirect_url: Option<String>,
+}
+
+impl Default for DunningPolicy {
+    fn default() -> Self {
+        DunningPolicy {
+            retry_delays: vec![
+                Duration::from_secs(24 * 3600),
+                Duration::from_secs(3 * 24 * 3600),
+                Duration::from_secs(5 * 24 * 3600),
+            ],
+            fallback_to_link: false,
+            redirect_url: None,
+        }
+    }
+}
+
+/// The state of a subscription.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    /// Charges are up to date.
+    Active,
+    /// The current charge failed and is being retried.
+    PastDue,
+    /// The grace period ended without payment; no further charges are made.
+    Lapsed,
+    /// The subscription was cancelled.
+    Cancelled,
+}
+
+/// The state of a charge attempt.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargeState {
+    /// Recorded before the request is sent; the outcome of the request is not known yet.
+    Submitting,
+    /// Fapshi created the transaction and the customer has not paid yet.
+    Pending,
+    /// The transaction failed or expired.
+    Failed,
+}
+
+/// The current charge of a subscription.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Charge {
+    /// The billing period being charged, starting at 1.
+    pub period: u32,
+    /// The attempt number within the period, starting at 1.
+    pub attempt: u32,
+    /// The external ID sent with this attempt, see [`charge_external_id`].
+    pub external_id: String,
+    /// When the period's charge was due.
+    pub due_at: DateTime<Utc>,
+    /// The state of this attempt.
+    pub state: ChargeState,
+    /// Whether this attempt is a payment link rather than a direct payment.
+    pub via_link: bool,
+    /// The Fapshi transaction ID, once known.
+    pub transaction_id: Option<String>,
+    /// The payment link, for link attempts.
+    pub payment_link: Option<String>,
+    /// When the next attempt is made, for failed attempts with retries left.
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+/// A customer's subscription to a plan.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Subscription {
+    /// Your identifier for the subscription (1-80 characters, alphanumeric with -_).
+    pub id: String,
+    /// The customer billed.
+    pub customer: Customer,
+    /// The plan, as it was when the customer subscribed.
+    pub plan: Plan,
+    /// The state of the subscription.
+    pub status: SubscriptionStatus,
+    /// The next billing period, starting at 1.
+    pub period: u32,
+    /// When the first period was due. Period `n` is due `n - 1` intervals after it.
+    pub anchor_at: DateTime<Utc>,
+    /// When the next period is due.
+    pub next_charge_at: DateTime<Utc>,
+    /// The charge in progress, if any.
+    pub charge: Option<Charge>,
+}
+
+/// Builds the external ID of a charge attempt: `{subscription_id}-p{period}-a{attempt}`.
+///
+/// The same subscription, period and attempt always give the same ID, so a charge can be
+/// found on Fapshi after a crash and matched in reconciliation.
+pub fn charge_external_id(subscription_id: &str, period: u32, attempt: u32) -> String {
+    format!("{subscription_id}-p{period}-a{attempt}")
+}
+
+/// Something that happened to a subscription during [`SubscriptionScheduler::run_due`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ChargeEvent {
+    /// A direct payment was initiated.
+    Initiated {
+        /// The subscription charged.
+        subscription_id: String,
+        /// The external ID of the attempt.
+        external_id: String,
+        /// The Fapshi transaction ID.
+        transaction_id: String,
+    },
+    /// A fallback payment link was created.
+    LinkSent {
+        /// The subscription charged.
+        subscription_id: String,
+        /// The external ID of the attempt.
+        external_id: String,
+        /// The payment link to send to the customer.
+        payment_link: String,
+    },
+    /// The period was paid.
+    Paid {
+        /// The subscription charged.
+        subscription_id: String,
+        /// The billing period paid.
+        period: u32,
+        /// The Fapshi transaction ID.
+        transaction_id: String,
+    },
+    /// An attempt failed.
+    Failed {
+        /// The subscription charged.
+        subscription_id: String,
+        /// The external ID of the attempt.
+        external_id: String,
+        /// When the next attempt is made, if any.
+        retry_at: Option<DateTime<Utc>>,
+    },
+    /// The grace period ended without payment.
+    Lapsed {
+        /// The subscription that lapsed.
+        subscription_id: String,
+    },
+    /// A request failed in a way that leaves the charge unchanged; it is retried on the next
+    /// run.
+    Error {
+        /// The subscription being processed.
+        subscription_id: String,
+        /// The error message.
+        error: String,
+    },
+}
+
+/// Storage for subscriptions.
+///
+/// Implement this trait to keep subscriptions in your own database.
+pub trait SubscriptionStore: Send + Sync {
+    /// Returns the subscription with the given ID, if any.
+    fn get(&self, id: &str) -> Result<Option<Subscription>, FapshiError>;
+
+    /// Inserts or replaces a subscription.
+    fn put(&self, subscription: &Subscription) -> Result<(), FapshiError>;
+
+    /// Returns all subscriptions.
+    fn list(&self) -> Result<Vec<Subscription>, FapshiError>;
+}
+
+/// A `SubscriptionStore` kept in memory for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct InMemorySubscriptionStore {
+    entries: Mutex<HashMap<String, Subscription>>,
+}
+
+impl InMemorySubscriptionStore {
+    /// Creates an empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SubscriptionStore for InMemorySubscriptionStore {
+    fn get(&self, id: &str) -> Result<Option<Subscription>, FapshiError> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.get(id).cloned())
+    }
+
+    fn put(&self, subscription: &Subscription) -> Result<(), FapshiError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(subscription.id.clone(), subscription.clone());
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Subscription>, FapshiError> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.values().cloned().collect())
+    }
+}
+
+/// A `SubscriptionStore` persisted as a JSON file, surviving process restarts.
+///
+/// The subscriptions are kept in memory once read, and the file is rewritten atomically
+/// (write to a temporary file, then rename) on every `put`. Do not share the file between
+/// processes.
+#[derive(Debug)]
+pub struct FileSubscriptionStore {
+    file: JsonFile<HashMap<String, Subscription>>,
+}
+
+impl FileSubscriptionStore {
+    /// Creates a store backed by the file at `path`. The file is created on first write.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileSubscriptionStore {
+            file: JsonFile::new(path),
+        }
+    }
+}
+
+impl SubscriptionStore for FileSubscriptionStore {
+    fn get(&self, id: &str) -> Result<Option<Subscription>, FapshiError> {
+        self.file.read(|entries| entries.get(id).cloned())
+    }
+
+    fn put(&self, subscription: &Subscription) -> Result<(), FapshiError> {
+        self.file.update(|entries| {
+            entries.insert(subscription.id.clone(), subscription.clone());
+            true
+        })?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Subscription>, FapshiError> {
+        self.file
+            .read(|entries| entries.values().cloned().collect())
+    }
+}
+
+/// The next thing to do for a subscription.
+enum Step {
+    Idle,
+    /// Send attempt `attempt` of the current period.
+    Charge {
+        attempt: u32,
+        via_link: bool,
+    },
+    /// Look for a transaction sent before a crash.
+    Recover {
+        user_id: String,
+    },
+    /// Check the status of the pending transaction.
+    Poll {
+        transaction_id: String,
+    },
+    Lapse,
+}
+
+/// Bills subscriptions with direct payments, retrying failed charges.
+///
+/// Each call to [`SubscriptionScheduler::run_due`] takes at most one step per subscription:
+/// start a charge that is due, check a pending charge, retry a failed one, fall back to a
+/// payment link, or lapse the subscription once its grace period is over. Every attempt is
+/// recorded in the store before it is sent and carries a deterministic external ID, so an
+/// attempt interrupted by a crash is looked up on Fapshi instead of being sent twice.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use chrono::Utc;
+/// use fapshi_rs::{
+///     client::FapshiClient,
+///     safety::Environment,
+///     subscription::{
+///         BillingInterval, Customer, DunningPolicy, FileSubscriptionStore, Plan, SubscriptionScheduler,
+///     },
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// let scheduler = SubscriptionScheduler::new(
+///     FileSubscriptionStore::new("subscriptions.json"),
+///     DunningPolicy { fallback_to_link: true, ..Default::default() },
+/// );
+/// let plan = Plan {
+///     id: "premium".to_string(),
+///     name: "Premium monthly".to_string(),
+///     amount: 5000.0,
+///     interval: BillingInterval::Months(1),
+///     grace_period: Duration::from_secs(7 * 24 * 3600),
+/// };
+/// let customer = Customer {
+///     user_id: "customer42".to_string(),
+///     phone: "670000000".to_string(),
+///     medium: Some("mobile money".to_string()),
+///     name: Some("Jane".to_string()),
+///     email: None,
+/// };
+/// scheduler.subscribe("sub-customer42", customer, plan, Utc::now())?;
+/// // Run from your cron, e.g. every hour:
+/// for event in scheduler.run_due(&client, Utc::now())? {
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SubscriptionScheduler<S> {
+    store: S,
+    dunning: DunningPolicy,
+}
+
+impl<S: SubscriptionStore> SubscriptionScheduler<S> {
+    /// Creates a scheduler over `store`.
+    pub fn new(store: S, dunning: DunningPolicy) -> Self {
+        SubscriptionScheduler { store, dunning }
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Subscribes a customer to a plan, with the first charge due at `start_at`.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the ID is invalid or already used, or if the
+    /// customer's details would be rejected by Fapshi.
+    pub fn subscribe(
+        &self,
+        id: &str,
+        customer: Customer,
+        plan: Plan,
+        start_at: DateTime<Utc>,
+    ) -> Result<Subscription, FapshiError> {
+        if id.len() > 80 {
+            return Err(FapshiError::InvalidRequest(format!(
+                "subscription ID {id:?} must be at most 80 characters"
+            )));
+        }
+        if self.store.get(id)?.is_some() {
+            return Err(FapshiError::InvalidRequest(format!(
+                "subscription {id:?} already exists"
+            )));
+        }
+        let subscription = Subscription {
+            id: id.to_string(),
+            customer,
+            plan,
+            status: SubscriptionStatus::Active,
+            period: 1,
+            anchor_at: start_at,
+            next_charge_at: start_at,
+            charge: None,
+        };
+        direct_request(&subscription, &charge_external_id(id, 1, 1)).validate()?;
+        self.store.put(&subscription)?;
+        Ok(subscription)
+    }
+
+    /// Cancels a subscription. A charge already sent is not expired.
+    ///
+    /// # Errors
+    /// Returns `FapshiError::InvalidRequest` if the subscription does not exist.
+    pub fn cancel(&self, id: &str) -> Result<Subscription, FapshiError> {
+        let mut subscription = self.store.get(id)?.ok_or_else(|| {
+            FapshiError::InvalidRequest(format!("subscription {id:?} does not exist"))
+        })?;
+        subscription.status = SubscriptionStatus::Cancelled;
+        self.store.put(&subscription)?;
+        Ok(subscription)
+    }
+
+    /// Takes the next step of every active or past-due subscription, synchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    /// A `Result` containing what happened, or a `FapshiError` if the store failed. API
+    /// errors are reported as [`ChargeEvent::Error`] and retried on the next run.
+    #[cfg(not(feature = "async"))]
+    pub fn run_due(
+        &self,
+        client: &FapshiClient,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ChargeEvent>, FapshiError> {
+        let mut events = Vec::new();
+        for mut subscription in self.store.list()? {
+            let event = match self.next_step(&subscription, now) {
+                Step::Idle => None,
+                Step::Lapse => Some(lapse(&mut subscription)),
+                Step::Charge { attempt, via_link } => {
+                    let previous = subscription.charge.clone();
+                    self.begin_charge(&mut subscription, attempt, via_link)?;
+                    let sent = send(client, &subscription, &self.dunning);
+                    Some(self.record_sent(&mut subscription, previous, sent, now))
+                }
+                Step::Recover { user_id } => {
+                    match TransactionApi::get_transactions_by_user_id(client, &user_id) {
+                        Ok(transactions) => {
+                            match self.recover(&mut subscription, transactions, now) {
+                                Some(event) => Some(event),
+                                None => {
+                                    let previous = subscription.charge.clone();
+                                    let sent = send(client, &subscription, &self.dunning);
+                                    Some(self.record_sent(&mut subscription, previous, sent, now))
+                                }
+                            }
+                        }
+                        Err(err) => Some(error_event(&subscription, err)),
+                    }
+                }
+                Step::Poll { transaction_id } => {
+                    match TransactionApi::get_status(client, &transaction_id) {
+                        Ok(tx) => self.apply_status(&mut subscription, &tx, now),
+                        Err(err) => Some(error_event(&subscription, err)),
+                    }
+                }
+            };
+            if let Some(event) = event {
+                self.store.put(&subscription)?;
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Takes the next step of every active or past-due subscription, asynchronously.
+    ///
+    /// # Arguments
+    /// * `client` - The `FapshiClient` instance for making API requests.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    /// A `Result` containing what happened, or a `FapshiError` if the store failed. API
+    /// errors are reported as [`ChargeEvent::Error`] and retried on the next run.
+    #[cfg(feature = "async")]
+    pub async fn run_due(
+        &self,
+        client: &FapshiClient,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ChargeEvent>, FapshiError> {
+        let mut events = Vec::new();
+        for mut subscription in self.store.list()? {
+            let event = match self.next_step(&subscription, now) {
+                Step::Idle => None,
+                Step::Lapse => Some(lapse(&mut subscription)),
+                Step::Charge { attempt, via_link } => {
+                    let previous = subscription.charge.clone();
+                    self.begin_charge(&mut subscription, attempt, via_link)?;
+                    let sent = send(client, &subscription, &self.dunning).await;
+                    Some(self.record_sent(&mut subscription, previous, sent, now))
+                }
+                Step::Recover { user_id } => {
+                    match TransactionApi::get_transactions_by_user_id(client, &user_id).await {
+                        Ok(transactions) => {
+                            match self.recover(&mut subscription, transactions, now) {
+                                Some(event) => Some(event),
+                                None => {
+                                    let previous = subscription.charge.clone();
+                                    let sent = send(client, &subscription, &self.dunning).await;
+                                    Some(self.record_sent(&mut subscription, previous, sent, now))
+                                }
+                            }
+                        }
+                        Err(err) => Some(error_event(&subscription, err)),
+                    }
+                }
+                Step::Poll { transaction_id } => {
+                    match TransactionApi::get_status(client, &transaction_id).await {
+                        Ok(tx) => self.apply_status(&mut subscription, &tx, now),
+                        Err(err) => Some(error_event(&subscription, err)),
+                    }
+                }
+            };
+            if let Some(event) = event {
+                self.store.put(&subscription)?;
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    fn next_step(&self, subscription: &Subscription, now: DateTime<Utc>) -> Step {
+        if !matches!(
+            subscription.status,
+            SubscriptionStatus::Active | SubscriptionStatus::PastDue
+        ) {
+            return Step::Idle;
+        }
+        let Some(charge) = &subscription.charge else {
+            return if now >= subscription.next_charge_at {
+                Step::Charge {
+                    attempt: 1,
+                    via_link: false,
+                }
+            } else {
+                Step::Idle
+            };
+        };
+        match (charge.state, &charge.transaction_id) {
+            (ChargeState::Submitting, _) => Step::Recover {
+                user_id: subscription.customer.user_id.clone(),
+            },
+            (ChargeState::Pending, Some(transaction_id)) => Step::Poll {
+                transaction_id: transaction_id.clone(),
+            },
+            (ChargeState::Pending, None) => Step::Idle,
+            (ChargeState::Failed, _) => {
+                if now >= after(charge.due_at, subscription.plan.grace_period) {
+                    Step::Lapse
+                } else if charge.retry_at.is_some_and(|retry_at| now >= retry_at) {
+                    Step::Charge {
+                        attempt: charge.attempt + 1,
+                        via_link: false,
+                    }
+                } else if charge.retry_at.is_none()
+                    && self.dunning.fallback_to_link
+                    && !charge.via_link
+                {
+                    Step::Charge {
+                        attempt: charge.attempt + 1,
+                        via_link: true,
+                    }
+                } else {
+                    Step::Idle
+                }
+            }
+        }
+    }
+
+    /// Records the attempt as submitting before it is sent.
+    fn begin_charge(
+        &self,
+        subscription: &mut Subscription,
+        attempt: u32,
+        via_link: bool,
+    ) -> Result<(), FapshiError> {
+        let due_at = subscription
+            .charge
+            .as_ref()
+            .map_or(subscription.next_charge_at, |charge| charge.due_at);
+        subscription.charge = Some(Charge {
+            period: subscription.period,
+            attempt,
+            external_id: charge_external_id(&subscription.id, subscription.period, attempt),
+            due_at,
+            state: ChargeState::Submitting,
+            via_link,
+            transaction_id: None,
+            payment_link: None,
+            retry_at: None,
+        });
+        self.store.put(subscription)
+    }
+
+    /// Records the outcome of sending the current attempt. `previous` is the charge as it was
+    /// before the attempt began, restored when the attempt was never made.
+    fn record_sent(
+        &self,
+        subscription: &mut Subscription,
+        previous: Option<Charge>,
+        sent: Result<Sent, FapshiError>,
+        now: DateTime<Utc>,
+    ) -> ChargeEvent {
+        let sent = match sent {
+            Ok(sent) => sent,
+            Err(err) if is_declined(&err) => return self.fail(subscription, now),
+            // Nothing reached the customer (open circuit, refused credentials, dry run...);
+            // undo the attempt so the next run makes it again.
+            Err(err) if err.is_not_processed() => {
+                subscription.charge = previous;
+                return error_event(subscription, err);
+            }
+            // The request may have gone through; leave the attempt submitting so the next run
+            // looks it up instead of sending it again.
+            Err(err) => return error_event(subscription, err),
+        };
+        let subscription_id = subscription.id.clone();
+        let charge = subscription
+            .charge
+            .as_mut()
+            .expect("a sent charge is in progress");
+        charge.state = ChargeState::Pending;
+        match sent {
+            Sent::Direct(transaction_id) => {
+                charge.transaction_id = Some(transaction_id.clone());
+                ChargeEvent::Initiated {
+                    subscription_id,
+                    external_id: charge.external_id.clone(),
+                    transaction_id,
+                }
+            }
+            Sent::Link {
+                transaction_id,
+                payment_link,
+            } => {
+                charge.transaction_id = Some(transaction_id);
+                charge.payment_link = Some(payment_link.clone());
+                ChargeEvent::LinkSent {
+                    subscription_id,
+                    external_id: charge.external_id.clone(),
+                    payment_link,
+                }
+            }
+        }
+    }
+
+    /// Adopts a transaction sent before a crash, or returns `None` if it was never created.
+    fn recover(
+        &self,
+        subscription: &mut Subscription,
+        transactions: Vec<TransactionStatus>,
+        now: DateTime<Utc>,
+    ) -> Option<ChargeEvent> {
+        let charge = subscription.charge.as_mut()?;
+        let external_id = charge.external_id.clone();
+        let tx = transactions
+            .into_iter()
+            .find(|tx| tx.external_id.as_deref() == Some(external_id.as_str()))?;
+        charge.state = ChargeState::Pending;
+        charge.transaction_id = Some(tx.transaction_id.clone());
+        let event = self.apply_status(subscription, &tx, now);
+        Some(event.unwrap_or(ChargeEvent::Initiated {
+            subscription_id: subscription.id.clone(),
+            external_id,
+            transaction_id: tx.transaction_id,
+        }))
+    }
+
+    fn apply_status(
+        &self,
+        subscription: &mut Subscription,
+        tx: &TransactionStatus,
+        now: DateTime<Utc>,
+    ) -> Option<ChargeEvent> {
+        match tx.status {
+            Status::SUCCESSFUL => {
+                let period = subscription.period;
+                subscription.period += 1;
+                subscription.next_charge_at = subscription
+                    .plan
+                    .interval
+                    .nth_after(subscription.anchor_at, period);
+                subscription.status = SubscriptionStatus::Active;
+                subscription.charge = None;
+                Some(ChargeEvent::Paid {
+                    subscription_id: subscription.id.clone(),
+                    period,
+                    transaction_id: tx.transaction_id.clone(),
+                })
+            }
+            Status::FAILED | Status::EXPIRED => Some(self.fail(subscription, now)),
+            Status::CREATED | Status::PENDING => None,
+        }
+    }
+
+    /// Marks the current attempt as failed and schedules the next retry, if any.
+    fn fail(&self, subscription: &mut Subscription, now: DateTime<Utc>) -> ChargeEvent {
+        subscription.status = SubscriptionStatus::PastDue;
+        let charge = subscription
+            .charge
+            .as_mut()
+            .expect("a failed charge is in progress");
+        charge.state = ChargeState::Failed;
+        charge.retry_at = if charge.via_link {
+            None
+        } else {
+            self.dunning
+                .retry_delays
+                .get(charge.attempt as usize - 1)
+                .map(|delay| after(now, *delay))
+        };
+        ChargeEvent::Failed {
+            subscription_id: subscription.id.clone(),
+            external_id: charge.external_id.clone(),
+            retry_at: charge.retry_at,
+        }
+    }
+}
+
+/// What Fapshi returned for a charge attempt.
+enum Sent {
+    Direct(String),
+    Link {
+        transaction_id: String,
+        payment_link: String,
+    },
+}
+
+fn direct_request(subscription: &Subscription, external_id: &str) -> DirectPaymentRequest {
+    let customer = &subscription.customer;
+    DirectPaymentRequest {
+        amount: subscription.plan.amount as f32,
+        phone: customer.phone.clone(),
+        medium: customer.medium.clone(),
+        name: customer.name.clone(),
+        email: customer.email.clone(),
+        user_id: Some(customer.user_id.clone()),
+        external_id: Some(external_id.to_string()),
+        message: Some(subscription.plan.name.clone()),
+    }
+}
+
+fn link_request(
+    subscription: &Subscription,
+    external_id: &str,
+    dunning: &DunningPolicy,
+) -> PaymentRequest {
+    PaymentRequest {
+        amount: subscription.plan.amount,
+        email: subscription.customer.email.clone(),
+        redirect_url: dunning.redirect_url.clone(),
+        user_id: Some(subscription.customer.user_id.clone()),
+        external_id: Some(external_id.to_string()),
+        message: subscription.plan.name.clone(),
+        card_only: None,
+    }
+}
+
+#[cfg(not(feature = "async"))]
+fn send(
+    client: &FapshiClient,
+    subscription: &Subscription,
+    dunning: &DunningPolicy,
+) -> Result<Sent, FapshiError> {
+    let charge = subscription
+        .charge
+        .as_ref()
+        .ok_or_else(|| FapshiError::InvalidRequest("no charge in progress".to_string()))?;
+    if charge.via_link {
+        let request = link_request(subscription, &charge.external_id, dunning);
+        let response = PaymentApi::create_payment(client, &request)?;
+        Ok(Sent::Link {
+            transaction_id: response.transaction_id,
+            payment_link: response.payment_link,
+        })
+    } else {
+        let request = direct_request(subscription, &charge.external_id);
+        let response = PaymentApi::initiate_direct_payment(client, &request)?;
+        Ok(Sent::Direct(response.transaction_id))
+    }
+}
+
+#[cfg(feature = "async")]
+async fn send(
+    client: &FapshiClient,
+    subscription: &Subscription,
+    dunning: &DunningPolicy,
+) -> Result<Sent, FapshiError> {
+    let charge = subscription
+        .charge
+        .as_ref()
+        .ok_or_else(|| FapshiError::InvalidRequest("no charge in progress".to_string()))?;
+    if charge.via_link {
+        let request = link_request(subscription, &charge.external_id, dunning);
+        let response = PaymentApi::create_payment(client, &request).await?;
+        Ok(Sent::Link {
+            transaction_id: response.transaction_id,
+            payment_link: response.payment_link,
+        })
+    } else {
+        let request = direct_request(subscription, &charge.external_id);
+        let response = PaymentApi::initiate_direct_payment(client, &request).await?;
+        Ok(Sent::Direct(response.transaction_id))
+    }
+}
+
+/// Whether Fapshi refused the charge itself, e.g. an invalid phone number, as opposed to the
+/// request being refused for reasons unrelated to the customer (credentials, rate limiting).
+fn is_declined(err: &FapshiError) -> bool {
+    match err {
+        FapshiError::StatusError { status, .. } => {
+            (400..500).contains(status) && !matches!(status, 401 | 403 | 404 | 408 | 429)
+        }
+        _ => false,
+    }
+}
+
+fn lapse(subscription: &mut Subscription) -> ChargeEvent {
+    subscription.status = SubscriptionStatus::Lapsed;
+    ChargeEvent::Lapsed {
+        subscription_id: subscription.id.clone(),
+    }
+}
+
+fn error_event(subscription: &Subscription, err: FapshiError) -> ChargeEvent {
+    ChargeEvent::Error {
+        subscription_id: subscription.id.clone(),
+        error: err.to_string(),
+    }
+}
+
+fn after(at: DateTime<Utc>, delay: Duration) -> DateTime<Utc> {
+    TimeDelta::from_std(delay)
+        .ok()
+        .and_then(|delay| at.checked_add_signed(delay))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use chrono::TimeZone;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse},
+        safety::Environment,
+    };
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap()
+    }
+
+    fn plan() -> Plan {
+        Plan {
+            id: "premium".to_string(),
+            name: "Premium monthly".to_string(),
+            amount: 5000.0,
+            interval: BillingInterval::Months(1),
+            grace_period: 10 * DAY,
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            user_id: "customer42".to_string(),
+            phone: "670000000".to_string(),
+            medium: Some("mobile money".to_string()),
+            name: None,
+            email: Some("jane@example.com".to_string()),
+        }
+    }
+
+    fn interaction(
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        status: u16,
+        response: Value,
+    ) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: method.to_string(),
+                path: path.to_string(),
+                headers: BTreeMap::new(),
+                body,
+            },
+            response: RecordedResponse {
+                status,
+                headers: BTreeMap::new(),
+                body: response.to_string(),
+            },
+        }
+    }
+
+    fn direct_pay(
+        subscription: &Subscription,
+        attempt: u32,
+        status: u16,
+        response: Value,
+    ) -> Interaction {
+        let external_id = charge_external_id(&subscription.id, subscription.period, attempt);
+        let body = serde_json::to_value(direct_request(subscription, &external_id)).unwrap();
+        interaction("POST", "direct-pay", Some(body), status, response)
+    }
+
+    fn accepted(transaction_id: &str) -> Value {
+        json!({"transId": transaction_id, "message": "Accepted", "dateInitiated": "2025-01-31"})
+    }
+
+    fn transaction(transaction_id: &str, status: &str, external_id: &str) -> Value {
+        json!({
+            "transId": transaction_id,
+            "status": status,
+            "serviceName": "Shop",
+            "amount": 5000.0,
+            "email": "",
+            "redirectUrl": "",
+            "externalId": external_id,
+            "dateInitiated": "2025-01-31",
+            "dateConfirmed": "",
+        })
+    }
+
+    fn payment_status(transaction_id: &str, status: &str, external_id: &str) -> Interaction {
+        interaction(
+            "GET",
+            &format!("payment-status/{transaction_id}"),
+            None,
+            200,
+            transaction(transaction_id, status, external_id),
+        )
+    }
+
+    fn client(interactions: Vec<Interaction>) -> FapshiClient {
+        FapshiClient::with_environment("user", "key", Environment::Sandbox)
+            .unwrap()
+            .with_cassette(Cassette::from_interactions(interactions))
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn run(
+        scheduler: &SubscriptionScheduler<InMemorySubscriptionStore>,
+        client: &FapshiClient,
+        now: DateTime<Utc>,
+    ) -> Vec<ChargeEvent> {
+        scheduler.run_due(client, now).unwrap()
+    }
+
+    #[cfg(feature = "async")]
+    fn run(
+        scheduler: &SubscriptionScheduler<InMemorySubscriptionStore>,
+        client: &FapshiClient,
+        now: DateTime<Utc>,
+    ) -> Vec<ChargeEvent> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(scheduler.run_due(client, now))
+            .unwrap()
+    }
+
+    fn stored(scheduler: &SubscriptionScheduler<InMemorySubscriptionStore>) -> Subscription {
+        scheduler.store().get("sub-1").unwrap().unwrap()
+    }
+
+    #[test]
+    fn monthly_schedule_keeps_the_anchor_day() {
+        let interval = BillingInterval::Months(1);
+        let anchor = at(2025, 1, 31);
+        assert_eq!(interval.nth_after(anchor, 1), at(2025, 2, 28));
+        assert_eq!(interval.nth_after(anchor, 2), at(2025, 3, 31));
+        assert_eq!(interval.nth_after(anchor, 3), at(2025, 4, 30));
+        assert_eq!(interval.nth_after(anchor, 13), at(2026, 2, 28));
+        assert_eq!(interval.nth_after(at(2024, 1, 31), 1), at(2024, 2, 29));
+        // Chaining from the previous date drifts, which is why schedules use the anchor.
+        assert_eq!(interval.next_after(at(2025, 2, 28)), at(2025, 3, 28));
+    }
+
+    #[test]
+    fn day_and_week_intervals_add_fixed_durations() {
+        let anchor = at(2025, 1, 31);
+        assert_eq!(
+            BillingInterval::Days(10).nth_after(anchor, 2),
+            at(2025, 2, 20)
+        );
+        assert_eq!(
+            BillingInterval::Weeks(2).next_after(anchor),
+            at(2025, 2, 14)
+        );
+        assert_eq!(
+            BillingInterval::Months(u32::MAX).nth_after(anchor, 2),
+            DateTime::<Utc>::MAX_UTC
+        );
+    }
+
+    #[test]
+    fn failed_charges_are_retried_then_fall_back_to_a_link() {
+        let scheduler = SubscriptionScheduler::new(
+            InMemorySubscriptionStore::new(),
+            DunningPolicy {
+                retry_delays: vec![DAY],
+                fallback_to_link: true,
+                redirect_url: None,
+            },
+        );
+        let start = at(2025, 1, 31);
+        let subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), start)
+            .unwrap();
+        let link = serde_json::to_value(link_request(
+            &subscription,
+            "sub-1-p1-a3",
+            &scheduler.dunning,
+        ))
+        .unwrap();
+        let client = client(vec![
+            direct_pay(&subscription, 1, 200, accepted("T1")),
+            payment_status("T1", "FAILED", "sub-1-p1-a1"),
+            direct_pay(&subscription, 2, 400, json!({"message": "Invalid phone"})),
+            interaction(
+                "POST",
+                "initiate-pay",
+                Some(link),
+                200,
+                json!({
+                    "message": "Request successful",
+                    "link": "https://checkout.fapshi.com/link/T3",
+                    "transId": "T3",
+                    "dateInitiated": "2025-02-01",
+                }),
+            ),
+            payment_status("T3", "SUCCESSFUL", "sub-1-p1-a3"),
+        ]);
+
+        assert!(run(&scheduler, &client, start - TimeDelta::hours(1)).is_empty());
+        assert!(matches!(
+            run(&scheduler, &client, start).as_slice(),
+            [ChargeEvent::Initiated { transaction_id, .. }] if transaction_id == "T1"
+        ));
+
+        let retry_at = start + TimeDelta::days(1);
+        assert_eq!(
+            run(&scheduler, &client, start),
+            [ChargeEvent::Failed {
+                subscription_id: "sub-1".to_string(),
+                external_id: "sub-1-p1-a1".to_string(),
+                retry_at: Some(retry_at),
+            }]
+        );
+        assert_eq!(stored(&scheduler).status, SubscriptionStatus::PastDue);
+        assert!(run(&scheduler, &client, retry_at - TimeDelta::hours(1)).is_empty());
+
+        // The second attempt is declined and is the last retry.
+        assert_eq!(
+            run(&scheduler, &client, retry_at),
+            [ChargeEvent::Failed {
+                subscription_id: "sub-1".to_string(),
+                external_id: "sub-1-p1-a2".to_string(),
+                retry_at: None,
+            }]
+        );
+        assert!(matches!(
+            run(&scheduler, &client, retry_at).as_slice(),
+            [ChargeEvent::LinkSent { external_id, .. }] if external_id == "sub-1-p1-a3"
+        ));
+        assert_eq!(
+            run(&scheduler, &client, retry_at),
+            [ChargeEvent::Paid {
+                subscription_id: "sub-1".to_string(),
+                period: 1,
+                transaction_id: "T3".to_string(),
+            }]
+        );
+        assert!(client.cassette().unwrap().unplayed().is_empty());
+
+        let paid = stored(&scheduler);
+        assert_eq!(paid.status, SubscriptionStatus::Active);
+        assert_eq!(paid.period, 2);
+        assert_eq!(paid.next_charge_at, at(2025, 2, 28));
+        assert_eq!(paid.charge, None);
+    }
+
+    #[test]
+    fn unpaid_subscription_lapses_after_the_grace_period() {
+        let scheduler = SubscriptionScheduler::new(
+            InMemorySubscriptionStore::new(),
+            DunningPolicy {
+                retry_delays: Vec::new(),
+                fallback_to_link: false,
+                redirect_url: None,
+            },
+        );
+        let start = at(2025, 1, 31);
+        let subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), start)
+            .unwrap();
+        let client = client(vec![direct_pay(
+            &subscription,
+            1,
+            400,
+            json!({"message": "Invalid phone"}),
+        )]);
+
+        assert!(matches!(
+            run(&scheduler, &client, start).as_slice(),
+            [ChargeEvent::Failed { retry_at: None, .. }]
+        ));
+        assert!(run(&scheduler, &client, start + TimeDelta::days(9)).is_empty());
+        assert_eq!(
+            run(&scheduler, &client, start + TimeDelta::days(10)),
+            [ChargeEvent::Lapsed {
+                subscription_id: "sub-1".to_string(),
+            }]
+        );
+        assert_eq!(stored(&scheduler).status, SubscriptionStatus::Lapsed);
+        assert!(run(&scheduler, &client, start + TimeDelta::days(40)).is_empty());
+    }
+
+    #[test]
+    fn unprocessed_request_is_not_counted_as_a_failed_attempt() {
+        let scheduler =
+            SubscriptionScheduler::new(InMemorySubscriptionStore::new(), DunningPolicy::default());
+        let start = at(2025, 1, 31);
+        let subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), start)
+            .unwrap();
+        let client = client(vec![
+            direct_pay(&subscription, 1, 401, json!({"message": "Unauthorized"})),
+            direct_pay(&subscription, 1, 200, accepted("T1")),
+        ]);
+
+        assert!(matches!(
+            run(&scheduler, &client, start).as_slice(),
+            [ChargeEvent::Error { .. }]
+        ));
+        let unchanged = stored(&scheduler);
+        assert_eq!(unchanged.status, SubscriptionStatus::Active);
+        assert_eq!(unchanged.charge, None);
+        assert!(matches!(
+            run(&scheduler, &client, start).as_slice(),
+            [ChargeEvent::Initiated { external_id, .. }] if external_id == "sub-1-p1-a1"
+        ));
+    }
+
+    #[test]
+    fn interrupted_attempt_is_recovered_instead_of_resent() {
+        let scheduler =
+            SubscriptionScheduler::new(InMemorySubscriptionStore::new(), DunningPolicy::default());
+        let start = at(2025, 1, 31);
+        let mut subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), start)
+            .unwrap();
+        // A crash after the attempt was recorded but before its outcome was.
+        scheduler.begin_charge(&mut subscription, 1, false).unwrap();
+        let client = client(vec![interaction(
+            "GET",
+            "transaction/customer42",
+            None,
+            200,
+            json!([
+                transaction("T0", "SUCCESSFUL", "other-order"),
+                transaction("T1", "PENDING", "sub-1-p1-a1"),
+            ]),
+        )]);
+
+        assert_eq!(
+            run(&scheduler, &client, start),
+            [ChargeEvent::Initiated {
+                subscription_id: "sub-1".to_string(),
+                external_id: "sub-1-p1-a1".to_string(),
+                transaction_id: "T1".to_string(),
+            }]
+        );
+        let charge = stored(&scheduler).charge.unwrap();
+        assert_eq!(charge.state, ChargeState::Pending);
+        assert_eq!(charge.transaction_id.as_deref(), Some("T1"));
+    }
+
+    #[test]
+    fn charge_external_ids_are_deterministic() {
+        assert_eq!(charge_external_id("sub-1", 3, 2), "sub-1-p3-a2");
+    }
+
+    #[test]
+    fn subscribe_validates_and_cancel_stops_billing() {
+        let scheduler =
+            SubscriptionScheduler::new(InMemorySubscriptionStore::new(), DunningPolicy::default());
+        let subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), at(2025, 1, 31))
+            .unwrap();
+        assert_eq!(subscription.status, SubscriptionStatus::Active);
+        assert_eq!(subscription.period, 1);
+        assert_eq!(subscription.next_charge_at, at(2025, 1, 31));
+
+        let duplicate = scheduler.subscribe("sub-1", customer(), plan(), at(2025, 1, 31));
+        assert!(matches!(duplicate, Err(FapshiError::InvalidRequest(_))));
+        let bad_phone = Customer {
+            phone: "12".to_string(),
+            ..customer()
+        };
+        let invalid = scheduler.subscribe("sub-2", bad_phone, plan(), at(2025, 1, 31));
+        assert!(matches!(invalid, Err(FapshiError::InvalidRequest(_))));
+        assert_eq!(scheduler.store().list().unwrap().len(), 1);
+
+        let cancelled = scheduler.cancel("sub-1").unwrap();
+        assert_eq!(cancelled.status, SubscriptionStatus::Cancelled);
+        assert_eq!(scheduler.store().get("sub-1").unwrap(), Some(cancelled));
+        assert!(scheduler.cancel("missing").is_err());
+    }
+
+    #[test]
+    fn file_store_survives_reopening() {
+        let path = std::env::temp_dir().join(format!(
+            "fapshi-rs-subscriptions-{}-reopen.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let scheduler =
+            SubscriptionScheduler::new(FileSubscriptionStore::new(&path), DunningPolicy::default());
+        let subscription = scheduler
+            .subscribe("sub-1", customer(), plan(), at(2025, 1, 31))
+            .unwrap();
+
+        let reopened = FileSubscriptionStore::new(&path);
+        assert_eq!(reopened.get("sub-1").unwrap(), Some(subscription));
+        assert_eq!(reopened.list().unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+}
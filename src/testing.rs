@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::{
+    api::transaction::TransactionApi,
+    cassette::{Cassette, CassetteMode, Interaction, RecordedRequest, RecordedResponse},
+    client::FapshiClient,
+    error::FapshiError,
+    models::{DirectPaymentRequest, Status, TransactionStatus},
+    safety::Environment,
+};
+
+/// Sandbox MTN Mobile Money numbers whose direct payments succeed.
+pub const SANDBOX_MTN_SUCCESS: &[&str] = &["670000000", "670000002", "650000000"];
+
+/// Sandbox MTN Mobile Money numbers whose direct payments fail.
+pub const SANDBOX_MTN_FAILURE: &[&str] = &["670000001", "670000003", "650000001"];
+
+/// Sandbox Orange Money numbers whose direct payments succeed.
+pub const SANDBOX_ORANGE_SUCCESS: &[&str] = &["690000000", "690000002", "656000000"];
+
+/// Sandbox Orange Money numbers whose direct payments fail.
+pub const SANDBOX_ORANGE_FAILURE: &[&str] = &["690000001", "690000003", "656000001"];
+
+/// Phone number put in requests for scenarios the sandbox cannot trigger. It is only ever
+/// answered by the local mock.
+const MOCK_PHONE: &str = "677777777";
+
+/// A direct payment outcome to reproduce in tests.
+///
+/// `Approve` and `Decline` map to the sandbox's magic phone numbers. The sandbox has no way to
+/// trigger the other outcomes, so they are served by a local mock: a client replaying an
+/// in-memory [`Cassette`] built by [`Scenario::mock_interactions`].
+///
+/// # Example
+/// ```no_run
+/// use fapshi_rs::{
+///     api::payment::PaymentApi,
+///     client::FapshiClient,
+///     models::Status,
+///     safety::Environment,
+///     testing::{assert_transaction_reaches, Scenario},
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let sandbox =
+///     FapshiClient::with_environment("your_api_user", "your_api_key", Environment::Sandbox)?;
+/// for scenario in Scenario::ALL {
+///     let request = scenario.direct_payment(500.0, "order-42");
+///     let client = scenario.client(&sandbox, &request);
+///     match PaymentApi::initiate_direct_payment(&client, &request) {
+///         Ok(response) => {
+///             let expected = scenario.expected_status().unwrap();
+///             assert_transaction_reaches(&client, &response.transaction_id, expected);
+///         }
+///         Err(err) => assert_eq!(scenario.expected_status(), None, "{err}"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// The payer approves the payment: the transaction becomes SUCCESSFUL.
+    Approve,
+    /// The payer declines the payment: the transaction becomes FAILED.
+    Decline,
+    /// The payer never answers the prompt: the transaction stays PENDING, then EXPIRED.
+    Timeout,
+    /// The payer's wallet cannot cover the amount: the transaction becomes FAILED.
+    InsufficientFunds,
+    /// Fapshi refuses the phone number: the request fails with a 400 status.
+    InvalidNumber,
+}
+
+impl Scenario {
+    /// All scenarios, for table-driven tests.
+    pub const ALL: [Scenario; 5] = [
+        Scenario::Approve,
+        Scenario::Decline,
+        Scenario::Timeout,
+        Scenario::InsufficientFunds,
+        Scenario::InvalidNumber,
+    ];
+
+    /// Returns the sandbox phone number triggering this scenario, if there is one.
+    pub fn sandbox_phone(self) -> Option<&'static str> {
+        match self {
+            Scenario::Approve => Some(SANDBOX_MTN_SUCCESS[0]),
+            Scenario::Decline => Some(SANDBOX_MTN_FAILURE[0]),
+            Scenario::Timeout | Scenario::InsufficientFunds | Scenario::InvalidNumber => None,
+        }
+    }
+
+    /// Returns whether the scenario runs against the sandbox rather than the local mock.
+    pub fn uses_sandbox(self) -> bool {
+        self.sandbox_phone().is_some()
+    }
+
+    /// Returns the status the transaction ends in, or `None` if the request itself fails.
+    pub fn expected_status(self) -> Option<Status> {
+        match self {
+            Scenario::Approve => Some(Status::SUCCESSFUL),
+            Scenario::Decline | Scenario::InsufficientFunds => Some(Status::FAILED),
+            Scenario::Timeout => Some(Status::EXPIRED),
+            Scenario::InvalidNumber => None,
+        }
+    }
+
+    /// Builds a direct payment request for this scenario.
+    pub fn direct_payment(self, amount: f32, external_id: &str) -> DirectPaymentRequest {
+        DirectPaymentRequest {
+            amount,
+            phone: self.sandbox_phone().unwrap_or(MOCK_PHONE).to_string(),
+            medium: Some("mobile money".to_string()),
+            name: None,
+            email: None,
+            user_id: None,
+            external_id: Some(external_id.to_string()),
+            message: Some(format!("Test scenario {self:?}")),
+        }
+    }
+
+    /// Returns the client to run the scenario with: a clone of `sandbox` if the sandbox can
+    /// trigger it, otherwise [`Scenario::mock_client`].
+    pub fn client(self, sandbox: &FapshiClient, request: &DirectPaymentRequest) -> FapshiClient {
+        if self.uses_sandbox() {
+            sandbox.clone()
+        } else {
+            self.mock_client(request)
+        }
+    }
+
+    /// Returns a client that answers `request` and the status polls that follow from a local
+    /// mock, without network access.
+    pub fn mock_client(self, request: &DirectPaymentRequest) -> FapshiClient {
+        FapshiClient::with_environment("mock", "mock", Environment::Sandbox)
+            .expect("static mock credentials are valid header values")
+            .with_cassette(Cassette::from_interactions(self.mock_interactions(request)))
+    }
+
+    /// Builds the exchanges Fapshi would have for `request` in this scenario: the direct
+    /// payment, then a PENDING status and the final status.
+    pub fn mock_interactions(self, request: &DirectPaymentRequest) -> Vec<Interaction> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let transaction_id = format!("mock{:?}", self).to_lowercase();
+        let direct_pay = RecordedRequest {
+            method: "POST".to_string(),
+            path: "direct-pay".to_string(),
+            headers: BTreeMap::new(),
+            body: serde_json::to_value(request).ok(),
+        };
+        let Some(final_status) = self.expected_status() else {
+            return vec![mock_interaction(
+                direct_pay,
+                400,
+                json!({ "message": "Invalid phone number" }),
+            )];
+        };
+        let status_request = || RecordedRequest {
+            method: "GET".to_string(),
+            path: format!("payment-status/{transaction_id}"),
+            headers: BTreeMap::new(),
+            body: None,
+        };
+        let transaction = |status: Status| TransactionStatus {
+            transaction_id: transaction_id.clone(),
+            medium: request.medium.clone(),
+            service_name: "Mock service".to_string(),
+            amount: request.amount as f64,
+            revenue: None,
+            payer_name: request.name.clone(),
+            email: request.email.clone().unwrap_or_default(),
+            redirect_url: String::new(),
+            external_id: request.external_id.clone(),
+            user_id: request.user_id.clone(),
+            webhook: None,
+            financial_transaction_id: (status == Status::SUCCESSFUL)
+                .then(|| format!("fin-{transaction_id}")),
+            date_initiated: now.clone(),
+            date_confirmed: if status == Status::SUCCESSFUL {
+                now.clone()
+            } else {
+                String::new()
+            },
+            status,
+        };
+        vec![
+            mock_interaction(
+                direct_pay,
+                200,
+                json!({
+                    "transId": transaction_id,
+                    "message": "Request successfully sent",
+                    "dateInitiated": now,
+                }),
+            ),
+            mock_interaction(
+                status_request(),
+                200,
+                serde_json::to_value(transaction(Status::PENDING)).unwrap_or_default(),
+            ),
+            mock_interaction(
+                status_request(),
+                200,
+                serde_json::to_value(transaction(final_status)).unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+fn mock_interaction(request: RecordedRequest, status: u16, body: Value) -> Interaction {
+    Interaction {
+        request,
+        response: RecordedResponse {
+            status,
+            headers: BTreeMap::new(),
+            body: body.to_string(),
+        },
+    }
+}
+
+/// How long and how often to poll a transaction's status.
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    /// Give up after this long.
+    pub timeout: Duration,
+    /// Wait this long between polls. Ignored when the client replays a cassette.
+    pub interval: Duration,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        PollOptions {
+            timeout: Duration::from_secs(120),
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Polls a transaction until its status is `expected` or final, synchronously.
+///
+/// # Arguments
+/// * `client` - The `FapshiClient` instance for making API requests.
+/// * `transaction_id` - The transaction to poll.
+/// * `expected` - The status to wait for.
+/// * `options` - The timeout and polling interval.
+///
+/// # Returns
+/// The last status read, which is not `expected` if the transaction ended in another final
+/// status or the timeout elapsed, or the first `FapshiError` met while polling.
+#[cfg(not(feature = "async"))]
+pub fn wait_for_status(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: &Status,
+    options: &PollOptions,
+) -> Result<TransactionStatus, FapshiError> {
+    let started = Instant::now();
+    loop {
+        let transaction = TransactionApi::get_status(client, transaction_id)?;
+        if done_polling(&transaction, expected, started, options) {
+            return Ok(transaction);
+        }
+        std::thread::sleep(poll_interval(client, options));
+    }
+}
+
+/// Polls a transaction until its status is `expected` or final, asynchronously.
+///
+/// # Arguments
+/// * `client` - The `FapshiClient` instance for making API requests.
+/// * `transaction_id` - The transaction to poll.
+/// * `expected` - The status to wait for.
+/// * `options` - The timeout and polling interval.
+///
+/// # Returns
+/// The last status read, which is not `expected` if the transaction ended in another final
+/// status or the timeout elapsed, or the first `FapshiError` met while polling.
+#[cfg(feature = "async")]
+pub async fn wait_for_status(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: &Status,
+    options: &PollOptions,
+) -> Result<TransactionStatus, FapshiError> {
+    let started = Instant::now();
+    loop {
+        let transaction = TransactionApi::get_status(client, transaction_id).await?;
+        if done_polling(&transaction, expected, started, options) {
+            return Ok(transaction);
+        }
+        tokio::time::sleep(poll_interval(client, options)).await;
+    }
+}
+
+/// Asserts that a transaction reaches `expected` within the default [`PollOptions`].
+///
+/// # Panics
+/// Panics if polling fails, or if the transaction ends in another status or is still open
+/// when the timeout elapses.
+#[cfg(not(feature = "async"))]
+pub fn assert_transaction_reaches(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: Status,
+) -> TransactionStatus {
+    assert_transaction_reaches_within(client, transaction_id, expected, &PollOptions::default())
+}
+
+/// Asserts that a transaction reaches `expected` within the default [`PollOptions`].
+///
+/// # Panics
+/// Panics if polling fails, or if the transaction ends in another status or is still open
+/// when the timeout elapses.
+#[cfg(feature = "async")]
+pub async fn assert_transaction_reaches(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: Status,
+) -> TransactionStatus {
+    assert_transaction_reaches_within(client, transaction_id, expected, &PollOptions::default())
+        .await
+}
+
+/// Asserts that a transaction reaches `expected` within `options.timeout`.
+///
+/// # Panics
+/// Panics if polling fails, or if the transaction ends in another status or is still open
+/// when the timeout elapses.
+#[cfg(not(feature = "async"))]
+pub fn assert_transaction_reaches_within(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: Status,
+    options: &PollOptions,
+) -> TransactionStatus {
+    let result = wait_for_status(client, transaction_id, &expected, options);
+    check_reached(result, transaction_id, expected)
+}
+
+/// Asserts that a transaction reaches `expected` within `options.timeout`.
+///
+/// # Panics
+/// Panics if polling fails, or if the transaction ends in another status or is still open
+/// when the timeout elapses.
+#[cfg(feature = "async")]
+pub async fn assert_transaction_reaches_within(
+    client: &FapshiClient,
+    transaction_id: &str,
+    expected: Status,
+    options: &PollOptions,
+) -> TransactionStatus {
+    let result = wait_for_status(client, transaction_id, &expected, options).await;
+    check_reached(result, transaction_id, expected)
+}
+
+fn done_polling(
+    transaction: &TransactionStatus,
+    expected: &Status,
+    started: Instant,
+    options: &PollOptions,
+) -> bool {
+    transaction.status == *expected
+        || !matches!(transaction.status, Status::CREATED | Status::PENDING)
+        || started.elapsed() >= options.timeout
+}
+
+fn poll_interval(client: &FapshiClient, options: &PollOptions) -> Duration {
+    match client.cassette() {
+        Some(cassette) if cassette.mode() == CassetteMode::Replay => Duration::ZERO,
+        _ => options.interval,
+    }
+}
+
+fn check_reached(
+    result: Result<TransactionStatus, FapshiError>,
+    transaction_id: &str,
+    expected: Status,
+) -> TransactionStatus {
+    match result {
+        Ok(transaction) if transaction.status == expected => transaction,
+        Ok(transaction) => panic!(
+            "transaction {transaction_id} is {} instead of {expected}",
+            transaction.status
+        ),
+        Err(err) => panic!("polling transaction {transaction_id} failed: {err}"),
+    }
+}